@@ -0,0 +1,86 @@
+use std::error::Error;
+
+use libglacierdisk::snapshot::DiskSnapshot;
+
+use crate::{
+  disk,
+  table::{cell, Table},
+};
+
+pub fn run(disk: &str, json: bool) -> Result<(), Box<dyn Error>> {
+  let mut disk = disk::open(disk)?;
+  let snapshot = DiskSnapshot::capture(&mut disk);
+
+  // NVMe drives have a fixed health log instead of attributes
+  if let Some(log) = &snapshot.nvme {
+    if json {
+      println!("{}", serde_json::to_string_pretty(log)?);
+      return Ok(());
+    }
+
+    let mut table = Table::new(&["FIELD", "VALUE"]);
+    let fields = [
+      ("Critical warning", format!("{:#04x}", log.critical_warning)),
+      ("Temperature", format!("{:.0}°C", log.temperature_celsius())),
+      ("Available spare", format!("{}%", log.available_spare)),
+      (
+        "Available spare threshold",
+        format!("{}%", log.available_spare_threshold),
+      ),
+      ("Percentage used", format!("{}%", log.percentage_used)),
+      ("Data units read", log.data_units_read.to_string()),
+      ("Data units written", log.data_units_written.to_string()),
+      ("Host read commands", log.host_read_commands.to_string()),
+      ("Host write commands", log.host_write_commands.to_string()),
+      (
+        "Controller busy time",
+        format!("{} minutes", log.controller_busy_time),
+      ),
+      ("Power cycles", log.power_cycles.to_string()),
+      ("Power on hours", log.power_on_hours.to_string()),
+      ("Unsafe shutdowns", log.unsafe_shutdowns.to_string()),
+      ("Media errors", log.media_errors.to_string()),
+      ("Error log entries", log.error_log_entries.to_string()),
+    ];
+
+    for (field, value) in fields {
+      table.row(vec![field.to_string(), value]);
+    }
+
+    print!("{table}");
+    return Ok(());
+  }
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&snapshot.attributes)?);
+    return Ok(());
+  }
+
+  if snapshot.attributes.is_empty() {
+    return Err(format!("{} has no SMART attributes", snapshot.path.display()).into());
+  }
+
+  let mut table = Table::new(&["ID", "NAME", "VALUE", "WORST", "THRESH", "RAW", "DECODED"]);
+
+  for attribute in &snapshot.attributes {
+    let decoded = match (attribute.decoded, &attribute.unit) {
+      (Some(value), Some(unit)) if unit != "none" => Some(format!("{value} {unit}")),
+      (Some(value), _) => Some(value.to_string()),
+      _ => None,
+    };
+
+    table.row(vec![
+      attribute.id.to_string(),
+      attribute.name.clone(),
+      attribute.current.to_string(),
+      attribute.worst.to_string(),
+      attribute.threshold.to_string(),
+      attribute.raw.to_string(),
+      cell(decoded),
+    ]);
+  }
+
+  print!("{table}");
+
+  Ok(())
+}
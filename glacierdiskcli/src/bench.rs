@@ -0,0 +1,181 @@
+use std::error::Error;
+
+use clap::{Args, ValueEnum};
+use libglacierdisk::{
+  benchmark::{Benchmark, BenchmarkConfig, BenchmarkType, Engine, GlacierDiskBenchmark},
+  disk::ShallowDisk,
+};
+use serde::Serialize;
+
+use crate::{disk, table::Table};
+
+const FILENAME: &str = "glacierdisk-bench.bin";
+const MB: f64 = 1024. * 1024.;
+
+/// The same suites as GlacierDiskMark
+#[derive(Clone, Copy, PartialEq, Debug, ValueEnum)]
+pub enum Suite {
+  All,
+  Seq1mQ8t1,
+  Seq1mQ1t1,
+  Rnd4kQ32t1,
+  Rnd4kQ1t1,
+}
+
+/// How reads and writes are issued
+#[derive(Clone, Copy, PartialEq, Debug, ValueEnum)]
+pub enum EngineArg {
+  Sync,
+  IoUring,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+  /// Disk to benchmark. It needs to have a mounted filesystem to write the test file to.
+  disk: String,
+  /// Which tests to run
+  #[arg(long, value_enum, default_value_t = Suite::All)]
+  suite: Suite,
+  /// Size of the test file in MiB
+  #[arg(long, default_value_t = 1024)]
+  size: usize,
+  /// Which of the disk's mounts to use, if it has more than one
+  #[arg(long, default_value_t = 0)]
+  mount: usize,
+  /// Go through the page cache instead of using O_DIRECT. Reads will mostly measure RAM speed.
+  #[arg(long)]
+  buffered: bool,
+  /// Falls back to sync if io_uring isn't available
+  #[arg(long, value_enum, default_value_t = EngineArg::IoUring)]
+  engine: EngineArg,
+}
+
+/// The result of one test
+#[derive(Serialize)]
+struct TestResult {
+  test: &'static str,
+  kind: &'static str,
+  block_size: usize,
+  block_count: usize,
+  random: bool,
+  queue_depth: usize,
+  threads: usize,
+  elapsed_secs: f64,
+  bytes_per_second: f64,
+  iops: f64,
+  /// "direct", "uncached" or "buffered"
+  mode: String,
+  /// "io_uring" or "sync"
+  engine: String,
+}
+
+pub fn run(args: &BenchArgs, json: bool) -> Result<(), Box<dyn Error>> {
+  let disk = ShallowDisk::new(disk::resolve(&args.disk)?)?;
+  let mounts = disk.mounts()?;
+  let mount = mounts
+    .get(args.mount)
+    .ok_or(format!("{disk} has no mount {}", args.mount))?;
+  let file = mount.mount_point.join(FILENAME);
+  let size = args.size * 1024 * 1024;
+
+  let tests = [
+    (Suite::Seq1mQ8t1, "SEQ1M Q8T1", 1024 * 1024, false, 8),
+    (Suite::Seq1mQ1t1, "SEQ1M Q1T1", 1024 * 1024, false, 1),
+    (Suite::Rnd4kQ32t1, "RND4K Q32T1", 4 * 1024, true, 32),
+    (Suite::Rnd4kQ1t1, "RND4K Q1T1", 4 * 1024, true, 1),
+  ];
+
+  let mut results = vec![];
+
+  for (suite, name, block_size, random, queue_depth) in tests {
+    if args.suite != Suite::All && args.suite != suite {
+      continue;
+    }
+
+    let config = BenchmarkConfig {
+      block_size,
+      block_count: size / block_size,
+      file_path: Some(file.clone()),
+      random,
+      direct: !args.buffered,
+      queue_depth,
+      engine: match args.engine {
+        EngineArg::Sync => Engine::Sync,
+        EngineArg::IoUring => Engine::IoUring,
+      },
+      ..Default::default()
+    };
+
+    // Write the test file, then read it back
+    for (kind, delete_after) in [(BenchmarkType::Write, false), (BenchmarkType::Read, true)] {
+      let kind_name = match kind {
+        BenchmarkType::Read => "read",
+        BenchmarkType::Write => "write",
+      };
+
+      if !json {
+        eprintln!("Running {name} {kind_name} on {mount}");
+      }
+
+      let mut benchmark = GlacierDiskBenchmark::new(
+        disk.clone(),
+        args.mount,
+        BenchmarkConfig {
+          kind,
+          delete_after,
+          ..config.clone()
+        },
+      )?;
+      let result = benchmark.run()?;
+
+      results.push(TestResult {
+        test: name,
+        kind: kind_name,
+        block_size: config.block_size,
+        block_count: config.block_count,
+        random,
+        queue_depth: config.queue_depth,
+        threads: config.threads,
+        elapsed_secs: result.elapsed.as_secs_f64(),
+        bytes_per_second: result.avg_speed,
+        iops: result.iops,
+        mode: result.mode.to_string(),
+        engine: result.engine.to_string(),
+      });
+    }
+  }
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&results)?);
+    return Ok(());
+  }
+
+  let mut table = Table::new(&["TEST", "READ", "WRITE"]);
+  for chunk in results.chunks(2) {
+    let speed = |kind: &str| {
+      chunk
+        .iter()
+        .find(|result| result.kind == kind)
+        .map(|result| format!("{:.2} MB/s", result.bytes_per_second / MB))
+        .unwrap_or("-".into())
+    };
+
+    table.row(vec![
+      chunk[0].test.to_string(),
+      speed("read"),
+      speed("write"),
+    ]);
+  }
+
+  print!("{table}");
+
+  if !args.buffered && results.iter().any(|result| result.mode != "direct") {
+    println!("O_DIRECT isn't supported on {mount}, so the page cache was flushed between tests instead");
+  }
+
+  if args.engine == EngineArg::IoUring && results.iter().any(|result| result.engine != "io_uring") {
+    println!("io_uring isn't available, so blocking reads and writes were used instead");
+  }
+
+  Ok(())
+}
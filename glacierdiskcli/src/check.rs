@@ -0,0 +1,374 @@
+use std::{error::Error, fmt::Display, path::PathBuf};
+
+use clap::Args;
+use libglacierdisk::{
+  disk::Disk,
+  health::{HealthStatus, HealthThresholds},
+  power::ReadPolicy,
+  snapshot::DiskSnapshot,
+  trend::TrendMetric,
+};
+
+use crate::disk;
+
+/// Plugin states, in the order Icinga ranks them from best to worst
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum State {
+  Ok,
+  Warning,
+  Unknown,
+  Critical,
+}
+
+impl State {
+  /// The exit code monitoring systems expect
+  pub fn code(&self) -> i32 {
+    match self {
+      State::Ok => 0,
+      State::Warning => 1,
+      State::Critical => 2,
+      State::Unknown => 3,
+    }
+  }
+}
+
+impl Display for State {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      State::Ok => write!(f, "OK"),
+      State::Warning => write!(f, "WARNING"),
+      State::Unknown => write!(f, "UNKNOWN"),
+      State::Critical => write!(f, "CRITICAL"),
+    }
+  }
+}
+
+impl From<HealthStatus> for State {
+  fn from(status: HealthStatus) -> Self {
+    match status {
+      HealthStatus::Good => State::Ok,
+      HealthStatus::Caution => State::Warning,
+      HealthStatus::Bad => State::Critical,
+    }
+  }
+}
+
+#[derive(Args)]
+pub struct CheckArgs {
+  /// Disks to check. Defaults to every disk.
+  disks: Vec<String>,
+  /// Temperature (°C) at which a disk is WARNING
+  #[arg(long, default_value_t = 55.)]
+  temp_warning: f64,
+  /// Temperature (°C) at which a disk is CRITICAL
+  #[arg(long, default_value_t = 65.)]
+  temp_critical: f64,
+  /// Bad sectors (reallocated + pending + uncorrectable) at which a disk is WARNING
+  #[arg(long, default_value_t = HealthThresholds::default().sectors_caution)]
+  sectors_warning: u64,
+  /// Bad sectors at which a disk is CRITICAL
+  #[arg(long, default_value_t = HealthThresholds::default().sectors_bad)]
+  sectors_critical: u64,
+  /// Remaining life (%) at or below which an SSD is WARNING
+  #[arg(long, default_value_t = HealthThresholds::default().life_caution)]
+  wear_warning: u8,
+  /// Remaining life (%) at or below which an SSD is CRITICAL
+  #[arg(long, default_value_t = HealthThresholds::default().life_bad)]
+  wear_critical: u8,
+  /// Read drives in standby too, waking them up. Otherwise they are reported as OK without being checked.
+  #[arg(long)]
+  wake: bool,
+}
+
+impl CheckArgs {
+  fn thresholds(&self) -> HealthThresholds {
+    HealthThresholds {
+      sectors_caution: self.sectors_warning,
+      sectors_bad: self.sectors_critical,
+      life_caution: self.wear_warning,
+      life_bad: self.wear_critical,
+    }
+  }
+
+  fn validate(&self) -> Result<(), Box<dyn Error>> {
+    if self.temp_warning > self.temp_critical {
+      return Err("--temp-warning must not be above --temp-critical".into());
+    }
+
+    if self.sectors_warning > self.sectors_critical {
+      return Err("--sectors-warning must not be above --sectors-critical".into());
+    }
+
+    if self.wear_warning < self.wear_critical {
+      return Err("--wear-warning must not be below --wear-critical".into());
+    }
+
+    Ok(())
+  }
+}
+
+/// The result of checking one disk
+struct DiskCheck {
+  state: State,
+  /// Why the disk isn't OK, or a short summary if it is
+  message: String,
+  perfdata: Vec<String>,
+}
+
+/// Print the plugin output and return the exit code. Errors are reported as UNKNOWN rather than returned.
+pub fn run(args: &CheckArgs) -> i32 {
+  let (state, message, perfdata) = match check(args) {
+    Ok(checks) => summarize(&checks),
+    Err(e) => (State::Unknown, e.to_string(), vec![]),
+  };
+
+  if perfdata.is_empty() {
+    println!("DISK {state} - {message}");
+  } else {
+    println!("DISK {state} - {message} | {}", perfdata.join(" "));
+  }
+
+  state.code()
+}
+
+fn check(args: &CheckArgs) -> Result<Vec<DiskCheck>, Box<dyn Error>> {
+  args.validate()?;
+
+  let policy = if args.wake {
+    ReadPolicy::Always
+  } else {
+    ReadPolicy::SkipIfSleeping
+  };
+
+  let paths = if args.disks.is_empty() {
+    let mut paths = libglacierdisk::disk::get_disk_paths()?
+      .into_iter()
+      .map(|name| PathBuf::from("/dev").join(name))
+      .collect::<Vec<PathBuf>>();
+    paths.sort();
+    paths
+  } else {
+    args
+      .disks
+      .iter()
+      .map(|disk| disk::resolve(disk))
+      .collect::<Result<Vec<PathBuf>, Box<dyn Error>>>()?
+  };
+
+  if paths.is_empty() {
+    return Err("No disks found".into());
+  }
+
+  let checks = paths
+    .into_iter()
+    .map(|path| check_disk(path, policy, args))
+    .collect::<Vec<DiskCheck>>();
+
+  if !args.disks.is_empty() {
+    return Ok(checks);
+  }
+
+  // Card readers and the like can't be read or have no SMART data, which only matters if they were asked for by name
+  let checks = checks
+    .into_iter()
+    .filter(|check| check.state != State::Unknown)
+    .collect::<Vec<DiskCheck>>();
+
+  if checks.is_empty() {
+    return Err("No disks with SMART data found".into());
+  }
+
+  Ok(checks)
+}
+
+fn check_disk(path: PathBuf, policy: ReadPolicy, args: &CheckArgs) -> DiskCheck {
+  let name = path
+    .file_name()
+    .unwrap_or_default()
+    .to_string_lossy()
+    .to_string();
+  let mut disk = match Disk::with_policy(path, policy) {
+    Ok(disk) => disk,
+    Err(e) => {
+      return DiskCheck {
+        state: State::Unknown,
+        message: format!("{name} unreadable ({e})"),
+        perfdata: vec![],
+      }
+    }
+  };
+  let snapshot = DiskSnapshot::capture(&mut disk);
+
+  evaluate(&name, &snapshot, args)
+}
+
+/// Check a snapshot against the thresholds
+fn evaluate(name: &str, snapshot: &DiskSnapshot, args: &CheckArgs) -> DiskCheck {
+  if snapshot.sleeping {
+    return DiskCheck {
+      state: State::Ok,
+      message: format!("{name} in standby"),
+      perfdata: vec![],
+    };
+  }
+
+  let Some(report) = snapshot.health_report(&args.thresholds()) else {
+    return DiskCheck {
+      state: State::Unknown,
+      message: format!("{name} has no SMART data"),
+      perfdata: vec![],
+    };
+  };
+
+  let mut state = State::from(report.status);
+  let mut problems = report
+    .problems()
+    .map(|problem| problem.message.clone())
+    .collect::<Vec<String>>();
+
+  if let Some(temperature) = snapshot.temperature {
+    let temperature_state = if temperature >= args.temp_critical {
+      State::Critical
+    } else if temperature >= args.temp_warning {
+      State::Warning
+    } else {
+      State::Ok
+    };
+
+    if temperature_state > State::Ok {
+      problems.push(format!("temperature {temperature:.0}°C"));
+    }
+
+    state = state.max(temperature_state);
+  }
+
+  let message = if problems.is_empty() {
+    format!("{name} {report}")
+  } else {
+    format!("{name} {report}: {}", problems.join(", "))
+  };
+
+  DiskCheck {
+    state,
+    message,
+    perfdata: perfdata(name, snapshot, report.score, args),
+  }
+}
+
+/// Performance data in the `'label'=value[UOM];[warn];[crit];[min];[max]` format
+fn perfdata(name: &str, snapshot: &DiskSnapshot, score: u8, args: &CheckArgs) -> Vec<String> {
+  let mut perfdata = vec![];
+
+  // `@N:` alerts when the value is N or above, the same as `evaluate`
+  if let Some(temperature) = snapshot.temperature {
+    perfdata.push(format!(
+      "'{name}_temperature'={temperature};@{}:;@{}:",
+      args.temp_warning, args.temp_critical
+    ));
+  }
+
+  // Plain thresholds alert when the value goes above them, so these are one below the count that changes the verdict
+  let sectors_warning = args.sectors_warning.saturating_sub(1);
+  let sectors_critical = args.sectors_critical.saturating_sub(1);
+  for (label, metric) in [
+    ("reallocated", TrendMetric::Reallocated),
+    ("pending", TrendMetric::Pending),
+  ] {
+    if let Some(value) = metric.value(snapshot) {
+      perfdata.push(format!(
+        "'{name}_{label}'={value};{sectors_warning};{sectors_critical};0;"
+      ));
+    }
+  }
+
+  // `N:` alerts when the value drops below N
+  if let Some(wear) = TrendMetric::WearRemaining.value(snapshot) {
+    perfdata.push(format!(
+      "'{name}_wear'={wear}%;{}:;{}:;0;100",
+      args.wear_warning as u16 + 1,
+      args.wear_critical as u16 + 1
+    ));
+  }
+
+  perfdata.push(format!("'{name}_health'={score}%;;;0;100"));
+
+  perfdata
+}
+
+/// Combine the checks into the overall state, the status text and the perfdata
+fn summarize(checks: &[DiskCheck]) -> (State, String, Vec<String>) {
+  let state = checks
+    .iter()
+    .map(|check| check.state)
+    .max()
+    .unwrap_or(State::Unknown);
+
+  // Only mention the disks that need attention, unless there is just the one
+  let message = if checks.len() == 1 {
+    checks[0].message.clone()
+  } else if state == State::Ok {
+    format!("{} disks OK", checks.len())
+  } else {
+    checks
+      .iter()
+      .filter(|check| check.state != State::Ok)
+      .map(|check| check.message.clone())
+      .collect::<Vec<String>>()
+      .join("; ")
+  };
+
+  let perfdata = checks
+    .iter()
+    .flat_map(|check| check.perfdata.clone())
+    .collect();
+
+  (state, message, perfdata)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn args(temp_warning: f64, temp_critical: f64) -> CheckArgs {
+    let thresholds = HealthThresholds::default();
+
+    CheckArgs {
+      disks: vec![],
+      temp_warning,
+      temp_critical,
+      sectors_warning: thresholds.sectors_caution,
+      sectors_critical: thresholds.sectors_bad,
+      wear_warning: thresholds.life_caution,
+      wear_critical: thresholds.life_bad,
+      wake: false,
+    }
+  }
+
+  fn temperature(value: f64, args: &CheckArgs) -> String {
+    let snapshot = DiskSnapshot {
+      temperature: Some(value),
+      ..Default::default()
+    };
+
+    perfdata("sda", &snapshot, 100, args).remove(0)
+  }
+
+  #[test]
+  fn temperature_perfdata() {
+    let thresholds = args(50., 60.);
+
+    // Just under the warning threshold, so OK, and not rounded up to look like it's over
+    assert_eq!(
+      temperature(49.6, &thresholds),
+      "'sda_temperature'=49.6;@50:;@60:"
+    );
+    assert_eq!(
+      temperature(50., &thresholds),
+      "'sda_temperature'=50;@50:;@60:"
+    );
+    assert_eq!(
+      temperature(61.25, &args(49.5, 60.5)),
+      "'sda_temperature'=61.25;@49.5:;@60.5:"
+    );
+  }
+}
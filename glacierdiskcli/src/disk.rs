@@ -0,0 +1,33 @@
+use std::{
+  error::Error,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+use libglacierdisk::{disk::Disk, id::DiskId};
+
+/// Find a disk by path (`/dev/sda`), name (`sda`) or stable identifier (`wwn-0x5000c500a1b2c3d4`)
+pub fn resolve(disk: &str) -> Result<PathBuf, Box<dyn Error>> {
+  if disk.starts_with('/') {
+    return Ok(disk.into());
+  }
+
+  let dev = Path::new("/dev").join(disk);
+  if dev.exists() {
+    return Ok(dev);
+  }
+
+  DiskId::from_str(disk)?.resolve()
+}
+
+/// Open a disk, reading its SMART data
+pub fn open(disk: &str) -> Result<Disk, Box<dyn Error>> {
+  let path = resolve(disk)?;
+
+  Disk::new(path.clone()).map_err(|e| format!("Failed to open {}: {e}", path.display()).into())
+}
+
+/// Whether we are running as root, which reading SMART data generally needs
+pub fn is_root() -> bool {
+  unsafe { libc::geteuid() == 0 }
+}
@@ -0,0 +1,97 @@
+use std::{error::Error, path::PathBuf};
+
+use libglacierdisk::{
+  health::{HealthStatus, HealthThresholds},
+  snapshot::DiskSnapshot,
+};
+use serde::Serialize;
+use shared::convert::bytes_to_readable;
+
+use crate::{disk, table::cell};
+
+/// Identity and health of a disk
+#[derive(Serialize)]
+struct DiskInfo {
+  path: PathBuf,
+  id: String,
+  model: Option<String>,
+  serial: Option<String>,
+  firmware: Option<String>,
+  kind: String,
+  size: Option<u64>,
+  link_speed: Option<String>,
+  health: Option<Health>,
+  temperature: Option<f64>,
+  power_on_hours: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct Health {
+  score: u8,
+  status: HealthStatus,
+  problems: Vec<String>,
+}
+
+pub fn run(disk: &str, json: bool) -> Result<(), Box<dyn Error>> {
+  let mut disk = disk::open(disk)?;
+  let snapshot = DiskSnapshot::capture(&mut disk);
+  let report = snapshot.health_report(&HealthThresholds::default());
+
+  let info = DiskInfo {
+    path: snapshot.path.clone(),
+    id: snapshot.id.clone(),
+    model: snapshot.model.clone(),
+    serial: snapshot.serial.clone(),
+    firmware: disk
+      .raw_disk()
+      .and_then(|mut raw| raw.identify_parse().ok())
+      .map(|identity| identity.firmware.trim().to_string()),
+    kind: disk.kind.to_string(),
+    size: disk.size().ok().filter(|size| *size > 0),
+    link_speed: Some(disk.ata_link.speed.clone()).filter(|speed| !speed.is_empty()),
+    health: report.map(|report| Health {
+      score: report.score,
+      status: report.status,
+      problems: report.problems().map(|p| p.to_string()).collect(),
+    }),
+    temperature: snapshot.temperature,
+    power_on_hours: snapshot.power_on_hours,
+  };
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&info)?);
+    return Ok(());
+  }
+
+  let health = info
+    .health
+    .as_ref()
+    .map(|health| format!("{} ({}%)", health.status, health.score));
+
+  let lines = [
+    ("Device", Some(info.path.display().to_string())),
+    ("ID", Some(info.id.clone())),
+    ("Model", info.model.clone()),
+    ("Serial", info.serial.clone()),
+    ("Firmware", info.firmware.clone()),
+    ("Kind", Some(info.kind.clone())),
+    ("Size", info.size.map(bytes_to_readable)),
+    ("Link speed", info.link_speed.clone()),
+    ("Health", health),
+    ("Temperature", info.temperature.map(|t| format!("{t:.0}°C"))),
+    (
+      "Power on",
+      info.power_on_hours.map(|h| format!("{h} hours")),
+    ),
+  ];
+
+  for (key, value) in lines {
+    println!("{key:>12}: {}", cell(value));
+  }
+
+  for problem in info.health.iter().flat_map(|health| &health.problems) {
+    println!("{:>12}  {problem}", "");
+  }
+
+  Ok(())
+}
@@ -0,0 +1,30 @@
+use std::error::Error;
+
+use libglacierdisk::{power::ReadPolicy, snapshot::DiskSnapshot};
+
+use crate::disk;
+
+/// Print full snapshots of the given disks, or every disk if none are given
+pub fn run(disks: &[String], wake: bool) -> Result<(), Box<dyn Error>> {
+  let snapshots = if disks.is_empty() {
+    let policy = if wake {
+      ReadPolicy::Always
+    } else {
+      ReadPolicy::SkipIfSleeping
+    };
+
+    libglacierdisk::list_disks_with_policy(policy)?
+      .iter_mut()
+      .map(DiskSnapshot::capture)
+      .collect::<Vec<DiskSnapshot>>()
+  } else {
+    disks
+      .iter()
+      .map(|disk| disk::open(disk).map(|mut disk| DiskSnapshot::capture(&mut disk)))
+      .collect::<Result<Vec<DiskSnapshot>, Box<dyn Error>>>()?
+  };
+
+  println!("{}", serde_json::to_string_pretty(&snapshots)?);
+
+  Ok(())
+}
@@ -0,0 +1,118 @@
+use std::{error::Error, path::PathBuf};
+
+use libglacierdisk::{
+  disk::{get_disk_paths, Disk},
+  health::HealthStatus,
+  kind::disk_class,
+  power::ReadPolicy,
+  snapshot::DiskSnapshot,
+};
+use serde::Serialize;
+use shared::convert::bytes_to_readable;
+
+use crate::{
+  disk::is_root,
+  table::{cell, Table},
+};
+
+/// One line of `glacierdisk list`
+#[derive(Serialize)]
+struct DiskSummary {
+  path: PathBuf,
+  id: Option<String>,
+  model: Option<String>,
+  kind: String,
+  size: Option<u64>,
+  sleeping: bool,
+  health: Option<u8>,
+  status: Option<HealthStatus>,
+  temperature: Option<f64>,
+  /// Why the disk couldn't be read, if it couldn't
+  error: Option<String>,
+}
+
+pub fn run(json: bool, wake: bool) -> Result<(), Box<dyn Error>> {
+  // Listing shouldn't spin up every drive in the system
+  let policy = if wake {
+    ReadPolicy::Always
+  } else {
+    ReadPolicy::SkipIfSleeping
+  };
+
+  let mut paths = get_disk_paths()?;
+  paths.sort();
+
+  let summaries = paths
+    .into_iter()
+    .map(|name| summarize(PathBuf::from("/dev").join(name), policy))
+    .collect::<Vec<DiskSummary>>();
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&summaries)?);
+    return Ok(());
+  }
+
+  let mut table = Table::new(&["DEVICE", "MODEL", "KIND", "SIZE", "HEALTH", "TEMP", "ID"]);
+
+  for summary in &summaries {
+    let health = match (&summary.error, summary.sleeping, summary.status) {
+      (Some(_), _, _) => "Unreadable".to_string(),
+      (_, true, _) => "Sleeping".to_string(),
+      (_, _, Some(status)) => format!("{status} ({}%)", summary.health.unwrap_or_default()),
+      _ => "-".to_string(),
+    };
+
+    table.row(vec![
+      summary.path.display().to_string(),
+      cell(summary.model.as_ref()),
+      summary.kind.clone(),
+      cell(summary.size.map(bytes_to_readable)),
+      health,
+      cell(summary.temperature.map(|t| format!("{t:.0}°C"))),
+      cell(summary.id.as_ref()),
+    ]);
+  }
+
+  print!("{table}");
+
+  if summaries.iter().any(|s| s.error.is_some()) && !is_root() {
+    eprintln!("Some disks couldn't be read, try running as root");
+  }
+
+  Ok(())
+}
+
+fn summarize(path: PathBuf, policy: ReadPolicy) -> DiskSummary {
+  let mut disk = match Disk::with_policy(path.clone(), policy) {
+    Ok(disk) => disk,
+    Err(e) => {
+      return DiskSummary {
+        kind: disk_class(&path).to_string(),
+        path,
+        id: None,
+        model: None,
+        size: None,
+        sleeping: false,
+        health: None,
+        status: None,
+        temperature: None,
+        error: Some(e.to_string()),
+      }
+    }
+  };
+
+  let snapshot = DiskSnapshot::capture(&mut disk);
+
+  DiskSummary {
+    path,
+    id: disk.id.as_ref().map(|id| id.to_string()),
+    model: snapshot.model,
+    kind: disk.kind.to_string(),
+    size: disk.size().ok().filter(|size| *size > 0),
+    sleeping: snapshot.sleeping,
+    health: snapshot.health,
+    status: snapshot.health_status,
+    temperature: snapshot.temperature,
+    error: None,
+  }
+}
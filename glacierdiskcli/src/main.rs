@@ -0,0 +1,124 @@
+use std::{env, error::Error, path::PathBuf, process::ExitCode};
+
+use clap::{Parser, Subcommand};
+
+mod attrs;
+mod bench;
+mod check;
+mod disk;
+mod info;
+mod json;
+mod list;
+mod monitor;
+mod selftest;
+mod table;
+
+/// Everything went fine
+pub const EXIT_OK: i32 = 0;
+/// Something went wrong, eg. the disk couldn't be read
+pub const EXIT_ERROR: i32 = 1;
+/// The disk failed, eg. a self-test found a problem. 2 is what clap uses for bad arguments.
+pub const EXIT_FAILED: i32 = 3;
+
+#[derive(Parser)]
+#[command(
+  name = "glacierdisk",
+  version,
+  about = "Read SMART data, benchmark and test disks"
+)]
+struct Cli {
+  /// Print JSON instead of tables
+  #[arg(long, global = true)]
+  json: bool,
+
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// List disks. Drives in standby are left asleep.
+  List {
+    /// Read drives in standby too, waking them up
+    #[arg(long)]
+    wake: bool,
+  },
+  /// Show the identity and health of a disk
+  Info {
+    /// Path (/dev/sda), name (sda) or stable id (wwn-0x...)
+    disk: String,
+  },
+  /// Print the SMART attribute table, or the health log for NVMe drives
+  Attrs {
+    /// Path (/dev/sda), name (sda) or stable id (wwn-0x...)
+    disk: String,
+  },
+  /// Print full snapshots of disks as JSON
+  Json {
+    /// Disks to snapshot. Defaults to every disk.
+    disks: Vec<String>,
+    /// Read drives in standby too, waking them up
+    #[arg(long)]
+    wake: bool,
+  },
+  /// Benchmark a disk through a file on one of its mounts
+  Bench(bench::BenchArgs),
+  /// Run, check on or abort SMART self-tests
+  #[command(subcommand)]
+  SelfTest(selftest::SelfTestCommand),
+  /// Nagios/Icinga plugin. Prints one status line with perfdata and exits 0/1/2/3 for OK/WARNING/CRITICAL/UNKNOWN.
+  Check(check::CheckArgs),
+  /// Show the disks and active alerts of a running glacierdisk-monitord
+  Monitor {
+    /// The daemon's socket
+    #[arg(long, default_value = shared::monitor::DEFAULT_SOCKET)]
+    socket: PathBuf,
+  },
+}
+
+fn main() -> ExitCode {
+  let cli = match Cli::try_parse() {
+    Ok(cli) => cli,
+    Err(e) => {
+      let _ = e.print();
+
+      // Monitoring systems treat 2 as CRITICAL, so bad arguments to a check are UNKNOWN instead
+      let code = if e.use_stderr() && env::args().any(|arg| arg == "check") {
+        check::State::Unknown.code()
+      } else {
+        e.exit_code()
+      };
+
+      return ExitCode::from(code as u8);
+    }
+  };
+
+  match run(&cli) {
+    Ok(code) => ExitCode::from(code as u8),
+    Err(e) => {
+      eprintln!("glacierdisk: {e}");
+
+      if !disk::is_root() && !matches!(cli.command, Command::Monitor { .. }) {
+        eprintln!("Reading disks usually needs root, try running with sudo");
+      }
+
+      ExitCode::from(EXIT_ERROR as u8)
+    }
+  }
+}
+
+fn run(cli: &Cli) -> Result<i32, Box<dyn Error>> {
+  match &cli.command {
+    Command::List { wake } => list::run(cli.json, *wake)?,
+    Command::Info { disk } => info::run(disk, cli.json)?,
+    Command::Attrs { disk } => attrs::run(disk, cli.json)?,
+    Command::Json { disks, wake } => json::run(disks, *wake)?,
+    Command::Bench(args) => bench::run(args, cli.json)?,
+    Command::SelfTest(command) => return selftest::run(command, cli.json),
+    // Check reports its own errors in the plugin format
+    Command::Check(args) => return Ok(check::run(args)),
+    Command::Monitor { socket } => monitor::run(socket, cli.json)?,
+  }
+
+  Ok(EXIT_OK)
+}
@@ -0,0 +1,52 @@
+use std::{error::Error, path::Path};
+
+use shared::monitor::fetch_state;
+
+use crate::table::{cell, Table};
+
+/// Show what a running glacierdisk-monitord last saw. Unlike the other commands, this doesn't need root.
+pub fn run(socket: &Path, json: bool) -> Result<(), Box<dyn Error>> {
+  let state = fetch_state(socket).map_err(|e| {
+    format!(
+      "Couldn't reach glacierdisk-monitord at {} ({e})",
+      socket.display()
+    )
+  })?;
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&state)?);
+    return Ok(());
+  }
+
+  let mut table = Table::new(&["DEVICE", "MODEL", "HEALTH", "TEMP", "ALERTS"]);
+
+  for disk in &state.disks {
+    let health = match (disk.sleeping, disk.health_status) {
+      (true, _) => "Sleeping".to_string(),
+      (_, Some(status)) => format!("{status} ({}%)", disk.health.unwrap_or_default()),
+      _ => "-".to_string(),
+    };
+
+    table.row(vec![
+      disk.path.display().to_string(),
+      cell(disk.model.as_ref()),
+      health,
+      cell(disk.temperature.map(|t| format!("{t:.0}°C"))),
+      state.alerts_for(&disk.id).len().to_string(),
+    ]);
+  }
+
+  print!("{table}");
+
+  for alert in &state.alerts {
+    println!(
+      "{} {} [{}] {}",
+      alert.path.display(),
+      alert.severity,
+      alert.rule,
+      alert.message
+    );
+  }
+
+  Ok(())
+}
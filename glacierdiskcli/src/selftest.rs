@@ -0,0 +1,133 @@
+use std::{error::Error, thread, time::Duration};
+
+use clap::Subcommand;
+use libglacierdisk::selftest::{self, SelfTestKind, SelfTestResult, SelfTestStatus};
+
+use crate::{disk, EXIT_FAILED, EXIT_OK};
+
+/// How often to check on a test with `--wait`
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Subcommand)]
+pub enum SelfTestCommand {
+  /// Show supported tests and the result of the last one
+  Status { disk: String },
+  /// Start a test. It runs in the background on the drive itself.
+  Start {
+    disk: String,
+    /// short, extended or conveyance
+    #[arg(default_value = "short", value_parser = parse_kind)]
+    kind: SelfTestKind,
+    /// Wait for the test to finish, then exit with its result
+    #[arg(long)]
+    wait: bool,
+  },
+  /// Abort a running test
+  Abort { disk: String },
+}
+
+/// Returns the exit code, which reflects the test result for `status` and `start --wait`
+pub fn run(command: &SelfTestCommand, json: bool) -> Result<i32, Box<dyn Error>> {
+  match command {
+    SelfTestCommand::Status { disk } => {
+      let status = SelfTestStatus::for_disk(disk::resolve(disk)?)?;
+      print_status(&status, json)?;
+      Ok(exit_code(&status.result))
+    }
+    SelfTestCommand::Start { disk, kind, wait } => {
+      let path = disk::resolve(disk)?;
+      let status = SelfTestStatus::for_disk(&path)?;
+
+      if !status.supports(*kind) {
+        return Err(format!("{} doesn't support {kind} self-tests", path.display()).into());
+      }
+
+      if status.result == SelfTestResult::InProgress {
+        return Err(
+          format!(
+            "A self-test is already running on {}, abort it first",
+            path.display()
+          )
+          .into(),
+        );
+      }
+
+      selftest::start(&path, *kind)?;
+
+      let duration = status.duration(*kind).unwrap_or_default();
+      eprintln!(
+        "Started {kind} self-test on {}, which should take about {} minutes",
+        path.display(),
+        duration.as_secs().div_ceil(60)
+      );
+
+      if !wait {
+        return Ok(EXIT_OK);
+      }
+
+      // Give the drive a moment to report that the test is running
+      thread::sleep(Duration::from_secs(1));
+
+      loop {
+        let status = SelfTestStatus::for_disk(&path)?;
+
+        if status.result != SelfTestResult::InProgress {
+          print_status(&status, json)?;
+          return Ok(exit_code(&status.result));
+        }
+
+        eprintln!("{}% remaining", status.remaining);
+        thread::sleep(POLL_INTERVAL);
+      }
+    }
+    SelfTestCommand::Abort { disk } => {
+      let path = disk::resolve(disk)?;
+      selftest::abort(&path)?;
+      eprintln!("Aborted self-test on {}", path.display());
+      Ok(EXIT_OK)
+    }
+  }
+}
+
+fn print_status(status: &SelfTestStatus, json: bool) -> Result<(), Box<dyn Error>> {
+  if json {
+    println!("{}", serde_json::to_string_pretty(status)?);
+    return Ok(());
+  }
+
+  let result = match (&status.result, status.kind) {
+    (SelfTestResult::InProgress, Some(kind)) => {
+      format!("{kind} test in progress, {}% remaining", status.remaining)
+    }
+    (SelfTestResult::InProgress, None) => format!("In progress, {}% remaining", status.remaining),
+    (result, Some(kind)) => format!("{result} ({kind} test)"),
+    (result, None) => result.to_string(),
+  };
+
+  println!("Last result: {result}");
+
+  if status.supported.is_empty() {
+    println!("Self-tests are not supported");
+  }
+
+  for (kind, duration) in &status.supported {
+    println!(
+      "  {kind}: about {} minutes",
+      duration.as_secs().div_ceil(60)
+    );
+  }
+
+  Ok(())
+}
+
+fn exit_code(result: &SelfTestResult) -> i32 {
+  if result.is_failure() {
+    EXIT_FAILED
+  } else {
+    EXIT_OK
+  }
+}
+
+fn parse_kind(kind: &str) -> Result<SelfTestKind, String> {
+  kind.parse()
+}
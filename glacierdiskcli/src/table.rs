@@ -0,0 +1,56 @@
+use std::fmt::Display;
+
+/// A plain text table with left-aligned columns
+pub struct Table {
+  headers: Vec<String>,
+  rows: Vec<Vec<String>>,
+}
+
+impl Table {
+  pub fn new(headers: &[&str]) -> Self {
+    Self {
+      headers: headers.iter().map(|h| h.to_string()).collect(),
+      rows: vec![],
+    }
+  }
+
+  pub fn row(&mut self, cells: Vec<String>) {
+    self.rows.push(cells);
+  }
+}
+
+impl Display for Table {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mut widths = self
+      .headers
+      .iter()
+      .map(|h| h.chars().count())
+      .collect::<Vec<usize>>();
+
+    for row in &self.rows {
+      for (i, cell) in row.iter().enumerate() {
+        if let Some(width) = widths.get_mut(i) {
+          *width = (*width).max(cell.chars().count());
+        }
+      }
+    }
+
+    for row in std::iter::once(&self.headers).chain(&self.rows) {
+      let line = row
+        .iter()
+        .zip(&widths)
+        .map(|(cell, width)| format!("{cell:width$}"))
+        .collect::<Vec<String>>()
+        .join("  ");
+
+      writeln!(f, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Shorthand for an optional value, with `-` for missing ones
+pub fn cell<T: Display>(value: Option<T>) -> String {
+  value.map(|v| v.to_string()).unwrap_or("-".into())
+}
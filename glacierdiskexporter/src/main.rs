@@ -0,0 +1,132 @@
+use std::{
+  env,
+  error::Error,
+  sync::{Arc, RwLock},
+  thread,
+  time::Duration,
+};
+
+use libglacierdisk::{metrics, power::ReadPolicy, snapshot::DiskSnapshot};
+use tiny_http::{Header, Method, Response, Server};
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:9635";
+const DEFAULT_INTERVAL: u64 = 60;
+
+const USAGE: &str =
+  "Usage: glacierdisk-exporter [--listen <addr>] [--interval <seconds>] [--wake-sleeping]
+
+Options:
+  -l, --listen <addr>         Address to serve /metrics on (default: 127.0.0.1:9635)
+  -i, --interval <seconds>    How often to read the disks (default: 60)
+      --wake-sleeping         Read disks that are in standby, waking them up
+  -h, --help                  Show this message";
+
+fn main() {
+  if let Err(e) = run() {
+    eprintln!("glacierdisk-exporter: {e}");
+    std::process::exit(1);
+  }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+  let mut listen = DEFAULT_LISTEN.to_string();
+  let mut interval = DEFAULT_INTERVAL;
+  let mut wake_sleeping = false;
+  let mut args = env::args().skip(1);
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-l" | "--listen" => listen = args.next().ok_or("--listen needs an address")?,
+      "-i" | "--interval" => {
+        interval = args
+          .next()
+          .ok_or("--interval needs a number of seconds")?
+          .parse()
+          .map_err(|e| format!("Invalid interval: {e}"))?;
+      }
+      "--wake-sleeping" => wake_sleeping = true,
+      "-h" | "--help" => {
+        println!("{USAGE}");
+        return Ok(());
+      }
+      _ => return Err(format!("Unknown argument: {arg}\n\n{USAGE}").into()),
+    }
+  }
+
+  if interval == 0 {
+    return Err("interval must be greater than zero".into());
+  }
+
+  let server = Server::http(&listen).map_err(|e| format!("Failed to listen on {listen}: {e}"))?;
+  let body = Arc::new(RwLock::new(String::new()));
+
+  // Scrapes are served from the last poll, so scraping often can't keep disks awake or hammer them with commands
+  let policy = if wake_sleeping {
+    ReadPolicy::Always
+  } else {
+    ReadPolicy::SkipIfSleeping
+  };
+  let cache = body.clone();
+  thread::spawn(move || {
+    let mut snapshots = vec![];
+
+    loop {
+      match poll(policy, &snapshots) {
+        Ok(polled) => {
+          snapshots = polled;
+          if let Ok(mut body) = cache.write() {
+            *body = metrics::render(&snapshots);
+          }
+        }
+        Err(e) => eprintln!("Error polling disks: {e}"),
+      }
+
+      thread::sleep(Duration::from_secs(interval));
+    }
+  });
+
+  println!("Serving metrics on http://{listen}/metrics, polling every {interval}s");
+
+  let content_type = Header::from_bytes("Content-Type", metrics::CONTENT_TYPE)
+    .map_err(|_| "Invalid content type header")?;
+
+  for request in server.incoming_requests() {
+    let response = match (request.method(), request.url()) {
+      (Method::Get, "/metrics") => {
+        let body = body.read().map(|body| body.clone()).unwrap_or_default();
+        Response::from_string(body).with_header(content_type.clone())
+      }
+      (Method::Get, "/") => Response::from_string("GlacierDisk exporter, see /metrics\n"),
+      _ => Response::from_string("Not found\n").with_status_code(404),
+    };
+
+    if let Err(e) = request.respond(response) {
+      eprintln!("Error responding to request: {e}");
+    }
+  }
+
+  Ok(())
+}
+
+/// Snapshot every disk. Sleeping disks keep the SMART data from their previous snapshot so their series don't disappear.
+fn poll(
+  policy: ReadPolicy,
+  previous: &[DiskSnapshot],
+) -> Result<Vec<DiskSnapshot>, Box<dyn Error>> {
+  let snapshots = libglacierdisk::list_disks_with_policy(policy)?
+    .iter_mut()
+    .map(|disk| {
+      let snapshot = DiskSnapshot::capture(disk);
+
+      match previous.iter().find(|p| p.id == snapshot.id) {
+        Some(previous) if snapshot.sleeping => DiskSnapshot {
+          sleeping: true,
+          ..previous.clone()
+        },
+        _ => snapshot,
+      }
+    })
+    .collect();
+
+  Ok(snapshots)
+}
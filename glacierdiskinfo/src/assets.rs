@@ -1,11 +1,12 @@
 use base64::prelude::*;
 
 // TODO I want to go back to using asset!() but it doesn't work for me right now
-pub const CSS: [&str; 5] = [
+pub const CSS: [&str; 6] = [
   include_str!("../assets/main.css"),
   include_str!("../assets/drive.css"),
   include_str!("../assets/driveattrtable.css"),
   include_str!("../assets/driveinfotable.css"),
+  include_str!("../assets/drivepartitiontable.css"),
   include_str!("../assets/drivetabs.css"),
 ];
 
@@ -1,12 +1,20 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use libglacierdisk::{
   ata::DiskAtaLink,
   attribute::{Attribute, Convertable},
+  cache::CacheSettings,
   disk::Disk,
+  endurance::{EnduranceData, EnduranceEstimate},
+  health::{HealthReport, HealthThresholds},
   kind::DiskKind,
   libatasmart::IdentifyParsedData,
   libatasmart_sys::SkSmartOverall,
+  md::{self, MdArray},
+  mount::FsUsage,
+  partition::Partition,
+  snapshot::DiskSnapshot,
+  stack,
 };
 
 use super::smart::smart_to_string;
@@ -34,10 +42,31 @@ pub struct DiskCache {
   kind: DiskKind,
 
   smart_overall: String,
+
+  // None if the drive is asleep or doesn't report it
+  cache_settings: Option<CacheSettings>,
+
+  // None if there is no SMART data to score
+  health: Option<HealthReport>,
+
+  // None for HDDs, or if the drive is asleep
+  endurance: Option<EnduranceEstimate>,
+
+  // Empty if the drive is asleep
+  partitions: Vec<Partition>,
+  // Partition number -> usage, for partitions that are mounted
+  partition_usage: HashMap<u32, FsUsage>,
+
+  // Software RAID arrays this disk is part of
+  md_arrays: Vec<MdArray>,
+
+  // Set if this was read from the monitor daemon, rather than from the disk itself
+  snapshot: Option<DiskSnapshot>,
 }
 
 impl DiskCache {
-  pub fn new(mut disk: Disk) -> Self {
+  /// `tbw_ratings` maps drive models to their rated endurance in TBW
+  pub fn new(mut disk: Disk, tbw_ratings: &HashMap<String, f64>) -> Self {
     let total_write = match disk.get_attribute("total-lbas-written") {
       Some(write) => write.pretty_unit.convert_to_base(write.pretty_value),
       None => 0,
@@ -55,12 +84,43 @@ impl DiskCache {
     let power_on = disk.raw_disk().map(|mut disk| disk.get_power_on().unwrap_or(0)).unwrap_or(0);
     let power_cycle_count = disk.raw_disk().map(|mut disk| disk.get_power_cycle_count().unwrap_or(0)).unwrap_or(0);
     let kind = disk.kind.clone();
-    let smart_overall = smart_to_string(
-      disk
-        .raw_disk()
-        .map(|mut disk| disk.smart_get_overall().unwrap_or(SkSmartOverall::SK_SMART_OVERALL_GOOD))
-        .unwrap_or(SkSmartOverall::SK_SMART_OVERALL_GOOD),
-    );
+    // Reading IDENTIFY could wake the drive up
+    let cache_settings = if disk.is_sleeping() { None } else { disk.cache_settings().ok() };
+    let health = if disk.is_sleeping() { None } else { disk.health().ok() };
+    let model = disk
+      .raw_disk()
+      .and_then(|mut disk| disk.identify_parse().ok())
+      .map(|identity| identity.model)
+      .or(disk.model().ok())
+      .unwrap_or_default();
+    let rated_tbw = tbw_ratings.get(model.trim()).copied();
+    let endurance = if disk.is_sleeping() || kind == DiskKind::HDD { None } else { disk.endurance(rated_tbw).ok() };
+    // Reading the partition table could also wake the drive up
+    let partitions = if disk.is_sleeping() { vec![] } else { disk.partitions().unwrap_or_default() };
+    let mounts = disk.mounts().unwrap_or_default();
+    let partition_usage = partitions
+      .iter()
+      .filter_map(|partition| {
+        let path = partition.path.as_ref()?;
+        // The filesystem may be on an LVM or LUKS volume inside the partition
+        let mount = mounts
+          .iter()
+          .find(|mount| mount.device.as_ref().is_some_and(|device| stack::is_stacked_on(device, path)))?;
+
+        Some((partition.number, mount.usage().ok()?))
+      })
+      .collect();
+    let md_arrays = disk.md_arrays().unwrap_or_default();
+    let smart_overall = if disk.is_sleeping() {
+      "Standby".to_string()
+    } else {
+      smart_to_string(
+        disk
+          .raw_disk()
+          .map(|mut disk| disk.smart_get_overall().unwrap_or(SkSmartOverall::SK_SMART_OVERALL_GOOD))
+          .unwrap_or(SkSmartOverall::SK_SMART_OVERALL_GOOD),
+      )
+    };
 
     Self {
       disk,
@@ -75,6 +135,67 @@ impl DiskCache {
       total_write,
       kind,
       smart_overall,
+      cache_settings,
+      health,
+      endurance,
+      partitions,
+      partition_usage,
+      md_arrays,
+      snapshot: None,
+    }
+  }
+
+  /// Build from a monitor daemon snapshot, without opening the disk. Anything the snapshot doesn't record is left empty.
+  pub fn from_snapshot(snapshot: &DiskSnapshot, tbw_ratings: &HashMap<String, f64>) -> Self {
+    let disk = Disk::unopened(snapshot.path.clone());
+    // libatasmart's base units, same as above
+    let total = |name: &str| snapshot.attribute_by_name(name).and_then(|attribute| attribute.decoded).unwrap_or(0.) as u64;
+
+    let kind = snapshot.kind.clone().unwrap_or(disk.kind.clone());
+    let attributes = snapshot.attributes.iter().map(Attribute::from).collect::<Vec<Attribute>>();
+    let temperature = snapshot.temperature.map(|t| ((t + 273.15) * 1000.) as u64).unwrap_or(273150);
+    let power_on = snapshot.power_on_hours.unwrap_or(0) * 60 * 60 * 1000;
+    let power_cycle_count = match &snapshot.nvme {
+      Some(log) => log.power_cycles.min(u64::MAX as u128) as u64,
+      None => snapshot.attribute(12).map(|attribute| attribute.raw).unwrap_or(0),
+    };
+    let health = snapshot.health_report(&HealthThresholds::default());
+    let rated_tbw = snapshot.model.as_ref().and_then(|model| tbw_ratings.get(model.trim())).copied();
+    let endurance = match &snapshot.nvme {
+      _ if snapshot.sleeping || kind == DiskKind::HDD => None,
+      Some(log) => Some(EnduranceEstimate::new(EnduranceData::from_nvme(log), rated_tbw)),
+      None if attributes.is_empty() => None,
+      None => Some(EnduranceEstimate::new(
+        EnduranceData::from_attributes(&attributes, snapshot.power_on_hours),
+        rated_tbw,
+      )),
+    };
+    let smart_overall = match (snapshot.sleeping, snapshot.health_status) {
+      (true, _) => "Standby".to_string(),
+      (false, Some(status)) => status.to_string(),
+      (false, None) => "N/A".to_string(),
+    };
+
+    Self {
+      path: snapshot.path.clone(),
+      temperature,
+      size: disk.size().unwrap_or(0),
+      attributes,
+      ata_link: disk.ata_link.clone(),
+      power_on,
+      power_cycle_count,
+      total_read: total("total-lbas-read"),
+      total_write: total("total-lbas-written"),
+      kind,
+      smart_overall,
+      cache_settings: None,
+      health,
+      endurance,
+      partitions: vec![],
+      partition_usage: HashMap::new(),
+      md_arrays: md::arrays_for_disk(&snapshot.path).unwrap_or_default(),
+      snapshot: Some(snapshot.clone()),
+      disk,
     }
   }
 
@@ -82,8 +203,20 @@ impl DiskCache {
     &self.path
   }
 
+  pub fn disk(&self) -> &Disk {
+    &self.disk
+  }
+
   // TODO store in struct
   pub fn identity(&self) -> IdentifyParsedData {
+    if let Some(snapshot) = &self.snapshot {
+      return IdentifyParsedData {
+        firmware: "N/A".into(),
+        serial: snapshot.serial.clone().unwrap_or("N/A".into()),
+        model: snapshot.model.clone().unwrap_or("N/A".into()),
+      };
+    }
+
     if self.disk.kind == DiskKind::USB {
       return IdentifyParsedData {
         firmware: "N/A".into(),
@@ -149,4 +282,42 @@ impl DiskCache {
   pub fn smart_overall(&self) -> &str {
     &self.smart_overall
   }
+
+  pub fn cache_settings(&self) -> Option<&CacheSettings> {
+    self.cache_settings.as_ref()
+  }
+
+  pub fn health(&self) -> Option<&HealthReport> {
+    self.health.as_ref()
+  }
+
+  pub fn endurance(&self) -> Option<&EnduranceEstimate> {
+    self.endurance.as_ref()
+  }
+
+  pub fn partitions(&self) -> &Vec<Partition> {
+    &self.partitions
+  }
+
+  pub fn partition_usage(&self, number: u32) -> Option<&FsUsage> {
+    self.partition_usage.get(&number)
+  }
+
+  pub fn md_arrays(&self) -> &Vec<MdArray> {
+    &self.md_arrays
+  }
+
+  /// The md arrays this disk is part of, e.g. `"md0 (raid1, degraded)"`. Empty if there are none.
+  pub fn raid(&self) -> String {
+    self
+      .md_arrays
+      .iter()
+      .map(|array| {
+        let level = array.level.as_deref().unwrap_or("inactive");
+        let state = if array.is_degraded() { "degraded" } else { &array.state };
+        format!("{} ({level}, {state})", array.name)
+      })
+      .collect::<Vec<String>>()
+      .join(", ")
+  }
 }
@@ -0,0 +1,30 @@
+use std::sync::Once;
+
+use libglacierdisk::{disk::Disk, history::HistoryStore, snapshot::DiskSnapshot};
+use shared::{dot_config, App};
+
+static COMPACT: Once = Once::new();
+
+pub fn history_store() -> Option<HistoryStore> {
+  HistoryStore::open(dot_config().join(App::GlacierDiskInfo.to_string()).join("history")).ok()
+}
+
+/// Record a snapshot of each disk, so we have something to compare against next time
+pub fn record(disks: &mut [Disk]) -> Vec<DiskSnapshot> {
+  let snapshots = disks.iter_mut().map(DiskSnapshot::capture).collect::<Vec<DiskSnapshot>>();
+
+  let Some(store) = history_store() else {
+    return snapshots;
+  };
+
+  for snapshot in &snapshots {
+    if let Err(e) = store.record(snapshot) {
+      eprintln!("Error recording history for {}: {e}", snapshot.path.display());
+    }
+  }
+
+  // Retention only needs applying once per launch
+  COMPACT.call_once(|| store.compact().unwrap_or_default());
+
+  snapshots
+}
@@ -1,13 +1,53 @@
 use disk_cache::DiskCache;
+use libglacierdisk::power::ReadPolicy;
+use shared::{
+  config::load_config,
+  monitor::{self, DEFAULT_SOCKET},
+  App,
+};
 use smart::smart_to_string;
 use status::Status;
 
 pub mod disk_cache;
+pub mod history;
+pub mod notify;
 pub mod smart;
 pub mod status;
 
 pub fn drives_and_status() -> Vec<(DiskCache, Status)> {
-  let mut drives = libglacierdisk::list_disks().expect("Failed to list disks");
+  let config = load_config(App::GlacierDiskInfo).unwrap_or_default();
+  let tbw_ratings = &config.tbw_ratings;
+
+  // The monitor daemon has already read (and recorded) every disk, and we don't need root to ask it
+  if let Ok(state) = monitor::fetch_state(DEFAULT_SOCKET) {
+    notify::check(&state.disks, &config);
+
+    return state
+      .disks
+      .iter()
+      .map(|snapshot| {
+        let disk_cache = DiskCache::from_snapshot(snapshot, tbw_ratings);
+        let state = match disk_cache.health() {
+          Some(health) => health.to_string(),
+          None if snapshot.sleeping => "Standby".into(),
+          None => "N/A".into(),
+        };
+        let temp = snapshot.temperature.unwrap_or(0.) as f32;
+
+        (disk_cache, Status { temp, state })
+      })
+      .collect();
+  }
+
+  // Don't spin up drives in standby just to refresh the list
+  let mut drives = libglacierdisk::list_disks_with_policy(ReadPolicy::SkipIfSleeping).unwrap_or_else(|e| {
+    // Without root, e.g. if the monitor daemon has gone away since we started
+    eprintln!("Error listing disks: {e}");
+    vec![]
+  });
+
+  let snapshots = history::record(&mut drives);
+  notify::check(&snapshots, &config);
 
   let drives: Vec<(DiskCache, Status)> = drives
     .iter_mut()
@@ -20,8 +60,6 @@ pub fn drives_and_status() -> Vec<(DiskCache, Status)> {
             return None;
           }
         };
-        let state = smart_to_string(smart);
-
         let temp = disk.get_temperature().unwrap_or(0);
 
         // convert mkelvin to celsius
@@ -30,13 +68,19 @@ pub fn drives_and_status() -> Vec<(DiskCache, Status)> {
         // Drop the mutex guard, preventing deadlock
         drop(disk);
 
-        let disk_cache = DiskCache::new(d.clone());
+        let disk_cache = DiskCache::new(d.clone(), tbw_ratings);
+        // Prefer our own health score, since libatasmart's overall status is much coarser
+        let state = match disk_cache.health() {
+          Some(health) => health.to_string(),
+          None => smart_to_string(smart),
+        };
 
         return Some((disk_cache, Status { temp, state }));
       }
 
-      let disk_cache = DiskCache::new(d.clone());
-      Some((disk_cache, Status { temp: 0., state: "N/A".into() }))
+      let state = if d.is_sleeping() { "Standby" } else { "N/A" };
+      let disk_cache = DiskCache::new(d.clone(), tbw_ratings);
+      Some((disk_cache, Status { temp: 0., state: state.into() }))
     })
     .collect();
 
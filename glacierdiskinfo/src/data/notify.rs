@@ -0,0 +1,118 @@
+use std::{sync::Mutex, time::Duration};
+
+use libglacierdisk::{
+  alert::{AlertEngine, AlertEvent, AlertRule, Condition, Metric, Severity},
+  snapshot::DiskSnapshot,
+};
+use shared::{
+  config::Config,
+  notify::{desktop::DesktopNotifier, Notifier},
+};
+
+use super::history::history_store;
+
+// Kept between refreshes, so alerts only fire when something actually changes
+static ENGINE: Mutex<Option<AlertEngine>> = Mutex::new(None);
+
+const HEALTH_RULE: &str = "Health changed";
+
+fn rules(config: &Config) -> Vec<AlertRule> {
+  vec![
+    AlertRule {
+      name: HEALTH_RULE.into(),
+      metric: Metric::HealthStatus,
+      condition: Condition::Changed,
+      value: 0.,
+      severity: Severity::Warning,
+      duration: None,
+      clear: None,
+      message: None,
+      disks: vec![],
+    },
+    AlertRule {
+      name: "Temperature".into(),
+      metric: Metric::Temperature,
+      condition: Condition::GreaterThan,
+      value: config.temperature_alert,
+      severity: Severity::Warning,
+      // Ignore short spikes, eg. during a benchmark
+      duration: Some(Duration::from_secs(60)),
+      clear: Some(config.temperature_alert - 5.),
+      message: None,
+      disks: vec![],
+    },
+  ]
+}
+
+/// Show desktop notifications for anything that changed since the last snapshots
+pub fn check(snapshots: &[DiskSnapshot], config: &Config) {
+  if !config.notifications {
+    return;
+  }
+
+  let Ok(mut engine) = ENGINE.lock() else {
+    return;
+  };
+
+  let engine = engine.get_or_insert_with(|| {
+    let mut engine = AlertEngine::new(rules(config));
+
+    // Start from the last time we ran, so a drive that went bad since then is still reported
+    if let Some(store) = history_store() {
+      for snapshot in snapshots {
+        let previous = store
+          .query(&snapshot.id, ..snapshot.timestamp)
+          .unwrap_or_default();
+        if let Some(previous) = previous.last() {
+          engine.evaluate(previous);
+        }
+      }
+    }
+
+    engine
+  });
+
+  let events = snapshots
+    .iter()
+    .flat_map(|snapshot| {
+      engine
+        .evaluate(snapshot)
+        .into_iter()
+        .map(move |event| (with_health_severity(event), snapshot))
+    })
+    .collect::<Vec<_>>();
+
+  if events.is_empty() {
+    return;
+  }
+
+  let mut notifier = match DesktopNotifier::for_invoking_user() {
+    Ok(notifier) => notifier,
+    Err(e) => {
+      eprintln!("Error connecting to the notification service: {e}");
+      return;
+    }
+  };
+
+  for (event, snapshot) in events {
+    if let Err(e) = notifier.notify(&event, snapshot) {
+      eprintln!("Error showing notification: {e}");
+    }
+  }
+}
+
+/// A health change is only as bad as the status it changed to, so eg. Caution to Good isn't a warning
+fn with_health_severity(mut event: AlertEvent) -> AlertEvent {
+  if let AlertEvent::Fired(alert) = &mut event {
+    if alert.rule == HEALTH_RULE {
+      // Same encoding as `Metric::HealthStatus`
+      alert.severity = match alert.value as u8 {
+        0 => Severity::Info,
+        1 => Severity::Warning,
+        _ => Severity::Critical,
+      };
+    }
+  }
+
+  event
+}
@@ -1,4 +1,7 @@
-use libglacierdisk::libatasmart_sys::SkSmartOverall;
+use libglacierdisk::{
+  health::{HealthReport, HealthStatus},
+  libatasmart_sys::SkSmartOverall,
+};
 
 pub enum DriveStatus {
   Good,
@@ -7,14 +10,11 @@ pub enum DriveStatus {
 }
 
 impl DriveStatus {
-  pub fn from_smart(s: impl AsRef<str>) -> Self {
-    match s.as_ref() {
-      "Good" => DriveStatus::Good,
-      "Bad Attribute In The Past" => DriveStatus::Caution,
-      "Bad Sector" => DriveStatus::Bad,
-      "Bad Attribute Now" => DriveStatus::Caution,
-      "Bad Sector Many" => DriveStatus::Bad,
-      "Bad Status" => DriveStatus::Bad,
+  /// Drives we couldn't score (asleep, no SMART, etc.) are shown as Caution
+  pub fn from_health(health: Option<&HealthReport>) -> Self {
+    match health.map(|health| health.status) {
+      Some(HealthStatus::Good) => DriveStatus::Good,
+      Some(HealthStatus::Bad) => DriveStatus::Bad,
       _ => DriveStatus::Caution,
     }
   }
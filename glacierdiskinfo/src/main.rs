@@ -10,6 +10,7 @@ use dioxus::{
 use dioxus_desktop::muda::MenuId;
 use shared::{
   config::{self, load_config},
+  monitor::{self, DEFAULT_SOCKET},
   theme::{self, read_theme_contents},
   App,
 };
@@ -28,13 +29,16 @@ pub static DRIVES: Global<Signal<Vec<(DiskCache, Status)>>> = Global::new(|| use
 fn main() {
   util::scaffold_folders();
 
-  match sudo::check() {
-    sudo::RunningAs::Root => (),
-    sudo::RunningAs::User => shared::root::pk_reopen(),
-    sudo::RunningAs::Suid => {
-      sudo::escalate_if_needed().expect("Failed to escalate privileges");
-    }
-  };
+  // If the monitor daemon is running, we can read everything from it as a normal user
+  if !monitor::is_running(DEFAULT_SOCKET) {
+    match sudo::check() {
+      sudo::RunningAs::Root => (),
+      sudo::RunningAs::User => shared::root::pk_reopen(),
+      sudo::RunningAs::Suid => {
+        sudo::escalate_if_needed().expect("Failed to escalate privileges");
+      }
+    };
+  }
 
   let window = WindowBuilder::new()
     .with_title("GlacierDiskInfo")
@@ -61,6 +65,8 @@ fn Root() -> Element {
     }
   });
 
+  let mut selected_drive = use_signal(|| DRIVES.resolve()()[0].0.clone());
+
   // Submenu handler
   dioxus::desktop::use_muda_event_handler(move |e| {
     let id = match e.id() {
@@ -69,7 +75,14 @@ fn Root() -> Element {
     .to_owned();
 
     if id == "refresh-disks" {
-      DRIVES.resolve().set(drives_and_status());
+      let drives = drives_and_status();
+
+      // Keep the same physical drive selected, even if its path changed
+      if let Some((disk, _)) = drives.iter().find(|(d, _)| d.disk() == selected_drive.peek().disk()) {
+        selected_drive.set(disk.clone());
+      }
+
+      DRIVES.resolve().set(drives);
     } else if id.starts_with("apply-") {
       let mut config = config::load_config(App::GlacierDiskInfo).unwrap_or_default();
       let name = id.strip_prefix("apply-").unwrap_or_default();
@@ -103,8 +116,6 @@ fn Root() -> Element {
     }
   });
 
-  let mut selected_drive = use_signal(|| DRIVES.resolve()()[0].0.clone());
-
   rsx! {
       style {
         r#"{CSS.join("\n")}"#
@@ -3,7 +3,7 @@ use shared::convert::bytes_to_readable;
 
 use crate::{
   data::{disk_cache::DiskCache, smart::DriveStatus},
-  ui::{drive_attr_table::DriveAttrTable, drive_info_table::DriveInfoTable},
+  ui::{drive_attr_table::DriveAttrTable, drive_info_table::DriveInfoTable, drive_partition_table::DrivePartitionTable},
 };
 
 #[derive(Props, PartialEq, Clone)]
@@ -18,8 +18,16 @@ pub fn Drive(props: DriveProps) -> Element {
   let identity = drive.identity();
   let size = drive.size();
   let size = bytes_to_readable(size);
-  let status = drive.smart_overall();
-  let status_class = match DriveStatus::from_smart(status) {
+  let health = drive.health();
+  let status = match health {
+    Some(health) => health.to_string(),
+    None => drive.smart_overall().to_string(),
+  };
+  // Explain the verdict on hover
+  let status_reasons = health
+    .map(|health| health.problems().map(|reason| reason.to_string()).collect::<Vec<String>>().join("\n"))
+    .unwrap_or_default();
+  let status_class = match DriveStatus::from_health(health) {
     DriveStatus::Good => "good",
     DriveStatus::Caution => "caution",
     DriveStatus::Bad => "bad",
@@ -54,6 +62,7 @@ pub fn Drive(props: DriveProps) -> Element {
             }
             span {
               class: "drive-health-status ".to_owned() + status_class,
+              title: "{status_reasons}",
               "{status}"
             }
           }
@@ -78,6 +87,10 @@ pub fn Drive(props: DriveProps) -> Element {
       DriveAttrTable {
         selected_drive: props.selected_drive.clone(),
       }
+
+      DrivePartitionTable {
+        selected_drive: props.selected_drive.clone(),
+      }
     }
   }
 }
@@ -24,6 +24,28 @@ pub fn DriveInfoTable(props: DriveInfoTableProps) -> Element {
   let identity = drive.identity();
   let lbas_read = drive.total_read();
   let lbas_written = drive.total_write();
+  let cache = drive.cache_settings();
+  let write_cache = cache.map(|c| c.write_cache.to_string()).unwrap_or("N/A".into());
+  let read_look_ahead = cache.map(|c| c.read_look_ahead.to_string()).unwrap_or("N/A".into());
+  let endurance = drive.endurance();
+  let life_used = match endurance.map(|e| (e.tbw, e.rated_tbw, e.life_used)) {
+    Some((Some(tbw), Some(rated), _)) => format!("{tbw:.2} / {rated} TBW"),
+    Some((_, _, Some(used))) => format!("{used:.0}% used"),
+    _ => "N/A".into(),
+  };
+  let write_amplification = endurance
+    .and_then(|e| e.write_amplification)
+    .map(|wa| format!("{wa:.2}"))
+    .unwrap_or("N/A".into());
+  let remaining_life = endurance
+    .and_then(|e| e.remaining)
+    .map(|remaining| {
+      let days = remaining.as_secs() / 60 / 60 / 24;
+      if days > 365 { format!("{:.1} years", days as f32 / 365.) } else { format!("{days} days") }
+    })
+    .unwrap_or("N/A".into());
+  let raid = drive.raid();
+  let raid = if raid.is_empty() { "None".into() } else { raid };
 
   let left_values = [
     ("Firmware", identity.firmware),
@@ -32,6 +54,7 @@ pub fn DriveInfoTable(props: DriveInfoTableProps) -> Element {
     ("Drive Path", drive.path().to_string_lossy().to_string()),
     ("SATA Speed", ata.speed.clone()),
     ("Kind", drive.kind().to_string()),
+    ("RAID", raid),
   ];
   let right_values = [
     ("Total Read", bytes_to_readable(lbas_read)),
@@ -42,6 +65,12 @@ pub fn DriveInfoTable(props: DriveInfoTableProps) -> Element {
       "Average Power On Time",
       ms_to_readable(if drive.power_cycle_count() > 0 { drive.power_on() / drive.power_cycle_count() } else { 0 }),
     ),
+    ("Write Cache", write_cache),
+    ("Read Look-Ahead", read_look_ahead),
+    ("Endurance", life_used),
+    ("Write Amplification", write_amplification),
+    // Remaining power-on time at the current write rate
+    ("Est. Remaining Life", remaining_life),
   ];
   let left_rows = left_values.iter().map(|(name, value)| {
     rsx! {
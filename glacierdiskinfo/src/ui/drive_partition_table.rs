@@ -0,0 +1,120 @@
+use dioxus::prelude::*;
+use shared::convert::bytes_to_readable;
+
+use crate::data::disk_cache::DiskCache;
+
+#[derive(Props, PartialEq, Clone)]
+pub struct DrivePartitionTableProps {
+  pub selected_drive: DiskCache,
+}
+
+#[component]
+pub fn DrivePartitionTable(props: DrivePartitionTableProps) -> Element {
+  let partitions = props.selected_drive.partitions();
+  let rows = partitions.iter().map(|partition| {
+    let path = partition
+      .path
+      .as_ref()
+      .map(|path| path.to_string_lossy().to_string())
+      .unwrap_or("--".into());
+    let kind = partition.type_name().unwrap_or("Unknown");
+    let size = bytes_to_readable(partition.size_bytes());
+    let used = match props.selected_drive.partition_usage(partition.number) {
+      Some(usage) => format!("{} ({:.0}%)", bytes_to_readable(usage.used), usage.percent_used()),
+      None => "--".into(),
+    };
+    let (filesystem, label, uuid) = match &partition.filesystem {
+      Some(fs) => (
+        fs.kind.to_string(),
+        fs.label.clone().or(partition.name.clone()).unwrap_or_default(),
+        fs.uuid.clone().unwrap_or_default(),
+      ),
+      None => ("--".into(), partition.name.clone().unwrap_or_default(), "".into()),
+    };
+
+    rsx! {
+      div {
+        class: "drive-part-row",
+        span {
+          class: "drive-part-number",
+          "{partition.number}"
+        },
+        span {
+          class: "drive-part-path",
+          "{path}"
+        },
+        span {
+          class: "drive-part-type",
+          "{kind}"
+        },
+        span {
+          class: "drive-part-fs",
+          "{filesystem}"
+        },
+        span {
+          class: "drive-part-label",
+          "{label}"
+        },
+        span {
+          class: "drive-part-size",
+          "{size}"
+        },
+        span {
+          class: "drive-part-used",
+          "{used}"
+        },
+        span {
+          class: "drive-part-uuid",
+          "{uuid}"
+        }
+      }
+    }
+  });
+
+  rsx! {
+    div {
+      class: "drive-part-table",
+
+      div {
+        class: "drive-part-table-header",
+        span {
+          class: "drive-part-number",
+          "#"
+        },
+        span {
+          class: "drive-part-path",
+          "Path"
+        },
+        span {
+          class: "drive-part-type",
+          "Type"
+        },
+        span {
+          class: "drive-part-fs",
+          "Filesystem"
+        },
+        span {
+          class: "drive-part-label",
+          "Label"
+        },
+        span {
+          class: "drive-part-size",
+          "Size"
+        },
+        span {
+          class: "drive-part-used",
+          "Used"
+        },
+        span {
+          class: "drive-part-uuid",
+          "UUID"
+        },
+      },
+
+      div {
+        class: "drive-part-table-body",
+        {rows}
+      }
+    }
+  }
+}
@@ -2,7 +2,7 @@ use dioxus::prelude::*;
 
 use crate::{
   assets::{ico_to_data_uri, BAD_ICO, CAUTION_ICO, GOOD_ICO},
-  data::{disk_cache::DiskCache, smart::DriveStatus},
+  data::{disk_cache::DiskCache, smart::DriveStatus, status::Status},
   DRIVES,
 };
 
@@ -15,56 +15,41 @@ pub struct DriveTabsProps {
 #[component]
 pub fn DriveTabs(props: DriveTabsProps) -> Element {
   let drives = DRIVES.resolve()();
-  let tab_renders = drives.iter().map(|(disk, status)| {
-    let disk = disk.clone();
-    let selected_name = props.selected_drive.path().to_string_lossy().to_string();
-    let evt_name = disk.path().to_string_lossy().to_string();
-    let temp = if status.temp == 0. { "--".into() } else { status.temp.to_string() };
-    let status_class = match DriveStatus::from_smart(status.state.as_str()) {
-      DriveStatus::Good => "good",
-      DriveStatus::Caution => "caution",
-      DriveStatus::Bad => "bad",
-    };
-    let ico = match status.state.as_str() {
-      "Good" => GOOD_ICO,
-      "Bad Attribute In The Past" => CAUTION_ICO,
-      "Bad Sector" => BAD_ICO,
-      "Bad Attribute Now" => CAUTION_ICO,
-      "Bad Sector Many" => BAD_ICO,
-      "Bad Status" => BAD_ICO,
-      _ => CAUTION_ICO,
-    };
+
+  // Disks in the same md arrays are grouped together, under the names of the arrays
+  let mut groups: Vec<(String, Vec<(DiskCache, Status)>)> = vec![];
+  for (disk, status) in drives {
+    let raid = disk.raid();
+
+    match groups.iter_mut().find(|(name, _)| *name == raid) {
+      Some((_, members)) => members.push((disk, status)),
+      None => groups.push((raid, vec![(disk, status)])),
+    }
+  }
+
+  let group_renders = groups.into_iter().map(|(raid, members)| {
+    let tab_renders = members
+      .into_iter()
+      .map(|(disk, status)| drive_tab(disk, status, &props.selected_drive, props.on_select));
+
+    if raid.is_empty() {
+      return rsx! {
+        {tab_renders}
+      };
+    }
 
     rsx! {
       div {
-        class: "drive-tab ".to_owned() + status_class + " " + (if evt_name == selected_name { "selected" } else { "" }),
-        onclick: move |_| props.on_select.call(disk.clone()),
+        class: "drive-tab-group",
 
-        div {
-          class: "drive-tab-status",
-
-          img {
-            class: "drive-tab-icon",
-            src: ico_to_data_uri(ico),
-          }
+        span {
+          class: "drive-tab-group-name",
+          "{raid}"
         }
+
         div {
-          class: "drive-tab-info",
-
-          span {
-            class: "drive-tab-state",
-            "{status.state}"
-          }
-
-          span {
-            class: "drive-tab-temp",
-            "{temp} °C"
-          }
-
-          span {
-            class: "drive-tab-name",
-            "{evt_name}"
-          }
+          class: "drive-tab-group-tabs",
+          {tab_renders}
         }
       }
     }
@@ -74,7 +59,53 @@ pub fn DriveTabs(props: DriveTabsProps) -> Element {
     div {
       id: "drive-tabs",
 
-      {tab_renders}
+      {group_renders}
+    }
+  }
+}
+
+fn drive_tab(disk: DiskCache, status: Status, selected_drive: &DiskCache, on_select: EventHandler<DiskCache>) -> Element {
+  // Compare the underlying disks rather than paths, since /dev/sdX names can move around
+  let selected = disk.disk() == selected_drive.disk();
+  let evt_name = disk.path().to_string_lossy().to_string();
+  let temp = if status.temp == 0. { "--".into() } else { status.temp.to_string() };
+  let (status_class, ico) = match DriveStatus::from_health(disk.health()) {
+    DriveStatus::Good => ("good", GOOD_ICO),
+    DriveStatus::Caution => ("caution", CAUTION_ICO),
+    DriveStatus::Bad => ("bad", BAD_ICO),
+  };
+
+  rsx! {
+    div {
+      class: "drive-tab ".to_owned() + status_class + " " + (if selected { "selected" } else { "" }),
+      onclick: move |_| on_select.call(disk.clone()),
+
+      div {
+        class: "drive-tab-status",
+
+        img {
+          class: "drive-tab-icon",
+          src: ico_to_data_uri(ico),
+        }
+      }
+      div {
+        class: "drive-tab-info",
+
+        span {
+          class: "drive-tab-state",
+          "{status.state}"
+        }
+
+        span {
+          class: "drive-tab-temp",
+          "{temp} °C"
+        }
+
+        span {
+          class: "drive-tab-name",
+          "{evt_name}"
+        }
+      }
     }
   }
 }
@@ -1,4 +1,5 @@
 pub mod drive;
 pub mod drive_attr_table;
 pub mod drive_info_table;
+pub mod drive_partition_table;
 pub mod drive_tabs;
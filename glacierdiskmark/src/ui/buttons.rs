@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use dioxus::prelude::*;
-use libglacierdisk::benchmark::BenchmarkConfig;
+use libglacierdisk::benchmark::{BenchmarkConfig, Engine};
 
 use crate::util::bench::BenchKind;
 
@@ -26,18 +26,23 @@ pub fn Buttons(props: ButtonsProps) -> Element {
       }
 
       Button {
-        label: "SEQ1M",
-        on_click: move |_| props.run_configs.call((BenchKind::SEQ1M, get_configs(props.test_size).get("SEQ1M").unwrap().to_vec())),
+        label: "SEQ1M Q8T1",
+        on_click: move |_| props.run_configs.call((BenchKind::SEQ1MQ8T1, get_configs(props.test_size).get("SEQ1M Q8T1").unwrap().to_vec())),
       }
 
       Button {
-        label: "SEQ128K",
-        on_click: move |_| props.run_configs.call((BenchKind::SEQ128K, get_configs(props.test_size).get("SEQ128K").unwrap().to_vec())),
+        label: "SEQ1M Q1T1",
+        on_click: move |_| props.run_configs.call((BenchKind::SEQ1MQ1T1, get_configs(props.test_size).get("SEQ1M Q1T1").unwrap().to_vec())),
       }
 
       Button {
-        label: "RAND4K",
-        on_click: move |_| props.run_configs.call((BenchKind::RAND4K, get_configs(props.test_size).get("RAND4K").unwrap().to_vec())),
+        label: "RND4K Q32T1",
+        on_click: move |_| props.run_configs.call((BenchKind::RND4KQ32T1, get_configs(props.test_size).get("RND4K Q32T1").unwrap().to_vec())),
+      }
+
+      Button {
+        label: "RND4K Q1T1",
+        on_click: move |_| props.run_configs.call((BenchKind::RND4KQ1T1, get_configs(props.test_size).get("RND4K Q1T1").unwrap().to_vec())),
       },
     }
   }
@@ -69,26 +74,39 @@ fn get_configs(test_size: usize) -> HashMap<String, Vec<BenchmarkConfig>> {
   let seq1m = BenchmarkConfig {
     block_size: 1024 * 1024,
     block_count: test_size / 1024 / 1024,
+    // Otherwise reads come straight back out of the page cache
+    direct: true,
+    engine: Engine::IoUring,
     ..Default::default()
   };
-  let seq128k = BenchmarkConfig {
-    block_size: 1024 * 128,
-    block_count: test_size / 1024 / 128,
-    ..Default::default()
-  };
-  let rand4k = BenchmarkConfig {
+  let rnd4k = BenchmarkConfig {
     block_size: 1024 * 4,
     block_count: test_size / 1024 / 4,
+    random: true,
+    direct: true,
+    engine: Engine::IoUring,
     ..Default::default()
   };
 
+  let seq1m_q8t1 = BenchmarkConfig {
+    queue_depth: 8,
+    ..seq1m.clone()
+  };
+  let seq1m_q1t1 = seq1m;
+  let rnd4k_q32t1 = BenchmarkConfig {
+    queue_depth: 32,
+    ..rnd4k.clone()
+  };
+  let rnd4k_q1t1 = rnd4k;
+
   configs.insert(
     "ALL".into(),
-    vec![seq1m.clone(), seq128k.clone(), rand4k.clone()],
+    vec![seq1m_q8t1.clone(), seq1m_q1t1.clone(), rnd4k_q32t1.clone(), rnd4k_q1t1.clone()],
   );
-  configs.insert("SEQ1M".into(), vec![seq1m.clone()]);
-  configs.insert("SEQ128K".into(), vec![seq128k.clone()]);
-  configs.insert("RAND4K".into(), vec![rand4k.clone()]);
+  configs.insert("SEQ1M Q8T1".into(), vec![seq1m_q8t1]);
+  configs.insert("SEQ1M Q1T1".into(), vec![seq1m_q1t1]);
+  configs.insert("RND4K Q32T1".into(), vec![rnd4k_q32t1]);
+  configs.insert("RND4K Q1T1".into(), vec![rnd4k_q1t1]);
 
   configs
 }
@@ -14,19 +14,25 @@ pub fn Results(/* props: ResultsProps */) -> Element {
         class: "bench-result-row",
       }
 
-      // SEQ1M
+      // SEQ1M Q8T1
       Result {
         read: "0.00 MB/s",
         write: "0.00 MB/s",
       }
 
-      // SEQ128K
+      // SEQ1M Q1T1
       Result {
         read: "0.00 MB/s",
         write: "0.00 MB/s",
       }
 
-      // RAND4K
+      // RND4K Q32T1
+      Result {
+        read: "0.00 MB/s",
+        write: "0.00 MB/s",
+      }
+
+      // RND4K Q1T1
       Result {
         read: "0.00 MB/s",
         write: "0.00 MB/s",
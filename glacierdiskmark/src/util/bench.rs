@@ -3,12 +3,14 @@ use libglacierdisk::{
   disk::ShallowDisk,
 };
 
+/// The same rows as CrystalDiskMark, so results can be compared
 #[derive(Clone, Debug)]
 pub enum BenchKind {
   All,
-  SEQ1M,
-  SEQ128K,
-  RAND4K,
+  SEQ1MQ8T1,
+  SEQ1MQ1T1,
+  RND4KQ32T1,
+  RND4KQ1T1,
 }
 
 pub fn run_rw(
@@ -26,13 +28,13 @@ pub fn run_rw(
   for config in configs {
     let w_config = BenchmarkConfig {
       kind: BenchmarkType::Write,
-      file_path: Some(mount.join("glacier-disk-mark.bin")),
+      file_path: Some(mount.mount_point.join("glacier-disk-mark.bin")),
       delete_after: false,
       ..config.clone()
     };
     let r_config = BenchmarkConfig {
       kind: BenchmarkType::Read,
-      file_path: Some(mount.join("glacier-disk-mark.bin")),
+      file_path: Some(mount.mount_point.join("glacier-disk-mark.bin")),
       delete_after: true,
       ..config.clone()
     };
@@ -0,0 +1,118 @@
+use std::{error::Error, path::PathBuf, time::Duration};
+
+use libglacierdisk::alert::{parse_duration, AlertEngine, AlertRule};
+use serde::{Deserialize, Deserializer};
+use shared::{monitor::DEFAULT_SOCKET, notify::NotifierConfig};
+
+pub const DEFAULT_CONFIG: &str = "/etc/glacierdisk/monitord.toml";
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MonitorConfig {
+  /// How often to poll disks (e.g. `"5m"`)
+  #[serde(
+    default = "default_interval",
+    deserialize_with = "deserialize_duration"
+  )]
+  pub interval: Duration,
+  /// Read SMART data from disks in standby, spinning them up
+  #[serde(default)]
+  pub wake_sleeping: bool,
+  /// Only monitor these disks, by stable ID or path. Monitors all disks if empty.
+  #[serde(default)]
+  pub disks: Vec<String>,
+  /// Where to store snapshot history. Set to `false` to disable.
+  #[serde(default = "default_history")]
+  pub history: History,
+  #[serde(default = "default_socket")]
+  pub socket: PathBuf,
+  /// Permissions for the socket. Only root and `socket_group` can connect by default.
+  #[serde(default = "default_socket_mode")]
+  pub socket_mode: u32,
+  /// The group that owns the socket, so its members can read state without root
+  #[serde(default)]
+  pub socket_group: Option<String>,
+  /// Extra alert rules, in the same format as `rule`
+  #[serde(default)]
+  pub rules_file: Option<PathBuf>,
+  #[serde(default, rename = "rule")]
+  pub rules: Vec<AlertRule>,
+  #[serde(default)]
+  pub notify: Vec<NotifierConfig>,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum History {
+  Enabled(bool),
+  Dir(PathBuf),
+}
+
+impl History {
+  pub fn dir(&self) -> Option<PathBuf> {
+    match self {
+      History::Enabled(true) => Some(PathBuf::from("/var/lib/glacierdisk/history")),
+      History::Enabled(false) => None,
+      History::Dir(dir) => Some(dir.clone()),
+    }
+  }
+}
+
+impl MonitorConfig {
+  pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, Box<dyn Error>> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+      .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+
+    Ok(toml::from_str(&contents)?)
+  }
+
+  /// Build an alert engine from the inline rules and the rules file
+  pub fn alert_engine(&self) -> Result<AlertEngine, Box<dyn Error>> {
+    let mut rules = self.rules.clone();
+
+    if let Some(path) = &self.rules_file {
+      rules.extend(AlertEngine::load(path)?.rules);
+    }
+
+    let mut names = rules.iter().map(|r| &r.name).collect::<Vec<&String>>();
+    names.sort();
+    names.dedup();
+
+    if names.len() != rules.len() {
+      return Err("Alert rule names must be unique".into());
+    }
+
+    Ok(AlertEngine::new(rules))
+  }
+}
+
+fn default_interval() -> Duration {
+  Duration::from_secs(5 * 60)
+}
+
+fn default_history() -> History {
+  History::Enabled(true)
+}
+
+fn default_socket() -> PathBuf {
+  PathBuf::from(DEFAULT_SOCKET)
+}
+
+fn default_socket_mode() -> u32 {
+  0o660
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Value {
+    Seconds(u64),
+    String(String),
+  }
+
+  match Value::deserialize(deserializer)? {
+    Value::Seconds(secs) => Ok(Duration::from_secs(secs)),
+    Value::String(s) => parse_duration(s).map_err(serde::de::Error::custom),
+  }
+}
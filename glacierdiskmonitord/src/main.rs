@@ -0,0 +1,87 @@
+use std::{
+  env,
+  error::Error,
+  path::PathBuf,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use config::{MonitorConfig, DEFAULT_CONFIG};
+use monitor::Monitor;
+
+mod config;
+mod monitor;
+mod socket;
+
+const USAGE: &str = "Usage: glacierdisk-monitord [--config <path>] [--once]
+
+Options:
+  -c, --config <path>  Config file (default: /etc/glacierdisk/monitord.toml)
+      --once           Poll once, print the state as JSON and exit
+  -h, --help           Show this message";
+
+fn main() {
+  if let Err(e) = run() {
+    eprintln!("glacierdisk-monitord: {e}");
+    std::process::exit(1);
+  }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+  let mut config_path = PathBuf::from(DEFAULT_CONFIG);
+  let mut once = false;
+  let mut args = env::args().skip(1);
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-c" | "--config" => {
+        config_path = args.next().ok_or("--config needs a path")?.into();
+      }
+      "--once" => once = true,
+      "-h" | "--help" => {
+        println!("{USAGE}");
+        return Ok(());
+      }
+      _ => return Err(format!("Unknown argument: {arg}\n\n{USAGE}").into()),
+    }
+  }
+
+  let config = MonitorConfig::load(&config_path)?;
+
+  if config.interval.is_zero() {
+    return Err("interval must be greater than zero".into());
+  }
+
+  let mut monitor = Monitor::new(config)?;
+
+  if once {
+    monitor.poll()?;
+    let state = monitor.state.lock().map_err(|_| "State lock poisoned")?;
+    println!("{}", serde_json::to_string_pretty(&*state)?);
+    return Ok(());
+  }
+
+  socket::serve(
+    &monitor.config.socket,
+    monitor.config.socket_mode,
+    monitor.config.socket_group.as_deref(),
+    monitor.state.clone(),
+  )?;
+
+  println!(
+    "Monitoring disks every {}s, listening on {}",
+    monitor.config.interval.as_secs(),
+    monitor.config.socket.display()
+  );
+
+  monitor.run();
+
+  Ok(())
+}
+
+/// Seconds since the Unix epoch
+pub fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or_default()
+}
@@ -0,0 +1,135 @@
+use std::{
+  error::Error,
+  path::Path,
+  sync::{Arc, Mutex},
+  thread,
+};
+
+use libglacierdisk::{
+  alert::AlertEngine, history::HistoryStore, power::ReadPolicy, snapshot::DiskSnapshot,
+};
+use shared::{monitor::MonitorState, notify::Notifier};
+
+use crate::{config::MonitorConfig, now};
+
+/// How often to apply the history retention policy
+const COMPACT_INTERVAL: u64 = 60 * 60 * 24;
+
+/// Polls disks, records history, evaluates alerts and sends notifications
+pub struct Monitor {
+  pub config: MonitorConfig,
+  pub state: Arc<Mutex<MonitorState>>,
+  engine: AlertEngine,
+  store: Option<HistoryStore>,
+  notifiers: Vec<Box<dyn Notifier>>,
+  last_compact: u64,
+}
+
+impl Monitor {
+  pub fn new(config: MonitorConfig) -> Result<Self, Box<dyn Error>> {
+    let engine = config.alert_engine()?;
+    let store = config.history.dir().map(HistoryStore::open).transpose()?;
+    let notifiers = config
+      .notify
+      .iter()
+      .map(|notifier| notifier.build())
+      .collect::<Result<Vec<Box<dyn Notifier>>, Box<dyn Error>>>()?;
+
+    let state = MonitorState {
+      version: env!("CARGO_PKG_VERSION").to_string(),
+      started: now(),
+      interval: config.interval.as_secs(),
+      ..Default::default()
+    };
+
+    Ok(Self {
+      config,
+      state: Arc::new(Mutex::new(state)),
+      engine,
+      store,
+      notifiers,
+      last_compact: 0,
+    })
+  }
+
+  /// Poll forever
+  pub fn run(&mut self) {
+    loop {
+      if let Err(e) = self.poll() {
+        eprintln!("Error polling disks: {e}");
+      }
+
+      thread::sleep(self.config.interval);
+    }
+  }
+
+  /// Take a snapshot of every disk and act on it
+  pub fn poll(&mut self) -> Result<(), Box<dyn Error>> {
+    // Polling every few minutes would otherwise keep drives from ever spinning down
+    let policy = if self.config.wake_sleeping {
+      ReadPolicy::Always
+    } else {
+      ReadPolicy::SkipIfSleeping
+    };
+
+    let mut snapshots = vec![];
+
+    for mut disk in libglacierdisk::list_disks_with_policy(policy)? {
+      let snapshot = DiskSnapshot::capture(&mut disk);
+
+      if !self.monitors(&snapshot) {
+        continue;
+      }
+
+      if let Some(store) = &self.store {
+        if let Err(e) = store.record(&snapshot) {
+          eprintln!("Error recording history for {disk}: {e}");
+        }
+      }
+
+      for event in self.engine.evaluate(&snapshot) {
+        for notifier in self.notifiers.iter_mut() {
+          if let Err(e) = notifier.notify(&event, &snapshot) {
+            eprintln!("Error sending notification with {}: {e}", notifier.name());
+          }
+        }
+      }
+
+      snapshots.push(snapshot);
+    }
+
+    for notifier in self.notifiers.iter_mut() {
+      if let Err(e) = notifier.flush() {
+        eprintln!("Error sending notification with {}: {e}", notifier.name());
+      }
+    }
+
+    if let Some(store) = &self.store {
+      if now().saturating_sub(self.last_compact) >= COMPACT_INTERVAL {
+        self.last_compact = now();
+        if let Err(e) = store.compact() {
+          eprintln!("Error compacting history: {e}");
+        }
+      }
+    }
+
+    let mut state = self.state.lock().map_err(|_| "State lock poisoned")?;
+    state.updated = now();
+    state.disks = snapshots;
+    state.alerts = self.engine.active().into_iter().cloned().collect();
+    state
+      .alerts
+      .sort_by(|a, b| (&a.disk, &a.rule).cmp(&(&b.disk, &b.rule)));
+
+    Ok(())
+  }
+
+  fn monitors(&self, snapshot: &DiskSnapshot) -> bool {
+    self.config.disks.is_empty()
+      || self
+        .config
+        .disks
+        .iter()
+        .any(|disk| *disk == snapshot.id || Path::new(disk) == snapshot.path)
+  }
+}
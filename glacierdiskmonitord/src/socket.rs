@@ -0,0 +1,77 @@
+use std::{
+  error::Error,
+  fs::{self, Permissions},
+  io::Write,
+  os::unix::{
+    fs::{chown, PermissionsExt},
+    net::{UnixListener, UnixStream},
+  },
+  path::Path,
+  sync::{Arc, Mutex},
+  thread,
+  time::Duration,
+};
+
+use shared::monitor::{self, MonitorState};
+
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serve the state on a Unix socket in the background. Each client gets the state as JSON, then the connection is closed.
+pub fn serve(
+  path: impl AsRef<Path>,
+  mode: u32,
+  group: Option<&str>,
+  state: Arc<Mutex<MonitorState>>,
+) -> Result<(), Box<dyn Error>> {
+  let path = path.as_ref();
+
+  if path.exists() {
+    if monitor::is_running(path) {
+      return Err(format!("Another monitor is already listening on {}", path.display()).into());
+    }
+
+    // Left over from a monitor that didn't shut down cleanly
+    fs::remove_file(path)?;
+  }
+
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+
+  let listener = UnixListener::bind(path)?;
+  fs::set_permissions(path, Permissions::from_mode(mode))?;
+
+  if let Some(group) = group {
+    let gid = uzers::get_group_by_name(group)
+      .ok_or_else(|| format!("No such group: {group}"))?
+      .gid();
+    chown(path, None, Some(gid))?;
+  }
+
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let result = stream
+        .map_err(|e| e.into())
+        .and_then(|stream| send_state(stream, &state));
+
+      if let Err(e) = result {
+        eprintln!("Error serving client: {e}");
+      }
+    }
+  });
+
+  Ok(())
+}
+
+fn send_state(mut stream: UnixStream, state: &Mutex<MonitorState>) -> Result<(), Box<dyn Error>> {
+  // Serialize before writing, so a slow client can't hold the lock
+  let json = {
+    let state = state.lock().map_err(|_| "State lock poisoned")?;
+    serde_json::to_string(&*state)?
+  };
+
+  stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+  stream.write_all(json.as_bytes())?;
+
+  Ok(())
+}
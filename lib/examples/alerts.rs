@@ -0,0 +1,55 @@
+use std::{thread, time::Duration};
+
+use libglacierdisk::{alert::AlertEngine, power::ReadPolicy, snapshot::DiskSnapshot};
+
+const RULES: &str = r#"
+[[rule]]
+name = "pending-sectors"
+metric = "attribute.197.raw"
+condition = ">"
+value = 0
+severity = "critical"
+
+[[rule]]
+name = "reallocated-increased"
+metric = "attribute.reallocated-sector-count"
+condition = "increased"
+severity = "warning"
+
+[[rule]]
+name = "hot"
+metric = "temperature"
+condition = ">"
+value = 55
+for = "10m"
+clear = 50
+severity = "warning"
+
+[[rule]]
+name = "low-health"
+metric = "health"
+condition = "<"
+value = 80
+severity = "warning"
+"#;
+
+fn main() {
+  sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+  let mut engine = AlertEngine::from_toml(RULES).expect("Failed to parse rules");
+
+  loop {
+    let disks = libglacierdisk::list_disks_with_policy(ReadPolicy::SkipIfSleeping)
+      .expect("Failed to get list of disks");
+
+    for mut disk in disks {
+      let snapshot = DiskSnapshot::capture(&mut disk);
+
+      for event in engine.evaluate(&snapshot) {
+        println!("{}", event);
+      }
+    }
+
+    thread::sleep(Duration::from_secs(60));
+  }
+}
@@ -11,7 +11,7 @@ fn main() {
   // Assume the first mount is fine
   let mounts = disk.mounts().expect("Failed to get mounts");
   let mount = mounts.first().unwrap();
-  let bin_file = format!("{}/test.bin", mount.to_path_buf().to_str().unwrap());
+  let bin_file = format!("{}/test.bin", mount.mount_point.to_str().unwrap());
 
   // Write benchmark
   let mut write_benchmark = GlacierDiskBenchmark::new(
@@ -0,0 +1,14 @@
+fn main() {
+  sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+  // Rated TBW for the drive, from the manufacturer's spec sheet
+  let rated_tbw = std::env::args().nth(1).and_then(|tbw| tbw.parse::<f64>().ok());
+
+  let disks = libglacierdisk::list_disks().expect("Failed to get list of disks");
+  for mut disk in disks {
+    match disk.endurance(rated_tbw) {
+      Ok(endurance) => println!("{}: {:#?}", disk, endurance),
+      Err(e) => println!("{}: {}", disk, e),
+    }
+  }
+}
@@ -0,0 +1,17 @@
+fn main() {
+  sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+  let disks = libglacierdisk::list_disks().expect("Failed to get list of disks");
+  for mut disk in disks {
+    match disk.health() {
+      Ok(health) => {
+        println!("{}: {}", disk, health);
+
+        for reason in health.reasons {
+          println!("  {}", reason);
+        }
+      }
+      Err(e) => println!("{}: {}", disk, e),
+    }
+  }
+}
@@ -0,0 +1,26 @@
+use libglacierdisk::{history::HistoryStore, snapshot::DiskSnapshot};
+
+fn main() {
+  sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+  let store = HistoryStore::open("glacierdisk-history").expect("Failed to open history");
+  let disks = libglacierdisk::list_disks().expect("Failed to get list of disks");
+
+  for mut disk in disks {
+    let snapshot = DiskSnapshot::capture(&mut disk);
+    store.record(&snapshot).expect("Failed to record snapshot");
+
+    // Everything from the last day
+    let since = snapshot.timestamp.saturating_sub(60 * 60 * 24);
+    let history = store
+      .query(&snapshot.id, since..)
+      .expect("Failed to query history");
+
+    println!("{}: {} snapshots in the last day", disk, history.len());
+    for snapshot in history {
+      println!("  {}: {:?} °C", snapshot.timestamp, snapshot.temperature);
+    }
+  }
+
+  store.compact().expect("Failed to compact history");
+}
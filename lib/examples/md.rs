@@ -0,0 +1,35 @@
+fn main() {
+  sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+  let arrays = libglacierdisk::list_md_arrays().expect("Failed to read md arrays");
+
+  for array in arrays {
+    println!(
+      "{} ({}, {}){}",
+      array.name,
+      array.level.as_deref().unwrap_or("unknown"),
+      array.state,
+      if array.is_degraded() { " DEGRADED" } else { "" }
+    );
+
+    if let Some(sync) = &array.sync {
+      println!("  {} {:.1}%", sync.action, sync.progress());
+    }
+
+    for member in &array.members {
+      let health = match member.open_disk().map(|mut disk| disk.health()) {
+        Ok(Ok(health)) => health.to_string(),
+        Ok(Err(e)) | Err(e) => e.to_string(),
+      };
+
+      println!(
+        "  {} {:?} [{}] on {}: {}",
+        member.name,
+        member.role,
+        member.state.join(","),
+        member.disk.display(),
+        health
+      );
+    }
+  }
+}
@@ -0,0 +1,13 @@
+use libglacierdisk::{metrics, snapshot::DiskSnapshot};
+
+fn main() {
+  sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+  let snapshots = libglacierdisk::list_disks()
+    .expect("Failed to get list of disks")
+    .iter_mut()
+    .map(DiskSnapshot::capture)
+    .collect::<Vec<DiskSnapshot>>();
+
+  print!("{}", metrics::render(&snapshots));
+}
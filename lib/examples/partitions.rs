@@ -0,0 +1,57 @@
+use std::fs::File;
+
+use libglacierdisk::{
+  disk::Disk,
+  filesystem,
+  partition::{Partition, PartitionTable},
+};
+
+fn main() {
+  // Works on disk images too, eg. `cargo run --example partitions disk.img`
+  let path = std::env::args().nth(1).unwrap_or("/dev/sda".to_string());
+  let is_image = std::fs::metadata(&path).is_ok_and(|meta| meta.is_file());
+
+  let partitions = if is_image {
+    let mut file = File::open(&path).expect("Failed to open image");
+
+    match PartitionTable::read(&mut file, 512).expect("Failed to read partition table") {
+      Some(table) => {
+        println!("{} partition table ({:?})", table.kind, table.id);
+        table.partitions
+      }
+      None => {
+        // No partition table, but maybe a filesystem
+        let filesystem = filesystem::probe(&mut file, 0).expect("Failed to probe image");
+        println!("No partition table: {:?}", filesystem);
+        return;
+      }
+    }
+  } else {
+    sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+    let disk = Disk::new(path.into()).expect("Failed to get Disk");
+    disk.partitions().expect("Failed to read partitions")
+  };
+
+  for partition in partitions {
+    print_partition(&partition);
+  }
+}
+
+fn print_partition(partition: &Partition) {
+  println!(
+    "#{} {:?}: start {} size {} ({} MiB), {} {:?}",
+    partition.number,
+    partition.path,
+    partition.start,
+    partition.size,
+    partition.size_bytes() / 1024 / 1024,
+    partition.type_name().unwrap_or("unknown type"),
+    partition.name
+  );
+
+  match &partition.filesystem {
+    Some(fs) => println!("  {} label={:?} uuid={:?}", fs.kind, fs.label, fs.uuid),
+    None => println!("  no filesystem"),
+  }
+}
@@ -0,0 +1,17 @@
+use libglacierdisk::power::ReadPolicy;
+
+fn main() {
+  sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+  // Don't wake up any drives that are already asleep
+  let disks = libglacierdisk::list_disks_with_policy(ReadPolicy::SkipIfSleeping)
+    .expect("Failed to get list of disks");
+
+  for disk in disks {
+    println!("{}: {:?}", disk, disk.power_state());
+
+    if !disk.is_sleeping() {
+      println!("  {:?}", disk.power_features());
+    }
+  }
+}
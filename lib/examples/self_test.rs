@@ -0,0 +1,24 @@
+use libglacierdisk::selftest::SelfTestKind;
+
+fn main() {
+  sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+  let disks = libglacierdisk::list_disks().expect("Failed to get list of disks");
+
+  for disk in disks {
+    let status = match disk.self_test_status() {
+      Ok(status) => status,
+      Err(e) => {
+        println!("{}: {}", disk, e);
+        continue;
+      }
+    };
+
+    println!("{}: last self-test {}", disk, status.result);
+    for kind in SelfTestKind::ALL {
+      if let Some(duration) = status.duration(kind) {
+        println!("  {} takes about {} minutes", kind, duration.as_secs() / 60);
+      }
+    }
+  }
+}
@@ -0,0 +1,29 @@
+use libglacierdisk::scan::{ScanConfig, SurfaceScan};
+
+fn main() {
+  sudo::escalate_if_needed().expect("Failed to escalate privileges");
+
+  let path = std::env::args().nth(1).unwrap_or("/dev/sda".to_string());
+  let mut scan = SurfaceScan::new(&path, ScanConfig::default()).expect("Failed to open disk");
+
+  let result = scan
+    .run(|progress| {
+      print!(
+        "\r{:.1}% ({:.2} MB/s, {} bad)",
+        progress.percent(),
+        progress.throughput / 1024. / 1024.,
+        progress.bad_sectors
+      );
+    })
+    .expect("Failed to scan disk");
+
+  println!();
+  println!(
+    "Scanned {} bytes in {:?}",
+    result.bytes_scanned, result.elapsed
+  );
+
+  for bad in result.bad_sectors {
+    println!("Bad sector at LBA {} ({:?})", bad.lba, bad.partition);
+  }
+}
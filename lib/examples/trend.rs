@@ -0,0 +1,18 @@
+use libglacierdisk::{history::HistoryStore, trend};
+
+fn main() {
+  let store = HistoryStore::open("glacierdisk-history").expect("Failed to open history");
+
+  for id in store.disks().expect("Failed to list disks with history") {
+    let history = store.query(&id, ..).expect("Failed to query history");
+
+    // Warn at 100 bad sectors, or 10% life remaining
+    let forecasts = trend::forecast_all(&history, 100., 10.);
+
+    println!("{}: {} snapshots", id, history.len());
+    for forecast in forecasts {
+      let flag = if forecast.accelerating { "!" } else { " " };
+      println!(" {flag} {forecast}");
+    }
+  }
+}
@@ -0,0 +1,973 @@
+use std::{
+  collections::HashMap,
+  error::Error,
+  fmt::Display,
+  path::{Path, PathBuf},
+  str::FromStr,
+  time::Duration,
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+  health::HealthStatus,
+  nvme::NvmeSmartLog,
+  snapshot::{DiskSnapshot, SnapshotAttribute},
+  sysfs::DiskStat,
+};
+
+/// How bad an alert is
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  Info,
+  Warning,
+  Critical,
+}
+
+impl Display for Severity {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Severity::Info => write!(f, "Info"),
+      Severity::Warning => write!(f, "Warning"),
+      Severity::Critical => write!(f, "Critical"),
+    }
+  }
+}
+
+/// Which part of attribute to compare
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AttributeField {
+  Raw,
+  Current,
+  Worst,
+  Threshold,
+}
+
+/// Something that can be read out of a [`DiskSnapshot`]
+#[derive(Clone, PartialEq, Debug)]
+pub enum Metric {
+  /// Temperature in Celsius
+  Temperature,
+  /// Health score from 0 to 100
+  Health,
+  /// Health verdict, as 0 (Good), 1 (Caution) or 2 (Bad)
+  HealthStatus,
+  PowerOnHours,
+  /// A SMART attribute, by ID
+  Attribute(u8, AttributeField),
+  /// A SMART attribute, by name (e.g. `reallocated-sector-count`)
+  AttributeName(String, AttributeField),
+  /// A field of the NVMe health log (e.g. `media_errors`)
+  Nvme(String),
+  /// A field of the kernel's disk stats (e.g. `in_flight`)
+  Stat(String),
+}
+
+impl Metric {
+  /// Read this metric from a snapshot, if it's available
+  pub fn value(&self, snapshot: &DiskSnapshot) -> Option<f64> {
+    let field = |attribute: &SnapshotAttribute, field: &AttributeField| {
+      (match field {
+        AttributeField::Raw => attribute.raw,
+        AttributeField::Current => attribute.current as u64,
+        AttributeField::Worst => attribute.worst as u64,
+        AttributeField::Threshold => attribute.threshold as u64,
+      }) as f64
+    };
+
+    match self {
+      Metric::Temperature => snapshot.temperature,
+      Metric::Health => snapshot.health.map(|h| h as f64),
+      Metric::HealthStatus => snapshot.health_status.map(|status| match status {
+        HealthStatus::Good => 0.,
+        HealthStatus::Caution => 1.,
+        HealthStatus::Bad => 2.,
+      }),
+      Metric::PowerOnHours => snapshot.power_on_hours.map(|h| h as f64),
+      Metric::Attribute(id, f) => snapshot.attribute(*id).map(|a| field(a, f)),
+      Metric::AttributeName(name, f) => snapshot.attribute_by_name(name).map(|a| field(a, f)),
+      Metric::Nvme(name) => snapshot
+        .nvme
+        .as_ref()
+        .and_then(|log| nvme_field(log, name))
+        .map(|v| v as f64),
+      Metric::Stat(name) => snapshot
+        .stat
+        .as_ref()
+        .and_then(|stat| stat_field(stat, name))
+        .map(|v| v as f64),
+    }
+  }
+
+  /// Format a value of this metric for messages, e.g. `"Caution"` for [`Metric::HealthStatus`]
+  pub fn format_value(&self, value: f64) -> String {
+    match self {
+      Metric::HealthStatus => match value as u8 {
+        0 => HealthStatus::Good.to_string(),
+        1 => HealthStatus::Caution.to_string(),
+        _ => HealthStatus::Bad.to_string(),
+      },
+      _ => value.to_string(),
+    }
+  }
+}
+
+impl FromStr for Metric {
+  type Err = Box<dyn Error>;
+
+  /// Parse a metric, e.g. `temperature`, `health`, `health_status`, `attribute.197.raw`, `attribute.reallocated-sector-count`,
+  /// `nvme.media_errors` or `stat.in_flight`. Attribute fields default to `raw`.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let parts = s.split('.').collect::<Vec<&str>>();
+
+    match parts.as_slice() {
+      ["temperature"] => Ok(Metric::Temperature),
+      ["health"] => Ok(Metric::Health),
+      ["health_status"] => Ok(Metric::HealthStatus),
+      ["power_on_hours"] => Ok(Metric::PowerOnHours),
+      ["attribute", attribute, rest @ ..] => {
+        let field = match rest {
+          [] | ["raw"] => AttributeField::Raw,
+          ["current"] | ["value"] => AttributeField::Current,
+          ["worst"] => AttributeField::Worst,
+          ["threshold"] => AttributeField::Threshold,
+          _ => return Err(format!("Unknown attribute field in metric: {s}").into()),
+        };
+
+        match attribute.parse::<u8>() {
+          Ok(id) => Ok(Metric::Attribute(id, field)),
+          Err(_) => Ok(Metric::AttributeName(attribute.to_string(), field)),
+        }
+      }
+      ["nvme", field] if nvme_field(&NvmeSmartLog::default(), field).is_some() => {
+        Ok(Metric::Nvme(field.to_string()))
+      }
+      ["stat", field] if stat_field(&DiskStat::default(), field).is_some() => {
+        Ok(Metric::Stat(field.to_string()))
+      }
+      _ => Err(format!("Unknown metric: {s}").into()),
+    }
+  }
+}
+
+impl Display for Metric {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let field = |field: &AttributeField| match field {
+      AttributeField::Raw => "raw",
+      AttributeField::Current => "current",
+      AttributeField::Worst => "worst",
+      AttributeField::Threshold => "threshold",
+    };
+
+    match self {
+      Metric::Temperature => write!(f, "temperature"),
+      Metric::Health => write!(f, "health"),
+      Metric::HealthStatus => write!(f, "health_status"),
+      Metric::PowerOnHours => write!(f, "power_on_hours"),
+      Metric::Attribute(id, fld) => write!(f, "attribute.{id}.{}", field(fld)),
+      Metric::AttributeName(name, fld) => write!(f, "attribute.{name}.{}", field(fld)),
+      Metric::Nvme(name) => write!(f, "nvme.{name}"),
+      Metric::Stat(name) => write!(f, "stat.{name}"),
+    }
+  }
+}
+
+/// How to compare a metric against a rule's value
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Condition {
+  #[serde(rename = ">")]
+  GreaterThan,
+  #[serde(rename = ">=")]
+  GreaterOrEqual,
+  #[serde(rename = "<")]
+  LessThan,
+  #[serde(rename = "<=")]
+  LessOrEqual,
+  #[serde(rename = "==")]
+  Equal,
+  #[serde(rename = "!=")]
+  NotEqual,
+  /// The metric went up by more than the rule's value since the last check
+  #[serde(rename = "increased")]
+  Increased,
+  /// The metric changed by more than the rule's value since the last check
+  #[serde(rename = "changed")]
+  Changed,
+}
+
+impl Condition {
+  fn test(&self, value: f64, threshold: f64, previous: Option<f64>) -> bool {
+    match self {
+      Condition::GreaterThan => value > threshold,
+      Condition::GreaterOrEqual => value >= threshold,
+      Condition::LessThan => value < threshold,
+      Condition::LessOrEqual => value <= threshold,
+      Condition::Equal => value == threshold,
+      Condition::NotEqual => value != threshold,
+      Condition::Increased => previous.is_some_and(|previous| value - previous > threshold),
+      Condition::Changed => previous.is_some_and(|previous| (value - previous).abs() > threshold),
+    }
+  }
+
+  /// Whether this condition describes a one-off change rather than an ongoing state
+  pub fn is_change(&self) -> bool {
+    matches!(self, Condition::Increased | Condition::Changed)
+  }
+}
+
+impl Display for Condition {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Condition::GreaterThan => write!(f, ">"),
+      Condition::GreaterOrEqual => write!(f, ">="),
+      Condition::LessThan => write!(f, "<"),
+      Condition::LessOrEqual => write!(f, "<="),
+      Condition::Equal => write!(f, "=="),
+      Condition::NotEqual => write!(f, "!="),
+      Condition::Increased => write!(f, "increased by more than"),
+      Condition::Changed => write!(f, "changed by more than"),
+    }
+  }
+}
+
+/// A single alert rule, for example:
+///
+/// ```toml
+/// [[rule]]
+/// name = "hot"
+/// metric = "temperature"
+/// condition = ">"
+/// value = 55
+/// for = "10m"
+/// clear = 50
+/// severity = "warning"
+/// ```
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct AlertRule {
+  pub name: String,
+  #[serde(deserialize_with = "deserialize_metric")]
+  pub metric: Metric,
+  pub condition: Condition,
+  /// The value to compare against. Defaults to 0, which is handy for `increased` and `changed`.
+  #[serde(default)]
+  pub value: f64,
+  pub severity: Severity,
+  /// How long the condition has to hold before the alert fires (e.g. `"10m"`, `"30s"`, `"1h"`)
+  #[serde(default, rename = "for", deserialize_with = "deserialize_duration")]
+  pub duration: Option<Duration>,
+  /// Hysteresis. Once fired, the alert only clears when the condition no longer holds against this value instead of `value`.
+  #[serde(default)]
+  pub clear: Option<f64>,
+  /// Custom message for fired alerts
+  #[serde(default)]
+  pub message: Option<String>,
+  /// Only apply to these disks, by stable ID or path. Applies to all disks if empty.
+  #[serde(default)]
+  pub disks: Vec<String>,
+}
+
+impl AlertRule {
+  fn applies_to(&self, snapshot: &DiskSnapshot) -> bool {
+    self.disks.is_empty()
+      || self
+        .disks
+        .iter()
+        .any(|disk| *disk == snapshot.id || Path::new(disk) == snapshot.path)
+  }
+
+  fn message(&self, value: f64, previous: Option<f64>) -> String {
+    if let Some(message) = &self.message {
+      return message.clone();
+    }
+
+    match previous {
+      Some(previous) if self.condition.is_change() => format!(
+        "{} changed from {} to {}",
+        self.metric,
+        self.metric.format_value(previous),
+        self.metric.format_value(value)
+      ),
+      _ => format!(
+        "{} is {} ({} {})",
+        self.metric,
+        self.metric.format_value(value),
+        self.condition,
+        self.value
+      ),
+    }
+  }
+}
+
+/// An alert for a specific rule and disk
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Alert {
+  pub rule: String,
+  pub severity: Severity,
+  /// Stable ID of the disk
+  pub disk: String,
+  pub path: PathBuf,
+  /// The value of the metric when the event happened
+  pub value: f64,
+  pub message: String,
+  /// Seconds since the Unix epoch
+  pub timestamp: u64,
+}
+
+/// A change in an alert's state
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum AlertEvent {
+  Fired(Alert),
+  Cleared(Alert),
+}
+
+impl AlertEvent {
+  pub fn alert(&self) -> &Alert {
+    match self {
+      AlertEvent::Fired(alert) | AlertEvent::Cleared(alert) => alert,
+    }
+  }
+}
+
+impl Display for AlertEvent {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AlertEvent::Fired(alert) => write!(
+        f,
+        "[{}] {} on {}: {}",
+        alert.severity,
+        alert.rule,
+        alert.path.display(),
+        alert.message
+      ),
+      AlertEvent::Cleared(alert) => write!(
+        f,
+        "[Cleared] {} on {}: {}",
+        alert.rule,
+        alert.path.display(),
+        alert.message
+      ),
+    }
+  }
+}
+
+#[derive(Clone, Default, Debug)]
+struct RuleState {
+  /// When the condition started holding, for rules with a duration
+  pending_since: Option<u64>,
+  active: Option<Alert>,
+  last_value: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct RuleFile {
+  #[serde(default, rename = "rule")]
+  rules: Vec<AlertRule>,
+}
+
+/// Evaluates [`AlertRule`]s against a stream of [`DiskSnapshot`]s, remembering which alerts are active
+#[derive(Clone, Default, Debug)]
+pub struct AlertEngine {
+  pub rules: Vec<AlertRule>,
+  /// Keyed by (disk ID, rule name)
+  state: HashMap<(String, String), RuleState>,
+}
+
+impl AlertEngine {
+  pub fn new(rules: Vec<AlertRule>) -> Self {
+    Self {
+      rules,
+      state: HashMap::new(),
+    }
+  }
+
+  /// Parse rules from TOML, written as a `[[rule]]` array
+  pub fn from_toml(toml: impl AsRef<str>) -> Result<Self, Box<dyn Error>> {
+    let file: RuleFile = toml::from_str(toml.as_ref())?;
+    let mut names = file.rules.iter().map(|r| &r.name).collect::<Vec<&String>>();
+    names.sort();
+    names.dedup();
+
+    if names.len() != file.rules.len() {
+      return Err("Alert rule names must be unique".into());
+    }
+
+    Ok(Self::new(file.rules))
+  }
+
+  /// Load rules from a TOML file
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+    Self::from_toml(std::fs::read_to_string(path)?)
+  }
+
+  /// Evaluate every rule against a new snapshot, returning alerts that fired or cleared since the last snapshot of the same disk.
+  /// Metrics that are missing from the snapshot (eg. the disk is asleep) leave the alert's state untouched.
+  pub fn evaluate(&mut self, snapshot: &DiskSnapshot) -> Vec<AlertEvent> {
+    let mut events = vec![];
+
+    for rule in self.rules.iter().filter(|rule| rule.applies_to(snapshot)) {
+      let Some(value) = rule.metric.value(snapshot) else {
+        continue;
+      };
+
+      let state = self
+        .state
+        .entry((snapshot.id.clone(), rule.name.clone()))
+        .or_default();
+      let previous = state.last_value.replace(value);
+
+      let alert = || Alert {
+        rule: rule.name.clone(),
+        severity: rule.severity,
+        disk: snapshot.id.clone(),
+        path: snapshot.path.clone(),
+        value,
+        message: rule.message(value, previous),
+        timestamp: snapshot.timestamp,
+      };
+
+      // Changes are one-off events, so every further change fires again, and they clear quietly as soon as the metric
+      // stops changing
+      if state.active.is_some() && rule.condition.is_change() {
+        if rule.condition.test(value, rule.value, previous) {
+          let alert = alert();
+          state.active = Some(alert.clone());
+          events.push(AlertEvent::Fired(alert));
+        } else {
+          state.active = None;
+          state.pending_since = None;
+        }
+
+        continue;
+      }
+
+      if state.active.is_some() {
+        let threshold = rule.clear.unwrap_or(rule.value);
+        if !rule.condition.test(value, threshold, previous) {
+          state.active = None;
+          state.pending_since = None;
+
+          events.push(AlertEvent::Cleared(Alert {
+            message: format!(
+              "{} is back to {}",
+              rule.metric,
+              rule.metric.format_value(value)
+            ),
+            ..alert()
+          }));
+        }
+
+        continue;
+      }
+
+      if !rule.condition.test(value, rule.value, previous) {
+        state.pending_since = None;
+        continue;
+      }
+
+      let since = *state.pending_since.get_or_insert(snapshot.timestamp);
+      let held = Duration::from_secs(snapshot.timestamp.saturating_sub(since));
+
+      if rule.duration.is_none_or(|duration| held >= duration) {
+        let alert = alert();
+        state.active = Some(alert.clone());
+        events.push(AlertEvent::Fired(alert));
+      }
+    }
+
+    events
+  }
+
+  /// All currently active alerts
+  pub fn active(&self) -> Vec<&Alert> {
+    self
+      .state
+      .values()
+      .filter_map(|state| state.active.as_ref())
+      .collect()
+  }
+}
+
+/// Parse a duration like `"90s"`, `"10m"`, `"1h"`, `"1d"`, or a plain number of seconds
+pub fn parse_duration(s: impl AsRef<str>) -> Result<Duration, Box<dyn Error>> {
+  let s = s.as_ref().trim();
+  let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+  let number = number
+    .parse::<u64>()
+    .map_err(|_| format!("Invalid duration: {s}"))?;
+
+  let multiplier = match unit.trim() {
+    "" | "s" => 1,
+    "m" => 60,
+    "h" => 60 * 60,
+    "d" => 60 * 60 * 24,
+    _ => return Err(format!("Invalid duration unit: {s}").into()),
+  };
+
+  let secs = number
+    .checked_mul(multiplier)
+    .ok_or_else(|| format!("Duration is too long: {s}"))?;
+
+  Ok(Duration::from_secs(secs))
+}
+
+fn nvme_field(log: &NvmeSmartLog, name: &str) -> Option<u128> {
+  Some(match name {
+    "critical_warning" => log.critical_warning as u128,
+    "temperature" => log.temperature as u128,
+    "available_spare" => log.available_spare as u128,
+    "available_spare_threshold" => log.available_spare_threshold as u128,
+    "percentage_used" => log.percentage_used as u128,
+    "data_units_read" => log.data_units_read,
+    "data_units_written" => log.data_units_written,
+    "power_cycles" => log.power_cycles,
+    "power_on_hours" => log.power_on_hours,
+    "unsafe_shutdowns" => log.unsafe_shutdowns,
+    "media_errors" => log.media_errors,
+    "error_log_entries" => log.error_log_entries,
+    _ => return None,
+  })
+}
+
+fn stat_field(stat: &DiskStat, name: &str) -> Option<u64> {
+  Some(match name {
+    "read_io" => stat.read_io,
+    "write_io" => stat.write_io,
+    "discard_io" => stat.discard_io,
+    "read_sectors" => stat.read_sectors,
+    "write_sectors" => stat.write_sectors,
+    "discard_sectors" => stat.discard_sectors,
+    "read_ticks" => stat.read_ticks,
+    "write_ticks" => stat.write_ticks,
+    "in_flight" => stat.in_flight,
+    "io_ticks" => stat.io_ticks,
+    "time_in_queue" => stat.time_in_queue,
+    _ => return None,
+  })
+}
+
+fn deserialize_metric<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Metric, D::Error> {
+  let s = String::deserialize(deserializer)?;
+  s.parse().map_err(serde::de::Error::custom)
+}
+
+/// Deserialize an optional duration written like `"10m"` or as a number of seconds, for `#[serde(deserialize_with)]`
+pub fn deserialize_duration<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Raw {
+    Secs(u64),
+    Text(String),
+  }
+
+  match Option::<Raw>::deserialize(deserializer)? {
+    None => Ok(None),
+    Some(Raw::Secs(secs)) => Ok(Some(Duration::from_secs(secs))),
+    Some(Raw::Text(text)) => parse_duration(text)
+      .map(Some)
+      .map_err(serde::de::Error::custom),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const RULES: &str = r#"
+    [[rule]]
+    name = "hot"
+    metric = "temperature"
+    condition = ">"
+    value = 55
+    clear = 50
+    severity = "warning"
+
+    [[rule]]
+    name = "pending"
+    metric = "attribute.197"
+    condition = "increased"
+    severity = "critical"
+
+    [[rule]]
+    name = "busy"
+    metric = "stat.in_flight"
+    condition = ">="
+    value = 10
+    for = "5m"
+    severity = "info"
+    disks = ["disk-a"]
+  "#;
+
+  fn snapshot(id: &str, timestamp: u64) -> DiskSnapshot {
+    DiskSnapshot {
+      id: id.to_string(),
+      path: PathBuf::from(format!("/dev/{id}")),
+      timestamp,
+      ..Default::default()
+    }
+  }
+
+  fn hot(temperature: f64) -> DiskSnapshot {
+    DiskSnapshot {
+      temperature: Some(temperature),
+      ..snapshot("disk-a", 0)
+    }
+  }
+
+  fn pending(timestamp: u64, raw: u64) -> DiskSnapshot {
+    DiskSnapshot {
+      attributes: vec![SnapshotAttribute {
+        id: 197,
+        name: "current-pending-sector".to_string(),
+        raw,
+        ..Default::default()
+      }],
+      ..snapshot("disk-a", timestamp)
+    }
+  }
+
+  fn busy(id: &str, timestamp: u64, in_flight: u64) -> DiskSnapshot {
+    DiskSnapshot {
+      stat: Some(DiskStat {
+        in_flight,
+        ..Default::default()
+      }),
+      ..snapshot(id, timestamp)
+    }
+  }
+
+  /// The events for each snapshot in turn, as `("fired" | "cleared", rule)`
+  fn run(engine: &mut AlertEngine, snapshots: &[DiskSnapshot]) -> Vec<Vec<(&'static str, String)>> {
+    snapshots
+      .iter()
+      .map(|snapshot| {
+        engine
+          .evaluate(snapshot)
+          .into_iter()
+          .map(|event| match event {
+            AlertEvent::Fired(alert) => ("fired", alert.rule),
+            AlertEvent::Cleared(alert) => ("cleared", alert.rule),
+          })
+          .collect()
+      })
+      .collect()
+  }
+
+  fn fired(rule: &str) -> Vec<(&'static str, String)> {
+    vec![("fired", rule.to_string())]
+  }
+
+  fn cleared(rule: &str) -> Vec<(&'static str, String)> {
+    vec![("cleared", rule.to_string())]
+  }
+
+  #[test]
+  fn hysteresis() {
+    let mut engine = AlertEngine::from_toml(RULES).unwrap();
+    let events = run(
+      &mut engine,
+      &[
+        hot(50.),
+        hot(56.),
+        hot(60.),
+        hot(52.),
+        hot(51.),
+        hot(50.),
+        hot(55.),
+        hot(56.),
+      ],
+    );
+
+    assert_eq!(
+      events,
+      vec![
+        vec![],
+        fired("hot"),
+        // Already firing
+        vec![],
+        // Under the rule's value, but not the clear value yet
+        vec![],
+        vec![],
+        cleared("hot"),
+        // Not over the rule's value
+        vec![],
+        fired("hot"),
+      ]
+    );
+    assert_eq!(engine.active().len(), 1);
+  }
+
+  #[test]
+  fn missing_metric_keeps_state() {
+    let mut engine = AlertEngine::from_toml(RULES).unwrap();
+    let asleep = DiskSnapshot {
+      sleeping: true,
+      ..snapshot("disk-a", 0)
+    };
+
+    let events = run(&mut engine, &[hot(60.), asleep, hot(40.)]);
+    assert_eq!(events, vec![fired("hot"), vec![], cleared("hot")]);
+  }
+
+  #[test]
+  fn duration() {
+    let mut engine = AlertEngine::from_toml(RULES).unwrap();
+    let events = run(
+      &mut engine,
+      &[
+        busy("disk-a", 0, 10),
+        busy("disk-a", 200, 12),
+        // Dropping out resets the timer
+        busy("disk-a", 250, 2),
+        busy("disk-a", 300, 10),
+        busy("disk-a", 500, 10),
+        busy("disk-a", 600, 11),
+        busy("disk-a", 900, 0),
+      ],
+    );
+
+    assert_eq!(
+      events,
+      vec![
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        fired("busy"),
+        cleared("busy"),
+      ]
+    );
+  }
+
+  #[test]
+  fn disk_filter() {
+    let mut engine = AlertEngine::from_toml(RULES).unwrap();
+    let events = run(
+      &mut engine,
+      &[busy("disk-b", 0, 50), busy("disk-b", 600, 50)],
+    );
+    assert_eq!(events, vec![vec![], vec![]]);
+
+    // By path too
+    engine.rules[2].disks = vec!["/dev/disk-b".to_string()];
+    let events = run(
+      &mut engine,
+      &[busy("disk-b", 0, 50), busy("disk-b", 600, 50)],
+    );
+    assert_eq!(events, vec![vec![], fired("busy")]);
+  }
+
+  #[test]
+  fn disks_are_separate() {
+    let mut engine = AlertEngine::from_toml(RULES).unwrap();
+    let disk_b = |temperature| DiskSnapshot {
+      temperature: Some(temperature),
+      ..snapshot("disk-b", 0)
+    };
+
+    let events = run(&mut engine, &[hot(60.), disk_b(40.), disk_b(60.), hot(40.)]);
+    assert_eq!(
+      events,
+      vec![fired("hot"), vec![], fired("hot"), cleared("hot")]
+    );
+    assert_eq!(engine.active()[0].disk, "disk-b");
+  }
+
+  #[test]
+  fn changes() {
+    let mut engine = AlertEngine::from_toml(RULES).unwrap();
+    let events = run(
+      &mut engine,
+      &[
+        pending(0, 0),
+        pending(1, 0),
+        pending(2, 1),
+        // Every further increase fires again
+        pending(3, 3),
+        // Clears quietly once it stops going up
+        pending(4, 3),
+        pending(5, 2),
+        pending(6, 4),
+      ],
+    );
+
+    assert_eq!(
+      events,
+      vec![
+        // Nothing to compare against
+        vec![],
+        vec![],
+        fired("pending"),
+        fired("pending"),
+        vec![],
+        vec![],
+        fired("pending"),
+      ]
+    );
+
+    let event = engine.evaluate(&pending(7, 10)).remove(0);
+    assert_eq!(
+      event.alert().message,
+      "attribute.197.raw changed from 4 to 10"
+    );
+  }
+
+  #[test]
+  fn health_status() {
+    let mut engine = AlertEngine::from_toml(
+      r#"
+        [[rule]]
+        name = "health"
+        metric = "health_status"
+        condition = "changed"
+        severity = "warning"
+      "#,
+    )
+    .unwrap();
+    let status = |health_status| DiskSnapshot {
+      health_status: Some(health_status),
+      ..snapshot("disk-a", 0)
+    };
+
+    engine.evaluate(&status(HealthStatus::Good));
+    let caution = engine.evaluate(&status(HealthStatus::Caution));
+    let bad = engine.evaluate(&status(HealthStatus::Bad));
+
+    assert_eq!(
+      caution[0].alert().message,
+      "health_status changed from Good to Caution"
+    );
+    assert_eq!(
+      bad[0].alert().message,
+      "health_status changed from Caution to Bad"
+    );
+  }
+
+  #[test]
+  fn metrics() {
+    let cases = [
+      ("temperature", Metric::Temperature),
+      ("health", Metric::Health),
+      ("health_status", Metric::HealthStatus),
+      ("power_on_hours", Metric::PowerOnHours),
+      ("attribute.5", Metric::Attribute(5, AttributeField::Raw)),
+      ("attribute.5.raw", Metric::Attribute(5, AttributeField::Raw)),
+      (
+        "attribute.5.value",
+        Metric::Attribute(5, AttributeField::Current),
+      ),
+      (
+        "attribute.5.current",
+        Metric::Attribute(5, AttributeField::Current),
+      ),
+      (
+        "attribute.5.worst",
+        Metric::Attribute(5, AttributeField::Worst),
+      ),
+      (
+        "attribute.5.threshold",
+        Metric::Attribute(5, AttributeField::Threshold),
+      ),
+      (
+        "attribute.reallocated-sector-count",
+        Metric::AttributeName("reallocated-sector-count".to_string(), AttributeField::Raw),
+      ),
+      // Too big for an ID, so a name
+      (
+        "attribute.256",
+        Metric::AttributeName("256".to_string(), AttributeField::Raw),
+      ),
+      (
+        "nvme.media_errors",
+        Metric::Nvme("media_errors".to_string()),
+      ),
+      ("stat.in_flight", Metric::Stat("in_flight".to_string())),
+    ];
+
+    for (text, metric) in cases {
+      assert_eq!(text.parse::<Metric>().unwrap(), metric, "{text}");
+    }
+
+    for text in [
+      "",
+      "temp",
+      "attribute",
+      "attribute.5.normalized",
+      "attribute.5.raw.extra",
+      "nvme",
+      "nvme.bogus",
+      "nvme.media_errors.extra",
+      "stat.bogus",
+    ] {
+      assert!(text.parse::<Metric>().is_err(), "{text}");
+    }
+
+    // Round trips through Display
+    let metric = Metric::Attribute(197, AttributeField::Worst);
+    assert_eq!(metric.to_string().parse::<Metric>().unwrap(), metric);
+  }
+
+  #[test]
+  fn durations() {
+    let cases = [
+      ("0", 0),
+      ("90", 90),
+      ("90s", 90),
+      ("10m", 600),
+      ("2h", 7200),
+      ("1d", 86400),
+      (" 5 m ", 300),
+    ];
+
+    for (text, secs) in cases {
+      assert_eq!(
+        parse_duration(text).unwrap(),
+        Duration::from_secs(secs),
+        "{text}"
+      );
+    }
+
+    for text in [
+      "",
+      "m",
+      "-5m",
+      "1.5h",
+      "10w",
+      "99999999999999999999",
+      "999999999999999999d",
+    ] {
+      assert!(parse_duration(text).is_err(), "{text}");
+    }
+  }
+
+  #[test]
+  fn invalid_rules() {
+    let duplicate = r#"
+      [[rule]]
+      name = "hot"
+      metric = "temperature"
+      condition = ">"
+      severity = "info"
+
+      [[rule]]
+      name = "hot"
+      metric = "health"
+      condition = "<"
+      severity = "info"
+    "#;
+    assert!(AlertEngine::from_toml(duplicate).is_err());
+
+    let overflow = r#"
+      [[rule]]
+      name = "hot"
+      metric = "temperature"
+      condition = ">"
+      severity = "info"
+      for = "999999999999999999d"
+    "#;
+    assert!(AlertEngine::from_toml(overflow).is_err());
+  }
+}
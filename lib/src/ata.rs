@@ -1,4 +1,9 @@
-use std::{fs, path::Path};
+use std::{fs, os::unix::io::AsRawFd, path::Path};
+
+use crate::sgio::{ata_command, open_device, AtaProtocol, AtaTaskfile};
+
+const ATA_IDENTIFY_DEVICE: u8 = 0xec;
+const HDIO_GET_IDENTITY: libc::c_ulong = 0x030d;
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct DiskAtaLink {
@@ -71,3 +76,59 @@ impl DiskAtaLink {
     Err("No link found".into())
   }
 }
+
+/// The raw 256 words returned by IDENTIFY DEVICE
+#[derive(Clone, PartialEq, Debug)]
+pub struct AtaIdentify {
+  pub words: [u16; 256],
+}
+
+impl AtaIdentify {
+  /// Read IDENTIFY DEVICE data from a disk. Note that this may wake a drive in standby.
+  pub fn for_disk(disk: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+    let file = open_device(disk)?;
+    let mut data = [0u8; 512];
+    let taskfile = AtaTaskfile {
+      command: ATA_IDENTIFY_DEVICE,
+      count: 1,
+      ..Default::default()
+    };
+
+    if ata_command(&file, &taskfile, AtaProtocol::PioIn, &mut data).is_err() {
+      // Fall back to the kernel's cached copy
+      let ret = unsafe { libc::ioctl(file.as_raw_fd(), HDIO_GET_IDENTITY as _, data.as_mut_ptr()) };
+      if ret < 0 {
+        return Err(std::io::Error::last_os_error().into());
+      }
+    }
+
+    Ok(Self::from_bytes(&data))
+  }
+
+  /// Parse IDENTIFY DEVICE data from the raw 512 byte sector
+  pub fn from_bytes(data: &[u8; 512]) -> Self {
+    let mut words = [0u16; 256];
+
+    for (i, word) in words.iter_mut().enumerate() {
+      *word = u16::from_le_bytes([data[i * 2], data[i * 2 + 1]]);
+    }
+
+    Self { words }
+  }
+
+  /// Get a single bit of a word, treating words that are all 0s or all 1s as "not reported"
+  pub fn bit(&self, word: usize, bit: u8) -> bool {
+    let value = self.words[word];
+
+    if value == 0x0000 || value == 0xffff {
+      return false;
+    }
+
+    // Words 83, 84 and 87 are only valid if bits 15:14 are 01
+    if matches!(word, 83 | 84 | 87) && value >> 14 != 0b01 {
+      return false;
+    }
+
+    value & (1 << bit) != 0
+  }
+}
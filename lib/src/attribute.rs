@@ -82,6 +82,40 @@ impl Attribute {
     }
     s
   }
+
+  /// The full 48-bit raw value
+  pub fn raw_value(&self) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes[..6].copy_from_slice(&self.raw);
+    u64::from_le_bytes(bytes)
+  }
+
+  /// The raw value as decoded by libatasmart, in base units, along with the name of the unit. Percentages with decimals
+  /// are scaled to whole percent. Returns `None` if the unit is unknown.
+  pub fn decoded(&self) -> Option<(f64, &'static str)> {
+    let value = self.pretty_value as f64;
+
+    match self.pretty_unit {
+      SkSmartAttributeUnit::SK_SMART_ATTRIBUTE_UNIT_NONE => Some((value, "none")),
+      SkSmartAttributeUnit::SK_SMART_ATTRIBUTE_UNIT_MSECONDS => Some((value, "milliseconds")),
+      SkSmartAttributeUnit::SK_SMART_ATTRIBUTE_UNIT_SECTORS => Some((value, "sectors")),
+      SkSmartAttributeUnit::SK_SMART_ATTRIBUTE_UNIT_MKELVIN => Some((value, "millikelvin")),
+      SkSmartAttributeUnit::SK_SMART_ATTRIBUTE_UNIT_SMALL_PERCENT => {
+        Some((value / 1000., "percent"))
+      }
+      SkSmartAttributeUnit::SK_SMART_ATTRIBUTE_UNIT_PERCENT => Some((value, "percent")),
+      SkSmartAttributeUnit::SK_SMART_ATTRIBUTE_UNIT_MB => Some((
+        self.pretty_unit.convert_to_base(self.pretty_value) as f64,
+        "bytes",
+      )),
+      _ => None,
+    }
+  }
+
+  /// The raw value as a count (e.g. reallocated sectors). Some vendors pack extra data into the upper bytes, so only the lower 32 bits are used.
+  pub fn raw_count(&self) -> u64 {
+    self.raw_value() & 0xffff_ffff
+  }
 }
 
 /// Get an [`Attribute`] from a [`Disk`] by name
@@ -0,0 +1,105 @@
+use std::{
+  alloc::{self, Layout},
+  fs::{self, File, OpenOptions},
+  ops::{Deref, DerefMut},
+  os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+  path::Path,
+};
+
+use super::{BenchmarkType, IoMode};
+
+/// O_DIRECT needs buffers, offsets and sizes aligned to the logical block size. 4 KiB covers every disk we know of.
+pub const ALIGNMENT: usize = 4096;
+
+/// A zeroed buffer aligned to [`ALIGNMENT`], for O_DIRECT reads and writes
+pub struct AlignedBuf {
+  ptr: *mut u8,
+  layout: Layout,
+}
+
+impl AlignedBuf {
+  pub fn new(size: usize) -> Self {
+    let layout = Layout::from_size_align(size.max(1), ALIGNMENT).expect("Invalid buffer size");
+    let ptr = unsafe { alloc::alloc_zeroed(layout) };
+
+    if ptr.is_null() {
+      alloc::handle_alloc_error(layout);
+    }
+
+    Self { ptr, layout }
+  }
+}
+
+// It owns its allocation, like a `Box<[u8]>`
+unsafe impl Send for AlignedBuf {}
+
+impl Deref for AlignedBuf {
+  type Target = [u8];
+
+  fn deref(&self) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(self.ptr, self.layout.size()) }
+  }
+}
+
+impl DerefMut for AlignedBuf {
+  fn deref_mut(&mut self) -> &mut [u8] {
+    unsafe { std::slice::from_raw_parts_mut(self.ptr, self.layout.size()) }
+  }
+}
+
+impl Drop for AlignedBuf {
+  fn drop(&mut self) {
+    unsafe { alloc::dealloc(self.ptr, self.layout) };
+  }
+}
+
+/// Reopen the benchmark file with O_DIRECT. If the filesystem doesn't support it (tmpfs, some FUSE filesystems) or the
+/// block size isn't aligned, keep the buffered file and flush it from the page cache instead.
+pub fn reopen(
+  path: &Path,
+  kind: &BenchmarkType,
+  block_size: usize,
+  file: File,
+) -> Result<(File, IoMode), Box<dyn std::error::Error>> {
+  // A read benchmark's file was just filled through the page cache. O_DIRECT reads of pages that haven't been written
+  // back yet would either see stale blocks or wait on the writeback mid-benchmark.
+  file.sync_all()?;
+
+  if !block_size.is_multiple_of(ALIGNMENT) {
+    drop_cache(&file)?;
+    return Ok((file, IoMode::Uncached));
+  }
+
+  let direct = OpenOptions::new()
+    .read(matches!(kind, BenchmarkType::Read))
+    .write(matches!(kind, BenchmarkType::Write))
+    .custom_flags(libc::O_DIRECT)
+    .open(path);
+
+  match direct {
+    Ok(direct) => Ok((direct, IoMode::Direct)),
+    Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {
+      drop_cache(&file)?;
+      Ok((file, IoMode::Uncached))
+    }
+    Err(e) => Err(e.into()),
+  }
+}
+
+/// Write out and evict the file's pages, so the next read has to come from the disk
+pub fn drop_cache(file: &File) -> Result<(), Box<dyn std::error::Error>> {
+  // Dirty pages can't be evicted
+  file.sync_all()?;
+
+  let ret = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+  if ret != 0 {
+    let e = std::io::Error::from_raw_os_error(ret);
+    return Err(format!("posix_fadvise failed ({e})").into());
+  }
+
+  // Some filesystems ignore the hint, so also drop clean caches system-wide. This only works as root, and is harmless
+  // (if slow for everything else for a moment) when it does.
+  fs::write("/proc/sys/vm/drop_caches", "1").unwrap_or_default();
+
+  Ok(())
+}
@@ -0,0 +1,127 @@
+use std::{
+  fs::File,
+  io::Read,
+  os::unix::fs::FileExt,
+  sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+  thread,
+};
+
+use super::{direct::AlignedBuf, uring::UringEngine, BenchmarkType, Engine};
+
+/// Something that can read or write a list of blocks with a number of requests in flight. Each benchmark thread gets
+/// its own region of the file, calls [`IoEngine::prepare`] on it, and then [`Prepared::run`] once the clock starts.
+pub trait IoEngine: Sync {
+  /// Set up everything needed to read or write a `block_size` block at each offset with up to `queue_depth` requests
+  /// in flight, like filling buffers and setting up rings, so none of it is timed.
+  fn prepare<'a>(
+    &self,
+    file: &'a File,
+    kind: &BenchmarkType,
+    offsets: &'a [u64],
+    block_size: usize,
+    queue_depth: usize,
+  ) -> std::io::Result<Box<dyn Prepared + 'a>>;
+}
+
+/// One thread's region of the file, ready to be read or written
+pub trait Prepared {
+  /// Read or write every block. Returns the number of blocks read or written.
+  fn run(self: Box<Self>) -> std::io::Result<u64>;
+}
+
+/// Get the engine to use, falling back to [`SyncEngine`] if the kernel doesn't support the one asked for (or it's
+/// blocked, as io_uring often is in containers). Also returns which one that ended up being.
+pub fn select(engine: Engine) -> (Box<dyn IoEngine>, Engine) {
+  match engine {
+    Engine::IoUring if UringEngine::supported() => (Box::new(UringEngine), Engine::IoUring),
+    _ => (Box::new(SyncEngine), Engine::Sync),
+  }
+}
+
+/// Blocking `pread`/`pwrite`. Only one request can be in flight per OS thread, so each slot in the queue gets a thread.
+pub struct SyncEngine;
+
+impl IoEngine for SyncEngine {
+  fn prepare<'a>(
+    &self,
+    file: &'a File,
+    kind: &BenchmarkType,
+    offsets: &'a [u64],
+    block_size: usize,
+    queue_depth: usize,
+  ) -> std::io::Result<Box<dyn Prepared + 'a>> {
+    let mut buffers = (0..queue_depth.max(1))
+      .map(|_| AlignedBuf::new(block_size))
+      .collect::<Vec<AlignedBuf>>();
+
+    // Filled once up front like the io_uring engine's buffers, so the two are measuring the same thing
+    if matches!(kind, BenchmarkType::Write) {
+      let mut urand = File::open("/dev/urandom")?;
+      for buf in buffers.iter_mut() {
+        urand.read_exact(buf)?;
+      }
+    }
+
+    Ok(Box::new(SyncRun {
+      file,
+      kind: kind.clone(),
+      offsets,
+      buffers,
+    }))
+  }
+}
+
+struct SyncRun<'a> {
+  file: &'a File,
+  kind: BenchmarkType,
+  offsets: &'a [u64],
+  /// One for each thread
+  buffers: Vec<AlignedBuf>,
+}
+
+impl Prepared for SyncRun<'_> {
+  fn run(mut self: Box<Self>) -> std::io::Result<u64> {
+    let (file, kind, offsets) = (self.file, &self.kind, self.offsets);
+    let cursor = AtomicUsize::new(0);
+    let ops = AtomicU64::new(0);
+
+    thread::scope(|scope| -> std::io::Result<()> {
+      let workers = self
+        .buffers
+        .iter_mut()
+        .map(|buf| {
+          let (cursor, ops) = (&cursor, &ops);
+
+          scope.spawn(move || -> std::io::Result<()> {
+            // Take the next block, so the queue drains the region in order
+            while let Some(offset) = offsets.get(cursor.fetch_add(1, Ordering::Relaxed)) {
+              match kind {
+                BenchmarkType::Write => file.write_all_at(buf, *offset)?,
+                BenchmarkType::Read => {
+                  // A short file (eg. a custom `file_path`) just ends the benchmark early
+                  if file.read_at(buf, *offset)? == 0 {
+                    break;
+                  }
+                }
+              }
+
+              ops.fetch_add(1, Ordering::Relaxed);
+            }
+
+            Ok(())
+          })
+        })
+        .collect::<Vec<_>>();
+
+      for worker in workers {
+        worker
+          .join()
+          .map_err(|_| std::io::Error::other("Benchmark thread panicked"))??;
+      }
+
+      Ok(())
+    })?;
+
+    Ok(ops.into_inner())
+  }
+}
@@ -2,13 +2,22 @@ use std::{
   fmt::Debug,
   fs::{self, File, OpenOptions},
   io::{Read, Seek, SeekFrom, Write},
-  path::PathBuf,
+  path::{Path, PathBuf},
+  sync::Barrier,
+  thread,
   time::{Duration, Instant},
 };
 
 use rand::seq::SliceRandom;
 
-use crate::disk::ShallowDisk;
+use crate::{disk::ShallowDisk, mount};
+
+pub(crate) mod direct;
+mod engine;
+mod uring;
+
+pub use engine::{IoEngine, Prepared, SyncEngine};
+pub use uring::UringEngine;
 
 const FILENAME: &str = "glacierdisk-test.bin";
 
@@ -19,11 +28,59 @@ pub enum BenchmarkType {
   Write,
 }
 
+/// How the benchmark file was read or written
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IoMode {
+  /// Through the page cache. Reads of a file that was just written mostly measure RAM speed.
+  Buffered,
+  /// With O_DIRECT, bypassing the page cache
+  Direct,
+  /// O_DIRECT wasn't available, so the page cache was flushed with `posix_fadvise` and dropped before reading, and
+  /// writes were synced before the timer stopped
+  Uncached,
+}
+
+impl std::fmt::Display for IoMode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      IoMode::Buffered => write!(f, "buffered"),
+      IoMode::Direct => write!(f, "direct"),
+      IoMode::Uncached => write!(f, "uncached"),
+    }
+  }
+}
+
+/// How reads and writes are issued
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Engine {
+  /// Blocking `pread`/`pwrite`, with a thread per request in flight
+  #[default]
+  Sync,
+  /// io_uring, with the whole queue in flight from one thread. Falls back to `Sync` if the kernel doesn't support it.
+  IoUring,
+}
+
+impl std::fmt::Display for Engine {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Engine::Sync => write!(f, "sync"),
+      Engine::IoUring => write!(f, "io_uring"),
+    }
+  }
+}
+
 /// The result of a benchmark
 #[derive(Clone, Debug)]
 pub struct BenchmarkResult {
   pub elapsed: Duration,
+  /// Bytes per second, across all threads
   pub avg_speed: f64,
+  /// Blocks read or written per second, across all threads
+  pub iops: f64,
+  /// What was actually used, which may not be what was asked for
+  pub mode: IoMode,
+  /// Same as `mode`
+  pub engine: Engine,
 }
 
 /// The configuration for a benchmark. This nclues everything from the block size and count, to the kind of benchmark.
@@ -41,6 +98,15 @@ pub struct BenchmarkConfig {
   pub delete_after: bool,
   /// Whether this is a sequential benchmark or a random benchmark
   pub random: bool,
+  /// Bypass the page cache with O_DIRECT. Falls back to flushing the cache if the filesystem doesn't support it, or if
+  /// the block size isn't a multiple of 4 KiB.
+  pub direct: bool,
+  /// Requests each thread keeps in flight, e.g. 32 for CrystalDiskMark's "Q32T1"
+  pub queue_depth: usize,
+  /// Threads, each working on its own region of the file, e.g. 1 for "Q32T1"
+  pub threads: usize,
+  /// How reads and writes are issued. Deep queues need [`Engine::IoUring`] to avoid a thread per request.
+  pub engine: Engine,
 }
 
 impl Default for BenchmarkConfig {
@@ -54,6 +120,10 @@ impl Default for BenchmarkConfig {
       file_path: None,
       delete_after: true,
       random: false,
+      direct: false,
+      queue_depth: 1,
+      threads: 1,
+      engine: Engine::Sync,
     }
   }
 }
@@ -111,7 +181,7 @@ impl Benchmark for GlacierDiskBenchmark {
 
     Ok(Self {
       disk: disk.clone(),
-      mount: mount.to_path_buf(),
+      mount: mount.mount_point.clone(),
       running: false,
 
       bench_config,
@@ -126,13 +196,37 @@ impl Benchmark for GlacierDiskBenchmark {
       &self.disk,
     )?;
 
+    let mode = if self.bench_config.direct {
+      let (direct, mode) = direct::reopen(
+        &path,
+        &self.bench_config.kind,
+        self.bench_config.block_size,
+        file,
+      )?;
+      file = direct;
+      mode
+    } else {
+      IoMode::Buffered
+    };
+
+    let (io_engine, engine) = engine::select(self.bench_config.engine);
+
     self.running = true;
 
-    let elapsed = match self.bench_config.kind {
-      BenchmarkType::Read => perform_read_benchmark(&self.bench_config, &mut file)?,
-      BenchmarkType::Write => perform_write_benchmark(&self.bench_config, &mut file)?,
+    let (elapsed, ops) = match self.bench_config.kind {
+      BenchmarkType::Read => {
+        perform_read_benchmark(&self.bench_config, &mut file, io_engine.as_ref())?
+      }
+      BenchmarkType::Write => {
+        perform_write_benchmark(&self.bench_config, &mut file, io_engine.as_ref(), mode)?
+      }
     };
 
+    // Don't leave the written file in the cache for a following read benchmark
+    if mode == IoMode::Uncached && matches!(self.bench_config.kind, BenchmarkType::Write) {
+      direct::drop_cache(&file)?;
+    }
+
     // Cleanup
     if self.bench_config.delete_after {
       fs::remove_file(path).unwrap_or_default();
@@ -142,7 +236,10 @@ impl Benchmark for GlacierDiskBenchmark {
 
     Ok(BenchmarkResult {
       elapsed,
-      avg_speed: self.bench_config.total_size() as f64 / elapsed.as_secs_f64(),
+      avg_speed: (ops * self.bench_config.block_size as u64) as f64 / elapsed.as_secs_f64(),
+      iops: ops as f64 / elapsed.as_secs_f64(),
+      mode,
+      engine,
     })
   }
 }
@@ -157,15 +254,22 @@ fn get_file(
     path
   } else {
     let mounts = disk.mounts()?;
+    // Bind mounts and the like are often read-only, even when another mount of the same filesystem isn't
     let mount = mounts
-      .first()
-      .ok_or(format!("No mounts found for disk {disk:?}",))?;
-    let mount = mount.to_path_buf();
+      .iter()
+      .find(|mount| !mount.is_read_only())
+      .ok_or(format!("No writable mounts found for disk {disk:?}",))?;
+    let mount = mount.mount_point.clone();
     let file_name = format!("{}/{}", mount.to_str().unwrap(), FILENAME);
 
     &mount.join(file_name)
   };
 
+  // Both kinds write the whole file, unless reading from an existing one
+  if existing_path.is_none() || matches!(bench_type, BenchmarkType::Write) {
+    check_free_space(actual_file, bench_config.total_size() as u64)?;
+  }
+
   match bench_type {
     BenchmarkType::Read => {
       let mut f = OpenOptions::new()
@@ -196,73 +300,130 @@ fn get_file(
   }
 }
 
+/// Make sure a file of `size` bytes will fit at `path`, counting the space an existing file there already takes up
+fn check_free_space(path: &Path, size: u64) -> Result<(), Box<dyn std::error::Error>> {
+  let dir = path
+    .parent()
+    .filter(|dir| !dir.as_os_str().is_empty())
+    .unwrap_or(Path::new("."));
+  let existing = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+  let needed = size.saturating_sub(existing);
+  let available = mount::usage(dir)?.available;
+
+  if needed > available {
+    return Err(
+      format!(
+        "Not enough free space in {} for the benchmark (needs {} MiB, {} MiB available)",
+        dir.display(),
+        needed / 1024 / 1024,
+        available / 1024 / 1024
+      )
+      .into(),
+    );
+  }
+
+  Ok(())
+}
+
 fn perform_read_benchmark(
   bench_config: &BenchmarkConfig,
   file: &mut File,
-) -> Result<Duration, Box<dyn std::error::Error>> {
-  let mut buf = vec![0; bench_config.block_size];
-
-  let start = Instant::now();
-
-  // Benchmark
-  if bench_config.random {
-    let mut rng = rand::rng();
-    let mut block_positions = (0..bench_config.block_count).collect::<Vec<usize>>();
-    block_positions.shuffle(&mut rng);
-
-    for (total_reads, _) in (0..bench_config.block_count).enumerate() {
-      let pos = block_positions[total_reads];
-
-      file.seek(SeekFrom::Start(pos as u64 * bench_config.block_size as u64))?;
-      let n = file.read(&mut buf)?;
-
-      if n == 0 {
-        break;
-      }
-    }
-  } else {
-    loop {
-      let n = file.read(&mut buf)?;
-      if n == 0 {
-        break;
-      }
-    }
-  }
-
-  Ok(start.elapsed())
+  io_engine: &dyn IoEngine,
+) -> Result<(Duration, u64), Box<dyn std::error::Error>> {
+  run_workers(bench_config, file, io_engine, &BenchmarkType::Read)
 }
 
 fn perform_write_benchmark(
   bench_config: &BenchmarkConfig,
   file: &mut File,
-) -> Result<Duration, Box<dyn std::error::Error>> {
-  let mut urand = File::open("/dev/urandom")?;
-  let mut buf = vec![0; bench_config.block_size];
+  io_engine: &dyn IoEngine,
+  mode: IoMode,
+) -> Result<(Duration, u64), Box<dyn std::error::Error>> {
+  let (elapsed, ops) = run_workers(bench_config, file, io_engine, &BenchmarkType::Write)?;
 
+  // Otherwise we'd only be timing how fast the data gets into the page cache
   let start = Instant::now();
+  if mode != IoMode::Buffered {
+    file.sync_all()?;
+  }
 
-  // Benchmark
-  if bench_config.random {
-    let mut rng = rand::rng();
-    let mut block_positions = (0..bench_config.block_count).collect::<Vec<usize>>();
-    block_positions.shuffle(&mut rng);
+  Ok((elapsed + start.elapsed(), ops))
+}
 
-    for (total_writes, _) in (0..bench_config.block_count).enumerate() {
-      let pos = block_positions[total_writes];
+/// Split the file into one region per thread, and list the block offsets each thread will hit, in order. Blocks that
+/// don't divide evenly go to the first few threads, and there's never a thread with nothing to do.
+fn thread_offsets(bench_config: &BenchmarkConfig) -> Vec<Vec<u64>> {
+  let threads = bench_config
+    .threads
+    .clamp(1, bench_config.block_count.max(1));
+  let per_thread = bench_config.block_count / threads;
+  let extra = bench_config.block_count % threads;
+  let mut rng = rand::rng();
+
+  (0..threads)
+    .map(|thread| {
+      let first = thread * per_thread + thread.min(extra);
+      let count = per_thread + usize::from(thread < extra);
+      let mut offsets = (first..first + count)
+        .map(|block| block as u64 * bench_config.block_size as u64)
+        .collect::<Vec<u64>>();
+
+      if bench_config.random {
+        offsets.shuffle(&mut rng);
+      }
 
-      urand.read_exact(&mut buf)?;
+      offsets
+    })
+    .collect()
+}
 
-      file.seek(SeekFrom::Start(pos as u64 * bench_config.block_size as u64))?;
-      file.write_all(&buf)?;
-    }
-  } else {
-    for _ in 0..bench_config.block_count {
-      urand.read_exact(&mut buf)?;
-      file.write_all(&buf)?;
+/// Run `threads` threads, each keeping `queue_depth` requests in flight on its own region of the file. Returns the
+/// time taken and the number of blocks read or written. The clock only starts once every thread is set up.
+fn run_workers(
+  bench_config: &BenchmarkConfig,
+  file: &File,
+  io_engine: &dyn IoEngine,
+  kind: &BenchmarkType,
+) -> Result<(Duration, u64), Box<dyn std::error::Error>> {
+  let regions = thread_offsets(bench_config);
+  let ready = Barrier::new(regions.len() + 1);
+
+  let (elapsed, ops) = thread::scope(|scope| -> std::io::Result<(Duration, u64)> {
+    let workers = regions
+      .iter()
+      .map(|offsets| {
+        let ready = &ready;
+
+        scope.spawn(move || {
+          let prepared = io_engine.prepare(
+            file,
+            kind,
+            offsets,
+            bench_config.block_size,
+            bench_config.queue_depth,
+          );
+
+          // Even if setting up failed, so the others aren't left waiting
+          ready.wait();
+          prepared?.run()
+        })
+      })
+      .collect::<Vec<_>>();
+
+    ready.wait();
+    let start = Instant::now();
+
+    let mut ops = 0;
+    for worker in workers {
+      ops += worker
+        .join()
+        .map_err(|_| std::io::Error::other("Benchmark thread panicked"))??;
     }
-  }
 
-  Ok(start.elapsed())
+    Ok((start.elapsed(), ops))
+  })?;
+
+  Ok((elapsed, ops))
 }
 
 fn random_fill(file: &mut File, size: usize) -> Result<(), Box<dyn std::error::Error>> {
@@ -279,3 +440,53 @@ fn random_fill(file: &mut File, size: usize) -> Result<(), Box<dyn std::error::E
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn regions(block_count: usize, threads: usize) -> Vec<Vec<u64>> {
+    thread_offsets(&BenchmarkConfig {
+      block_size: 1,
+      block_count,
+      threads,
+      ..Default::default()
+    })
+  }
+
+  #[test]
+  fn uneven_split() {
+    assert_eq!(
+      regions(10, 3),
+      vec![vec![0, 1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]]
+    );
+    assert_eq!(
+      regions(8, 4),
+      vec![vec![0, 1], vec![2, 3], vec![4, 5], vec![6, 7]]
+    );
+  }
+
+  #[test]
+  fn more_threads_than_blocks() {
+    assert_eq!(regions(2, 8), vec![vec![0], vec![1]]);
+    assert_eq!(regions(0, 4), vec![Vec::<u64>::new()]);
+  }
+
+  #[test]
+  fn random_covers_every_block() {
+    let mut offsets = thread_offsets(&BenchmarkConfig {
+      block_size: 4096,
+      block_count: 1000,
+      threads: 7,
+      random: true,
+      ..Default::default()
+    })
+    .concat();
+    offsets.sort();
+
+    assert_eq!(
+      offsets,
+      (0..1000).map(|block| block * 4096).collect::<Vec<u64>>()
+    );
+  }
+}
@@ -0,0 +1,525 @@
+//! A minimal io_uring, using the raw syscalls. See `io_uring_setup(2)` and `io_uring_enter(2)`.
+
+use std::{
+  fs::File,
+  io::{Error, Read},
+  os::fd::{AsRawFd, FromRawFd, OwnedFd},
+  ptr,
+  sync::atomic::{AtomicU32, Ordering},
+};
+
+use super::{
+  direct::AlignedBuf,
+  engine::{IoEngine, Prepared},
+  BenchmarkType,
+};
+
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x8000000;
+const IORING_OFF_SQES: libc::off_t = 0x10000000;
+const IORING_FEAT_SINGLE_MMAP: u32 = 1 << 0;
+const IORING_ENTER_GETEVENTS: u32 = 1 << 0;
+const IORING_REGISTER_BUFFERS: u32 = 0;
+const IORING_REGISTER_FILES: u32 = 2;
+const IORING_REGISTER_PROBE: u32 = 8;
+const IORING_OP_READ_FIXED: u8 = 4;
+const IORING_OP_WRITE_FIXED: u8 = 5;
+const IORING_OP_READ: u8 = 22;
+const IORING_OP_WRITE: u8 = 23;
+const IOSQE_FIXED_FILE: u8 = 1 << 0;
+const IO_URING_OP_SUPPORTED: u16 = 1 << 0;
+
+#[repr(C)]
+#[derive(Default)]
+struct SqringOffsets {
+  head: u32,
+  tail: u32,
+  ring_mask: u32,
+  ring_entries: u32,
+  flags: u32,
+  dropped: u32,
+  array: u32,
+  resv1: u32,
+  user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct CqringOffsets {
+  head: u32,
+  tail: u32,
+  ring_mask: u32,
+  ring_entries: u32,
+  overflow: u32,
+  cqes: u32,
+  flags: u32,
+  resv1: u32,
+  user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Params {
+  sq_entries: u32,
+  cq_entries: u32,
+  flags: u32,
+  sq_thread_cpu: u32,
+  sq_thread_idle: u32,
+  features: u32,
+  wq_fd: u32,
+  resv: [u32; 3],
+  sq_off: SqringOffsets,
+  cq_off: CqringOffsets,
+}
+
+/// A submission queue entry. Only the fields used for reads and writes are named.
+#[repr(C)]
+#[derive(Default)]
+struct Sqe {
+  opcode: u8,
+  flags: u8,
+  ioprio: u16,
+  fd: i32,
+  off: u64,
+  addr: u64,
+  len: u32,
+  rw_flags: u32,
+  user_data: u64,
+  buf_index: u16,
+  personality: u16,
+  splice_fd_in: i32,
+  addr3: u64,
+  pad: u64,
+}
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct ProbeOp {
+  op: u8,
+  resv: u8,
+  flags: u16,
+  resv2: u32,
+}
+
+/// Which opcodes the kernel supports. The kernel fills in up to `ops.len()` of them.
+#[repr(C)]
+struct Probe {
+  last_op: u8,
+  ops_len: u8,
+  resv: u16,
+  resv2: [u32; 3],
+  ops: [ProbeOp; 256],
+}
+
+/// A completion queue entry
+#[repr(C)]
+struct Cqe {
+  user_data: u64,
+  res: i32,
+  flags: u32,
+}
+
+/// An mmap()ed region of the ring, unmapped on drop
+struct Mmap {
+  ptr: *mut u8,
+  len: usize,
+}
+
+impl Mmap {
+  fn new(fd: &OwnedFd, len: usize, offset: libc::off_t) -> std::io::Result<Self> {
+    let ptr = unsafe {
+      libc::mmap(
+        ptr::null_mut(),
+        len,
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_SHARED | libc::MAP_POPULATE,
+        fd.as_raw_fd(),
+        offset,
+      )
+    };
+
+    if ptr == libc::MAP_FAILED {
+      return Err(Error::last_os_error());
+    }
+
+    Ok(Self {
+      ptr: ptr as *mut u8,
+      len,
+    })
+  }
+
+  /// A pointer to something in the ring, at an offset the kernel gave us
+  fn at<T>(&self, offset: u32) -> *mut T {
+    self.ptr.wrapping_add(offset as usize) as *mut T
+  }
+}
+
+impl Drop for Mmap {
+  fn drop(&mut self) {
+    unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+  }
+}
+
+/// A submission and completion queue pair. Only used from one thread.
+struct Ring {
+  // Unmapped before the fd is closed
+  sq_ring: Mmap,
+  cq_ring: Option<Mmap>,
+  sqes: Mmap,
+  fd: OwnedFd,
+  params: Params,
+}
+
+impl Ring {
+  fn new(entries: u32) -> std::io::Result<Self> {
+    let mut params = Params::default();
+    let fd = unsafe {
+      libc::syscall(
+        libc::SYS_io_uring_setup,
+        entries,
+        &mut params as *mut Params,
+      )
+    };
+    if fd < 0 {
+      return Err(Error::last_os_error());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+    let sq_len = params.sq_off.array as usize + params.sq_entries as usize * size_of::<u32>();
+    let cq_len = params.cq_off.cqes as usize + params.cq_entries as usize * size_of::<Cqe>();
+
+    // Since 5.4 both queues share one mapping
+    let (sq_ring, cq_ring) = if params.features & IORING_FEAT_SINGLE_MMAP != 0 {
+      (
+        Mmap::new(&fd, sq_len.max(cq_len), IORING_OFF_SQ_RING)?,
+        None,
+      )
+    } else {
+      (
+        Mmap::new(&fd, sq_len, IORING_OFF_SQ_RING)?,
+        Some(Mmap::new(&fd, cq_len, IORING_OFF_CQ_RING)?),
+      )
+    };
+    let sqes = Mmap::new(
+      &fd,
+      params.sq_entries as usize * size_of::<Sqe>(),
+      IORING_OFF_SQES,
+    )?;
+
+    Ok(Self {
+      sq_ring,
+      cq_ring,
+      sqes,
+      fd,
+      params,
+    })
+  }
+
+  fn cq_ring(&self) -> &Mmap {
+    self.cq_ring.as_ref().unwrap_or(&self.sq_ring)
+  }
+
+  fn register(&self, opcode: u32, arg: *const libc::c_void, count: u32) -> std::io::Result<()> {
+    let ret = unsafe {
+      libc::syscall(
+        libc::SYS_io_uring_register,
+        self.fd.as_raw_fd(),
+        opcode,
+        arg,
+        count,
+      )
+    };
+
+    if ret < 0 {
+      return Err(Error::last_os_error());
+    }
+
+    Ok(())
+  }
+
+  /// Pin the buffers in the kernel, so they don't have to be mapped for every request
+  fn register_buffers(&self, buffers: &[AlignedBuf]) -> std::io::Result<()> {
+    let iovecs = buffers
+      .iter()
+      .map(|buf| libc::iovec {
+        iov_base: buf.as_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+      })
+      .collect::<Vec<libc::iovec>>();
+
+    self.register(
+      IORING_REGISTER_BUFFERS,
+      iovecs.as_ptr() as *const libc::c_void,
+      iovecs.len() as u32,
+    )
+  }
+
+  /// Keep a reference to the file in the kernel, so it doesn't have to be looked up for every request
+  fn register_files(&self, files: &[i32]) -> std::io::Result<()> {
+    self.register(
+      IORING_REGISTER_FILES,
+      files.as_ptr() as *const libc::c_void,
+      files.len() as u32,
+    )
+  }
+
+  /// Whether the kernel supports an opcode. Probing needs 5.6, so an older kernel doesn't support anything here.
+  fn supports(&self, opcode: u8) -> bool {
+    let mut probe = Probe {
+      last_op: 0,
+      ops_len: 0,
+      resv: 0,
+      resv2: [0; 3],
+      ops: [ProbeOp::default(); 256],
+    };
+
+    if self
+      .register(
+        IORING_REGISTER_PROBE,
+        &mut probe as *mut Probe as *const libc::c_void,
+        probe.ops.len() as u32,
+      )
+      .is_err()
+    {
+      return false;
+    }
+
+    opcode <= probe.last_op && probe.ops[opcode as usize].flags & IO_URING_OP_SUPPORTED != 0
+  }
+
+  /// Queue a request. The caller has to make sure there's room, by never having more than `sq_entries` in flight.
+  fn push(&mut self, sqe: Sqe) {
+    let off = &self.params.sq_off;
+
+    unsafe {
+      let tail = &*self.sq_ring.at::<AtomicU32>(off.tail);
+      let mask = *self.sq_ring.at::<u32>(off.ring_mask);
+      let index = tail.load(Ordering::Relaxed) & mask;
+
+      ptr::write(self.sqes.at::<Sqe>(0).add(index as usize), sqe);
+      *self.sq_ring.at::<u32>(off.array).add(index as usize) = index;
+      tail.fetch_add(1, Ordering::Release);
+    }
+  }
+
+  /// Submit `count` queued requests, and wait for at least `wait` to complete
+  fn enter(&self, mut count: u32, wait: u32) -> std::io::Result<()> {
+    loop {
+      let ret = unsafe {
+        libc::syscall(
+          libc::SYS_io_uring_enter,
+          self.fd.as_raw_fd(),
+          count,
+          wait,
+          IORING_ENTER_GETEVENTS,
+          ptr::null::<libc::sigset_t>(),
+          0,
+        )
+      };
+
+      if ret >= 0 {
+        // It can be interrupted partway through, after submitting some
+        count -= ret as u32;
+        if count == 0 {
+          return Ok(());
+        }
+        continue;
+      }
+
+      let e = Error::last_os_error();
+      if e.kind() != std::io::ErrorKind::Interrupted {
+        return Err(e);
+      }
+    }
+  }
+
+  /// How many queued requests the kernel hasn't taken yet
+  fn unsubmitted(&self) -> u32 {
+    let off = &self.params.sq_off;
+
+    unsafe {
+      let head = &*self.sq_ring.at::<AtomicU32>(off.head);
+      let tail = &*self.sq_ring.at::<AtomicU32>(off.tail);
+      tail
+        .load(Ordering::Relaxed)
+        .wrapping_sub(head.load(Ordering::Acquire))
+    }
+  }
+
+  /// Take the next completion, if there is one
+  fn pop(&mut self) -> Option<Cqe> {
+    let off = &self.params.cq_off;
+    let cq_ring = self.cq_ring();
+
+    unsafe {
+      let head = &*cq_ring.at::<AtomicU32>(off.head);
+      let tail = &*cq_ring.at::<AtomicU32>(off.tail);
+      let current = head.load(Ordering::Relaxed);
+
+      if current == tail.load(Ordering::Acquire) {
+        return None;
+      }
+
+      let mask = *cq_ring.at::<u32>(off.ring_mask);
+      let cqe = ptr::read(cq_ring.at::<Cqe>(off.cqes).add((current & mask) as usize));
+      head.store(current.wrapping_add(1), Ordering::Release);
+
+      Some(cqe)
+    }
+  }
+}
+
+/// io_uring, with one ring per benchmark thread. Every slot in the queue gets its own registered buffer, and the file
+/// is registered too, so the kernel doesn't have to map buffers or look up the file on every request.
+pub struct UringEngine;
+
+impl UringEngine {
+  /// Whether io_uring can be used. It needs Linux 5.6 (for `IORING_OP_READ`), and is often blocked by seccomp in
+  /// containers.
+  pub fn supported() -> bool {
+    Ring::new(1).is_ok_and(|ring| ring.supports(IORING_OP_READ) && ring.supports(IORING_OP_WRITE))
+  }
+}
+
+impl IoEngine for UringEngine {
+  fn prepare<'a>(
+    &self,
+    file: &'a File,
+    kind: &BenchmarkType,
+    offsets: &'a [u64],
+    block_size: usize,
+    queue_depth: usize,
+  ) -> std::io::Result<Box<dyn Prepared + 'a>> {
+    let depth = queue_depth.clamp(1, offsets.len().max(1));
+    let mut buffers = (0..depth)
+      .map(|_| AlignedBuf::new(block_size))
+      .collect::<Vec<AlignedBuf>>();
+
+    // Filled once up front, so the benchmark isn't limited by how fast /dev/urandom is
+    if matches!(kind, BenchmarkType::Write) {
+      let mut urand = File::open("/dev/urandom")?;
+      for buf in buffers.iter_mut() {
+        urand.read_exact(buf)?;
+      }
+    }
+
+    // Nothing is in flight until it runs, so these can return early
+    let ring = Ring::new(depth as u32)?;
+    ring.register_files(&[file.as_raw_fd()])?;
+    // Registered buffers count against RLIMIT_MEMLOCK on older kernels, so fall back to plain reads and writes
+    let fixed = ring.register_buffers(&buffers).is_ok();
+
+    let opcode = match (kind, fixed) {
+      (BenchmarkType::Read, true) => IORING_OP_READ_FIXED,
+      (BenchmarkType::Read, false) => IORING_OP_READ,
+      (BenchmarkType::Write, true) => IORING_OP_WRITE_FIXED,
+      (BenchmarkType::Write, false) => IORING_OP_WRITE,
+    };
+
+    Ok(Box::new(UringRun {
+      ring,
+      buffers,
+      offsets,
+      block_size,
+      opcode,
+      fixed,
+    }))
+  }
+}
+
+struct UringRun<'a> {
+  // Declared before the buffers, so it's dropped (and the kernel is done with them) first
+  ring: Ring,
+  /// One for each slot in the queue
+  buffers: Vec<AlignedBuf>,
+  offsets: &'a [u64],
+  block_size: usize,
+  opcode: u8,
+  /// Whether the buffers are registered
+  fixed: bool,
+}
+
+impl Prepared for UringRun<'_> {
+  fn run(mut self: Box<Self>) -> std::io::Result<u64> {
+    let ring = &mut self.ring;
+    let depth = self.buffers.len();
+    let mut free = (0..depth).rev().collect::<Vec<usize>>();
+    let mut next = self.offsets.iter();
+    let mut in_flight = 0;
+    let mut done = false;
+    let mut error = None;
+    let mut ops = 0;
+
+    loop {
+      let mut queued = 0;
+
+      // Fill every free slot with the next block
+      while !done && !free.is_empty() {
+        let Some(offset) = next.next() else {
+          break;
+        };
+        let slot = free.pop().unwrap();
+
+        ring.push(Sqe {
+          opcode: self.opcode,
+          flags: IOSQE_FIXED_FILE,
+          // Index into the registered files
+          fd: 0,
+          off: *offset,
+          addr: self.buffers[slot].as_ptr() as u64,
+          len: self.block_size as u32,
+          user_data: slot as u64,
+          buf_index: if self.fixed { slot as u16 } else { 0 },
+          ..Default::default()
+        });
+        queued += 1;
+      }
+
+      in_flight += queued;
+      if in_flight == 0 {
+        break;
+      }
+
+      if let Err(e) = ring.enter(queued, 1) {
+        // Whatever the kernel didn't take is still in the submission queue, and never will be submitted
+        in_flight -= ring.unsubmitted();
+        error.get_or_insert(e);
+        break;
+      }
+
+      while let Some(cqe) = ring.pop() {
+        in_flight -= 1;
+        free.push(cqe.user_data as usize);
+
+        match cqe.res {
+          // Stop, but let the rest finish first, since the kernel is still using their buffers
+          res if res < 0 => {
+            error.get_or_insert(Error::from_raw_os_error(-res));
+            done = true;
+          }
+          // A short file (eg. a custom `file_path`) just ends the benchmark early
+          0 => done = true,
+          _ => ops += 1,
+        }
+      }
+    }
+
+    // The kernel is still using the buffers of anything in flight, so wait for it before they're freed
+    while in_flight > 0 {
+      if ring.enter(0, 1).is_err() {
+        // Nothing left to do but never free them
+        std::mem::forget(std::mem::take(&mut self.buffers));
+        break;
+      }
+
+      while ring.pop().is_some() {
+        in_flight -= 1;
+      }
+    }
+
+    match error {
+      Some(e) => Err(e),
+      None => Ok(ops),
+    }
+  }
+}
@@ -0,0 +1,276 @@
+use std::{error::Error, fmt::Display, fs::File, path::Path};
+
+use crate::{
+  ata::AtaIdentify,
+  sgio::{ata_command, open_device, scsi_command, AtaProtocol, AtaTaskfile, ScsiDirection},
+};
+
+const ATA_SET_FEATURES: u8 = 0xef;
+const FEATURE_WRITE_CACHE_ENABLE: u8 = 0x02;
+const FEATURE_WRITE_CACHE_DISABLE: u8 = 0x82;
+const FEATURE_LOOK_AHEAD_ENABLE: u8 = 0xaa;
+const FEATURE_LOOK_AHEAD_DISABLE: u8 = 0x55;
+
+const SCSI_MODE_SENSE_10: u8 = 0x5a;
+const SCSI_MODE_SELECT_10: u8 = 0x55;
+const CACHING_PAGE: u8 = 0x08;
+const MODE_HEADER_LEN: usize = 8;
+
+/// The state of a single cache feature
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct CacheSetting {
+  /// Whether the drive lets us change this setting
+  pub supported: bool,
+  pub enabled: bool,
+  /// Whether a change survives a power cycle. ATA drives always revert to their defaults, SCSI drives may be able to save it.
+  pub persistent: bool,
+}
+
+impl Display for CacheSetting {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match (self.supported, self.enabled) {
+      (false, _) => write!(f, "Unsupported"),
+      (true, true) => write!(f, "Enabled"),
+      (true, false) => write!(f, "Disabled"),
+    }
+  }
+}
+
+/// The volatile write cache and read look-ahead state of a drive
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct CacheSettings {
+  pub write_cache: CacheSetting,
+  pub read_look_ahead: CacheSetting,
+}
+
+impl CacheSettings {
+  /// Read the cache settings of a disk, via IDENTIFY DEVICE for ATA drives or the caching mode page for SCSI/SAS drives
+  pub fn for_disk(disk: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+    let disk = disk.as_ref();
+
+    if let Ok(identify) = AtaIdentify::for_disk(disk) {
+      return Ok(Self::from_identify(&identify));
+    }
+
+    let file = open_device(disk)?;
+    let current = CachingPage::read(&file, PageControl::Current)?;
+    let changeable = CachingPage::read(&file, PageControl::Changeable)?;
+
+    Ok(Self {
+      write_cache: CacheSetting {
+        supported: changeable.write_cache(),
+        enabled: current.write_cache(),
+        persistent: current.saveable(),
+      },
+      read_look_ahead: CacheSetting {
+        // DRA is "disable read-ahead", so changeable means the bit is set in the mask
+        supported: !changeable.read_look_ahead(),
+        enabled: current.read_look_ahead(),
+        persistent: current.saveable(),
+      },
+    })
+  }
+
+  /// Decode the cache bits of IDENTIFY DEVICE. SET FEATURES changes don't survive a power cycle on ATA drives.
+  pub fn from_identify(identify: &AtaIdentify) -> Self {
+    Self {
+      write_cache: CacheSetting {
+        supported: identify.bit(82, 5),
+        enabled: identify.bit(85, 5),
+        persistent: false,
+      },
+      read_look_ahead: CacheSetting {
+        supported: identify.bit(82, 6),
+        enabled: identify.bit(85, 6),
+        persistent: false,
+      },
+    }
+  }
+}
+
+/// Enable or disable the volatile write cache. Returns the new state of the setting.
+pub fn set_write_cache(
+  disk: impl AsRef<Path>,
+  enabled: bool,
+) -> Result<CacheSetting, Box<dyn Error>> {
+  let disk = disk.as_ref();
+  let settings = CacheSettings::for_disk(disk)?;
+
+  if !settings.write_cache.supported {
+    return Err(
+      format!(
+        "{} does not support changing the write cache",
+        disk.display()
+      )
+      .into(),
+    );
+  }
+
+  let feature = if enabled {
+    FEATURE_WRITE_CACHE_ENABLE
+  } else {
+    FEATURE_WRITE_CACHE_DISABLE
+  };
+
+  set_cache(disk, feature, |page| page.set_write_cache(enabled))?;
+
+  Ok(CacheSettings::for_disk(disk)?.write_cache)
+}
+
+/// Enable or disable read look-ahead. Returns the new state of the setting.
+pub fn set_read_look_ahead(
+  disk: impl AsRef<Path>,
+  enabled: bool,
+) -> Result<CacheSetting, Box<dyn Error>> {
+  let disk = disk.as_ref();
+  let settings = CacheSettings::for_disk(disk)?;
+
+  if !settings.read_look_ahead.supported {
+    return Err(
+      format!(
+        "{} does not support changing read look-ahead",
+        disk.display()
+      )
+      .into(),
+    );
+  }
+
+  let feature = if enabled {
+    FEATURE_LOOK_AHEAD_ENABLE
+  } else {
+    FEATURE_LOOK_AHEAD_DISABLE
+  };
+
+  set_cache(disk, feature, |page| page.set_read_look_ahead(enabled))?;
+
+  Ok(CacheSettings::for_disk(disk)?.read_look_ahead)
+}
+
+/// Apply a setting with SET FEATURES on ATA drives, or MODE SELECT on SCSI drives
+fn set_cache(
+  disk: &Path,
+  feature: u8,
+  modify: impl FnOnce(&mut CachingPage),
+) -> Result<(), Box<dyn Error>> {
+  let file = open_device(disk)?;
+
+  if AtaIdentify::for_disk(disk).is_ok() {
+    let taskfile = AtaTaskfile {
+      command: ATA_SET_FEATURES,
+      feature,
+      ..Default::default()
+    };
+
+    ata_command(&file, &taskfile, AtaProtocol::NonData, &mut [])?;
+    return Ok(());
+  }
+
+  let mut page = CachingPage::read(&file, PageControl::Current)?;
+  modify(&mut page);
+  page.write(&file)
+}
+
+#[derive(Clone, Copy)]
+enum PageControl {
+  Current = 0,
+  Changeable = 1,
+}
+
+/// The SCSI caching mode page (0x08)
+struct CachingPage {
+  page: Vec<u8>,
+}
+
+impl CachingPage {
+  fn read(file: &File, control: PageControl) -> Result<Self, Box<dyn Error>> {
+    let mut data = [0u8; 64];
+    let len = data.len() as u16;
+    // DBD, since we don't care about block descriptors
+    let cdb = [
+      SCSI_MODE_SENSE_10,
+      0x08,
+      ((control as u8) << 6) | CACHING_PAGE,
+      0,
+      0,
+      0,
+      0,
+      (len >> 8) as u8,
+      (len & 0xff) as u8,
+      0,
+    ];
+
+    scsi_command(file, &cdb, ScsiDirection::FromDevice, &mut data)?;
+
+    // Some devices return block descriptors anyway
+    let block_desc_len = u16::from_be_bytes([data[6], data[7]]) as usize;
+    let start = MODE_HEADER_LEN + block_desc_len;
+    let page_len = *data.get(start + 1).ok_or("Mode page is truncated")? as usize + 2;
+    let page = data
+      .get(start..start + page_len)
+      .ok_or("Mode page is truncated")?
+      .to_vec();
+
+    if page[0] & 0x3f != CACHING_PAGE || page.len() < 13 {
+      return Err("Device did not return the caching mode page".into());
+    }
+
+    Ok(Self { page })
+  }
+
+  fn write(&self, file: &File) -> Result<(), Box<dyn Error>> {
+    let mut data = vec![0u8; MODE_HEADER_LEN];
+    let mut page = self.page.clone();
+    let save = self.saveable();
+
+    // The PS bit is reserved in MODE SELECT
+    page[0] &= 0x3f;
+    data.extend(page);
+
+    let len = data.len() as u16;
+    // PF, plus SP to save the page if the drive supports it
+    let cdb = [
+      SCSI_MODE_SELECT_10,
+      0x10 | save as u8,
+      0,
+      0,
+      0,
+      0,
+      0,
+      (len >> 8) as u8,
+      (len & 0xff) as u8,
+      0,
+    ];
+
+    scsi_command(file, &cdb, ScsiDirection::ToDevice, &mut data)
+  }
+
+  fn saveable(&self) -> bool {
+    self.page[0] & 0x80 != 0
+  }
+
+  /// WCE
+  fn write_cache(&self) -> bool {
+    self.page[2] & 0x04 != 0
+  }
+
+  fn set_write_cache(&mut self, enabled: bool) {
+    if enabled {
+      self.page[2] |= 0x04;
+    } else {
+      self.page[2] &= !0x04;
+    }
+  }
+
+  /// Inverse of DRA (disable read-ahead)
+  fn read_look_ahead(&self) -> bool {
+    self.page[12] & 0x20 == 0
+  }
+
+  fn set_read_look_ahead(&mut self, enabled: bool) {
+    if enabled {
+      self.page[12] &= !0x20;
+    } else {
+      self.page[12] |= 0x20;
+    }
+  }
+}
@@ -6,12 +6,24 @@ use std::{
   path::{Path, PathBuf},
   rc::Rc,
   sync::{Mutex, MutexGuard},
+  time::Duration,
 };
 
 use crate::{
   ata::DiskAtaLink,
   attribute::{get_all_attributes, get_attribute, Attribute},
+  cache::{self, CacheSetting, CacheSettings},
+  endurance::{EnduranceData, EnduranceEstimate},
+  health::{HealthReport, HealthThresholds},
+  id::DiskId,
   kind::{disk_class, DiskKind},
+  md::{self, MdArray},
+  mount::{self, Mount},
+  nvme::NvmeSmartLog,
+  partition::{self, Partition},
+  power::{self, power_state, PowerFeatures, PowerState, ReadPolicy},
+  selftest::{self, SelfTestKind, SelfTestStatus},
+  stack,
 };
 
 // TODO other platforms (eg. FreeBSD)
@@ -23,14 +35,20 @@ static NUMBERS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
 #[derive(Clone)]
 pub struct Disk {
   pub path: PathBuf,
+  /// Stable identifier, if the disk reports one
+  pub id: Option<DiskId>,
   pub kind: DiskKind,
   pub ata_link: DiskAtaLink,
+  pub read_policy: ReadPolicy,
   disk: Option<Rc<Mutex<libatasmart::Disk>>>,
+  sleeping: bool,
 }
 
 #[derive(Clone, Debug)]
 pub struct ShallowDisk {
   pub path: PathBuf,
+  /// Stable identifier, if the disk reports one
+  pub id: Option<DiskId>,
   pub kind: DiskKind,
   pub ata_link: DiskAtaLink,
 }
@@ -39,8 +57,11 @@ impl Debug for Disk {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     // Write struct without the disk field
     let mut s = f.debug_struct("Disk");
+    s.field("id", &self.id);
     s.field("kind", &self.kind);
     s.field("ata_link", &self.ata_link);
+    s.field("read_policy", &self.read_policy);
+    s.field("sleeping", &self.sleeping);
     s.finish()
   }
 }
@@ -53,7 +74,7 @@ impl AsRef<Path> for Disk {
 
 impl PartialEq for Disk {
   fn eq(&self, other: &Self) -> bool {
-    self.path == other.path
+    same_disk(&self.id, &self.path, &other.id, &other.path)
   }
 }
 
@@ -66,11 +87,13 @@ impl Display for Disk {
 impl ShallowDisk {
   /// Create a new Disk from the path (e.g. `"/dev/sda"`)
   pub fn new(path: PathBuf) -> Result<Self, Box<dyn Error>> {
+    let id = DiskId::for_disk(&path).ok();
     let kind = disk_class(&path);
     let ata_link = DiskAtaLink::for_disk(&path).unwrap_or_default();
 
     Ok(Self {
       path,
+      id,
       kind,
       ata_link,
     })
@@ -80,28 +103,80 @@ impl ShallowDisk {
 impl Disk {
   /// Create a new Disk from the path (e.g. `"/dev/sda"`)
   pub fn new(path: PathBuf) -> Result<Self, Box<dyn Error>> {
+    Self::with_policy(path, ReadPolicy::default())
+  }
+
+  /// Create a new Disk from the path, using the given [`ReadPolicy`]. With [`ReadPolicy::SkipIfSleeping`], a drive in standby
+  /// is not opened (so it stays asleep), and the Disk will have no SMART data until [`Disk::refresh`] succeeds.
+  pub fn with_policy(path: PathBuf, read_policy: ReadPolicy) -> Result<Self, Box<dyn Error>> {
+    let id = DiskId::for_disk(&path).ok();
+
     // Special case for USB devices
     if disk_class(&path) == DiskKind::USB {
       return Ok(Self {
         path,
+        id,
         kind: DiskKind::USB,
         ata_link: DiskAtaLink::default(),
+        read_policy,
         disk: None,
+        sleeping: false,
       });
     }
 
-    let disk = libatasmart::Disk::new(&path)?;
     let kind = disk_class(&path);
     let ata_link = DiskAtaLink::for_disk(&path).unwrap_or_default();
 
+    // Opening via libatasmart reads SMART data, which would spin the drive up
+    if !read_policy.allows(&path) {
+      return Ok(Self {
+        path,
+        id,
+        kind,
+        ata_link,
+        read_policy,
+        disk: None,
+        sleeping: true,
+      });
+    }
+
+    // libatasmart doesn't understand NVMe, but we can still read its health log ourselves
+    let disk = match libatasmart::Disk::new(&path) {
+      Ok(disk) => Some(Rc::new(Mutex::new(disk))),
+      Err(_) if kind == DiskKind::NVME => None,
+      Err(e) => return Err(e.into()),
+    };
+
     Ok(Self {
       path,
+      id,
       kind,
       ata_link,
-      disk: Some(Rc::new(Mutex::new(disk))),
+      read_policy,
+      disk,
+      sleeping: false,
     })
   }
 
+  /// Create a Disk from the path without opening it, so it has no SMART data. This doesn't need root, for when SMART
+  /// data comes from elsewhere (e.g. the monitor daemon).
+  pub fn unopened(path: PathBuf) -> Self {
+    Self {
+      id: DiskId::for_disk(&path).ok(),
+      kind: disk_class(&path),
+      ata_link: DiskAtaLink::for_disk(&path).unwrap_or_default(),
+      path,
+      read_policy: ReadPolicy::default(),
+      disk: None,
+      sleeping: false,
+    }
+  }
+
+  /// Open a disk by its stable identifier, wherever it currently lives in `/dev`
+  pub fn from_id(id: &DiskId) -> Result<Self, Box<dyn Error>> {
+    Self::new(id.resolve()?)
+  }
+
   /// Get a SMART attribute from the disk
   pub fn get_attribute(&mut self, name: impl AsRef<str>) -> Option<Attribute> {
     if let Some(mut disk) = self.raw_disk() {
@@ -127,14 +202,175 @@ impl Disk {
     }
   }
 
+  /// Get the health score and verdict, using the default [`HealthThresholds`]
+  pub fn health(&mut self) -> Result<HealthReport, Box<dyn Error>> {
+    self.health_with_thresholds(&HealthThresholds::default())
+  }
+
+  /// Get the health score and verdict, from the NVMe health log or the SMART attributes
+  pub fn health_with_thresholds(&mut self, thresholds: &HealthThresholds) -> Result<HealthReport, Box<dyn Error>> {
+    if self.kind == DiskKind::NVME {
+      return Ok(HealthReport::from_nvme(&self.nvme_smart_log()?, thresholds));
+    }
+
+    let attributes = self.get_all_attributes();
+    if attributes.is_empty() {
+      return Err(format!("No SMART attributes available for {}", self.path.display()).into());
+    }
+
+    Ok(HealthReport::from_attributes(&attributes, thresholds))
+  }
+
+  /// Estimate how much of the drive's rated endurance has been used. `rated_tbw` is the manufacturer's rating in terabytes written, if known.
+  pub fn endurance(&mut self, rated_tbw: Option<f64>) -> Result<EnduranceEstimate, Box<dyn Error>> {
+    if self.kind == DiskKind::NVME {
+      return Ok(EnduranceEstimate::new(EnduranceData::from_nvme(&self.nvme_smart_log()?), rated_tbw));
+    }
+
+    let attributes = self.get_all_attributes();
+    if attributes.is_empty() {
+      return Err(format!("No SMART attributes available for {}", self.path.display()).into());
+    }
+
+    // libatasmart reports this in milliseconds
+    let power_on_hours = self.raw_disk().and_then(|mut disk| disk.get_power_on().ok()).map(|ms| ms / 1000 / 60 / 60);
+
+    Ok(EnduranceEstimate::new(EnduranceData::from_attributes(&attributes, power_on_hours), rated_tbw))
+  }
+
+  /// Read the NVMe SMART / Health Information log
+  pub fn nvme_smart_log(&self) -> Result<NvmeSmartLog, Box<dyn Error>> {
+    if self.kind != DiskKind::NVME {
+      return Err(format!("{} is not an NVMe drive", self.path.display()).into());
+    }
+
+    NvmeSmartLog::for_disk(&self.path)
+  }
+
+  /// Get the power state of the disk, without waking it up
+  pub fn power_state(&self) -> Result<PowerState, Box<dyn Error>> {
+    power_state(&self.path)
+  }
+
+  /// Get the APM/AAM support and current levels
+  pub fn power_features(&self) -> Result<PowerFeatures, Box<dyn Error>> {
+    PowerFeatures::for_disk(&self.path)
+  }
+
+  /// Set the Advanced Power Management level (like `hdparm -B`). 255 disables APM.
+  pub fn set_apm_level(&self, level: u8) -> Result<(), Box<dyn Error>> {
+    power::set_apm_level(&self.path, level)
+  }
+
+  /// Set the Automatic Acoustic Management level (like `hdparm -M`). 0 disables AAM.
+  pub fn set_aam_level(&self, level: u8) -> Result<(), Box<dyn Error>> {
+    power::set_aam_level(&self.path, level)
+  }
+
+  /// Set the standby timeout (like `hdparm -S`). A zero duration disables it.
+  pub fn set_standby_timeout(&self, timeout: Duration) -> Result<(), Box<dyn Error>> {
+    power::set_standby_timeout(&self.path, timeout)
+  }
+
+  /// Spin the drive down now (like `hdparm -y`)
+  pub fn standby_now(&self) -> Result<(), Box<dyn Error>> {
+    power::standby_now(&self.path)
+  }
+
+  /// Get the partitions on this disk, with their filesystems
+  pub fn partitions(&self) -> Result<Vec<Partition>, Box<dyn Error>> {
+    partition::partitions(&self.path)
+  }
+
+  /// Get the partitions, LVM/LUKS volumes and md arrays stored on this disk
+  pub fn stacked_devices(&self) -> Vec<PathBuf> {
+    stack::stacked_devices(&self.path)
+  }
+
+  /// Get the software RAID arrays this disk (or one of its partitions) is a member of
+  pub fn md_arrays(&self) -> Result<Vec<MdArray>, Box<dyn Error>> {
+    md::arrays_for_disk(&self.path)
+  }
+
+  /// Get self-test support and the result of the most recent self-test
+  pub fn self_test_status(&self) -> Result<SelfTestStatus, Box<dyn Error>> {
+    SelfTestStatus::for_disk(&self.path)
+  }
+
+  /// Start a self-test in the background
+  pub fn start_self_test(&self, kind: SelfTestKind) -> Result<(), Box<dyn Error>> {
+    selftest::start(&self.path, kind)
+  }
+
+  /// Abort a running self-test
+  pub fn abort_self_test(&self) -> Result<(), Box<dyn Error>> {
+    selftest::abort(&self.path)
+  }
+
+  /// Get the write cache and read look-ahead state
+  pub fn cache_settings(&self) -> Result<CacheSettings, Box<dyn Error>> {
+    CacheSettings::for_disk(&self.path)
+  }
+
+  /// Enable or disable the volatile write cache. Check [`CacheSetting::persistent`] to see if this survives a power cycle.
+  pub fn set_write_cache(&self, enabled: bool) -> Result<CacheSetting, Box<dyn Error>> {
+    cache::set_write_cache(&self.path, enabled)
+  }
+
+  /// Enable or disable read look-ahead. Check [`CacheSetting::persistent`] to see if this survives a power cycle.
+  pub fn set_read_look_ahead(&self, enabled: bool) -> Result<CacheSetting, Box<dyn Error>> {
+    cache::set_read_look_ahead(&self.path, enabled)
+  }
+
+  /// Whether SMART data was skipped because the drive was asleep, according to the [`ReadPolicy`]
+  pub fn is_sleeping(&self) -> bool {
+    self.sleeping
+  }
+
+  /// Re-read SMART data from the disk, respecting the [`ReadPolicy`]. Returns `false` if the read was skipped because the drive is asleep.
+  pub fn refresh(&mut self) -> Result<bool, Box<dyn Error>> {
+    if self.kind == DiskKind::USB {
+      return Ok(false);
+    }
+
+    let Some(mut disk) = self.raw_disk() else {
+      // We skipped opening the disk earlier, so try again now
+      if !self.read_policy.allows(&self.path) {
+        return Ok(false);
+      }
+
+      // NVMe health is read on demand, so there may be nothing to open
+      self.disk = match libatasmart::Disk::new(&self.path) {
+        Ok(disk) => Some(Rc::new(Mutex::new(disk))),
+        Err(_) if self.kind == DiskKind::NVME => None,
+        Err(e) => return Err(e.into()),
+      };
+      self.sleeping = false;
+      return Ok(true);
+    };
+
+    // libatasmart can tell us if the disk is awake using the handle we already have. Drives that don't support the check are always awake.
+    if self.read_policy == ReadPolicy::SkipIfSleeping && !disk.check_sleep_mode().unwrap_or(true) {
+      drop(disk);
+      self.sleeping = true;
+      return Ok(false);
+    }
+
+    disk.refresh_smart_data()?;
+    drop(disk);
+    self.sleeping = false;
+
+    Ok(true)
+  }
+
   /// Get a reference to the raw [`libatasmart::Disk`] struct
-  pub fn raw_disk(&self) -> Option<MutexGuard<libatasmart::Disk>> {
+  pub fn raw_disk(&self) -> Option<MutexGuard<'_, libatasmart::Disk>> {
     self.disk.as_ref().map(|disk| disk.lock().unwrap())
   }
 
   /// Get the mount locations of the disk. A disk may have multiple if there are multiple partitions, or a disk may have none if it is not mounted.
-  pub fn mounts(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
-    get_mounts(&self)
+  pub fn mounts(&self) -> Result<Vec<Mount>, Box<dyn Error>> {
+    mount::mounts_for_disk(&self.path)
   }
 
   /// Read the `model` via sysfs
@@ -166,9 +402,14 @@ impl Disk {
 }
 
 impl ShallowDisk {
+  /// Get the power state of the disk, without waking it up
+  pub fn power_state(&self) -> Result<PowerState, Box<dyn Error>> {
+    power_state(&self.path)
+  }
+
   /// Get the mount locations of the disk. A disk may have multiple if there are multiple partitions, or a disk may have none if it is not mounted.
-  pub fn mounts(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
-    get_mounts(&self)
+  pub fn mounts(&self) -> Result<Vec<Mount>, Box<dyn Error>> {
+    mount::mounts_for_disk(&self.path)
   }
 }
 
@@ -180,7 +421,7 @@ impl AsRef<Path> for ShallowDisk {
 
 impl PartialEq for ShallowDisk {
   fn eq(&self, other: &Self) -> bool {
-    self.path == other.path
+    same_disk(&self.id, &self.path, &other.id, &other.path)
   }
 }
 
@@ -194,6 +435,7 @@ impl From<Disk> for ShallowDisk {
   fn from(disk: Disk) -> Self {
     Self {
       path: disk.path,
+      id: disk.id,
       kind: disk.kind,
       ata_link: disk.ata_link,
     }
@@ -221,27 +463,12 @@ pub fn get_disk_paths() -> Result<Vec<String>, Box<dyn Error>> {
   Ok(disks)
 }
 
-fn get_mounts(path: &impl AsRef<Path>) -> Result<Vec<PathBuf>, Box<dyn Error>> {
-  let path = path.as_ref().to_string_lossy().to_string();
-  let mounts = fs::read_to_string("/proc/mounts")?;
-  let mounts = mounts.split("\n");
-
-  let mounts = mounts
-    .filter_map(|mount| {
-      // We use starts_with as it's possible for multiple mounts to use the same disk, eg:
-      // /dev/sda1
-      // /dev/sda2
-      if !mount.starts_with(&path) {
-        return None;
-      }
-
-      let mount = mount.split_whitespace().collect::<Vec<&str>>();
-
-      mount[1].parse::<PathBuf>().ok()
-    })
-    .collect();
-
-  Ok(mounts)
+/// Compare two disks by their stable identifiers, falling back to the device path if either is missing one
+fn same_disk(a_id: &Option<DiskId>, a_path: &Path, b_id: &Option<DiskId>, b_path: &Path) -> bool {
+  match (a_id, b_id) {
+    (Some(a), Some(b)) => a == b,
+    _ => a_path == b_path,
+  }
 }
 
 fn fits_filter(disk: &str) -> bool {
@@ -0,0 +1,142 @@
+use std::time::{Duration, SystemTime};
+
+use libatasmart_sys::SkSmartAttributeUnit;
+
+use crate::{
+  attribute::{Attribute, Convertable},
+  health::WEAR_ATTRIBUTES,
+  nvme::NvmeSmartLog,
+};
+
+const TOTAL_LBAS_WRITTEN: u8 = 241;
+/// Intel (and some others) report NAND writes in GiB here
+const NAND_WRITES_GIB: u8 = 249;
+
+/// Drives are rated in decimal terabytes
+const TB: f64 = 1000. * 1000. * 1000. * 1000.;
+const HOUR: u64 = 60 * 60;
+
+/// The raw counters that endurance is estimated from
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct EnduranceData {
+  /// Bytes written by the host
+  pub host_bytes_written: Option<u64>,
+  /// Bytes written to flash, including garbage collection and wear leveling. Only some drives report this.
+  pub nand_bytes_written: Option<u64>,
+  /// Percent of rated life used, according to the drive's own wear indicator
+  pub wear_used: Option<u8>,
+  pub power_on_hours: Option<u64>,
+}
+
+impl EnduranceData {
+  /// Collect endurance counters from SMART attributes. libatasmart reports power-on time separately, so it is passed in.
+  pub fn from_attributes(attributes: &[Attribute], power_on_hours: Option<u64>) -> Self {
+    let find = |id: u8| attributes.iter().find(|a| a.id == id);
+
+    let host_bytes_written = find(TOTAL_LBAS_WRITTEN).map(|a| match a.pretty_unit {
+      SkSmartAttributeUnit::SK_SMART_ATTRIBUTE_UNIT_MB => {
+        a.pretty_unit.convert_to_base(a.pretty_value)
+      }
+      // Assume 512 byte LBAs
+      _ => a.raw_value() * 512,
+    });
+    // Some vendors put garbage in the raw value, which can overflow once converted to bytes
+    let nand_bytes_written =
+      find(NAND_WRITES_GIB).and_then(|a| a.raw_value().checked_mul(1024 * 1024 * 1024));
+    let wear_used = WEAR_ATTRIBUTES
+      .iter()
+      .find_map(|id| find(*id))
+      .map(|a| 100 - a.current.min(100));
+
+    Self {
+      host_bytes_written,
+      nand_bytes_written,
+      wear_used,
+      power_on_hours,
+    }
+  }
+
+  /// Collect endurance counters from the NVMe health log
+  pub fn from_nvme(log: &NvmeSmartLog) -> Self {
+    Self {
+      host_bytes_written: Some(log.bytes_written().min(u64::MAX as u128) as u64),
+      nand_bytes_written: None,
+      wear_used: Some(log.percentage_used),
+      power_on_hours: Some(log.power_on_hours.min(u64::MAX as u128) as u64),
+    }
+  }
+}
+
+/// How much of an SSD's rated endurance has been used, and how long it has left at the current rate
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct EnduranceEstimate {
+  pub data: EnduranceData,
+  /// Percent of rated life used. This is the drive's wear indicator if it has one, otherwise TBW used.
+  pub life_used: Option<f64>,
+  /// Rated endurance in terabytes written
+  pub rated_tbw: Option<f64>,
+  /// Terabytes written by the host so far
+  pub tbw: Option<f64>,
+  /// Percent of `rated_tbw` written so far
+  pub tbw_used: Option<f64>,
+  /// NAND writes divided by host writes
+  pub write_amplification: Option<f64>,
+  /// Average host writes in bytes per hour of power-on time
+  pub write_rate: Option<f64>,
+  /// Estimated powered-on time left, using whichever of the wear indicator or rated TBW runs out first
+  pub remaining: Option<Duration>,
+}
+
+impl EnduranceEstimate {
+  /// Estimate endurance. `rated_tbw` is the manufacturer's rating in terabytes written, if known.
+  pub fn new(data: EnduranceData, rated_tbw: Option<f64>) -> Self {
+    let rated_tbw = rated_tbw.filter(|tbw| *tbw > 0.);
+    let host = data.host_bytes_written.filter(|b| *b > 0).map(|b| b as f64);
+    let hours = data.power_on_hours.filter(|h| *h > 0).map(|h| h as f64);
+
+    let tbw = host.map(|bytes| bytes / TB);
+    let tbw_used = tbw.zip(rated_tbw).map(|(tbw, rated)| tbw / rated * 100.);
+    let life_used = data.wear_used.map(|used| used as f64).or(tbw_used);
+    let write_amplification = data
+      .nand_bytes_written
+      .map(|b| b as f64)
+      .zip(host)
+      .map(|(nand, host)| nand / host);
+    let write_rate = host.zip(hours).map(|(bytes, hours)| bytes / hours);
+
+    // Hours left if wear continues at the same rate it has so far
+    let by_wear = data
+      .wear_used
+      .filter(|used| *used > 0)
+      .zip(hours)
+      .map(|(used, hours)| hours * (100. - used as f64).max(0.) / used as f64);
+    let by_tbw = rated_tbw
+      .zip(host)
+      .zip(write_rate)
+      .map(|((rated, host), rate)| (rated * TB - host).max(0.) / rate);
+    let remaining = match (by_wear, by_tbw) {
+      (Some(a), Some(b)) => Some(a.min(b)),
+      (a, b) => a.or(b),
+    }
+    .map(|hours| Duration::from_secs_f64(hours * HOUR as f64));
+
+    Self {
+      data,
+      life_used,
+      rated_tbw,
+      tbw,
+      tbw_used,
+      write_amplification,
+      write_rate,
+      remaining,
+    }
+  }
+
+  /// Projected end-of-life date, assuming the drive stays powered on. Divide [`EnduranceEstimate::remaining`] by
+  /// your expected daily uptime for a more realistic date on machines that are switched off.
+  pub fn end_of_life(&self) -> Option<SystemTime> {
+    self
+      .remaining
+      .and_then(|remaining| SystemTime::now().checked_add(remaining))
+  }
+}
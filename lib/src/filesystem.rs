@@ -0,0 +1,390 @@
+use std::{
+  error::Error,
+  fmt::Display,
+  io::{Read, Seek, SeekFrom},
+};
+
+type ProbeResult = Result<Option<Filesystem>, Box<dyn Error>>;
+/// Checks for one kind of filesystem, given the reader, the offset and the first 8 KiB at that offset
+type Probe<R> = fn(&mut R, u64, &[u8]) -> ProbeResult;
+
+/// A filesystem (or similar) found by its on-disk signature
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilesystemKind {
+  Ext2,
+  Ext3,
+  Ext4,
+  Xfs,
+  Btrfs,
+  Vfat,
+  Exfat,
+  Ntfs,
+  Swap,
+  /// An encrypted LUKS container. The filesystem inside can't be read without unlocking it.
+  Luks,
+}
+
+impl Display for FilesystemKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    // The same names blkid and mount use
+    match self {
+      FilesystemKind::Ext2 => write!(f, "ext2"),
+      FilesystemKind::Ext3 => write!(f, "ext3"),
+      FilesystemKind::Ext4 => write!(f, "ext4"),
+      FilesystemKind::Xfs => write!(f, "xfs"),
+      FilesystemKind::Btrfs => write!(f, "btrfs"),
+      FilesystemKind::Vfat => write!(f, "vfat"),
+      FilesystemKind::Exfat => write!(f, "exfat"),
+      FilesystemKind::Ntfs => write!(f, "ntfs"),
+      FilesystemKind::Swap => write!(f, "swap"),
+      FilesystemKind::Luks => write!(f, "crypto_LUKS"),
+    }
+  }
+}
+
+/// The type, label and UUID of a filesystem
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Filesystem {
+  pub kind: FilesystemKind,
+  pub label: Option<String>,
+  /// Formatted the way blkid does, e.g. `"1b4e28ba-2fa1-11d2-883f-0016d3cca427"` or `"A1B2-C3D4"` for FAT
+  pub uuid: Option<String>,
+}
+
+/// Look for a filesystem starting `offset` bytes into `reader`, which can be a disk, a partition or an image file.
+/// Returns `None` if nothing recognisable is there.
+pub fn probe<R: Read + Seek>(reader: &mut R, offset: u64) -> ProbeResult {
+  // Everything except btrfs has its signature in the first few KiB
+  let head = read_at(reader, offset, 8192)?;
+
+  let probes: [Probe<R>; 8] = [luks, ext, xfs, btrfs, ntfs, exfat, vfat, swap];
+
+  for probe in probes {
+    if let Some(filesystem) = probe(reader, offset, &head)? {
+      return Ok(Some(filesystem));
+    }
+  }
+
+  Ok(None)
+}
+
+/// Read up to `len` bytes at `offset`. Returns fewer if the end of the reader is reached.
+pub(crate) fn read_at(
+  reader: &mut (impl Read + Seek),
+  offset: u64,
+  len: usize,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+  // `len` can come from the disk, so only grow the buffer as data actually arrives
+  let mut buf = Vec::with_capacity(len.min(0x10000));
+  reader.seek(SeekFrom::Start(offset))?;
+  reader.take(len as u64).read_to_end(&mut buf)?;
+
+  Ok(buf)
+}
+
+/// A standard big-endian UUID, e.g. as used by ext4, xfs and btrfs
+pub(crate) fn format_uuid(bytes: &[u8]) -> Option<String> {
+  let hex = hex(bytes.get(..16)?);
+
+  if bytes[..16].iter().all(|b| *b == 0) {
+    return None;
+  }
+
+  Some(format!(
+    "{}-{}-{}-{}-{}",
+    &hex[0..8],
+    &hex[8..12],
+    &hex[12..16],
+    &hex[16..20],
+    &hex[20..32]
+  ))
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// A NUL-padded label
+fn label(bytes: Option<&[u8]>) -> Option<String> {
+  let bytes = bytes?;
+  let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+  let label = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+
+  Some(label).filter(|label| !label.is_empty())
+}
+
+/// A UTF-16LE label, as used by exFAT and NTFS
+pub(crate) fn label_utf16(bytes: Option<&[u8]>) -> Option<String> {
+  let units = bytes?
+    .chunks_exact(2)
+    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+    .take_while(|unit| *unit != 0)
+    .collect::<Vec<u16>>();
+  let label = String::from_utf16_lossy(&units).trim().to_string();
+
+  Some(label).filter(|label| !label.is_empty())
+}
+
+fn u16_le(buf: &[u8], at: usize) -> Option<u16> {
+  Some(u16::from_le_bytes(buf.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_le(buf: &[u8], at: usize) -> Option<u32> {
+  Some(u32::from_le_bytes(buf.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_le(buf: &[u8], at: usize) -> Option<u64> {
+  Some(u64::from_le_bytes(buf.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// A FAT/exFAT volume serial, e.g. `"A1B2-C3D4"`
+fn volume_serial(serial: u32) -> String {
+  format!("{:04X}-{:04X}", serial >> 16, serial & 0xffff)
+}
+
+fn luks<R: Read + Seek>(_: &mut R, _: u64, head: &[u8]) -> ProbeResult {
+  if !head.starts_with(b"LUKS\xba\xbe") {
+    return Ok(None);
+  }
+
+  // Only LUKS2 has a label
+  let version = head.get(6..8).map(|v| u16::from_be_bytes([v[0], v[1]]));
+
+  Ok(Some(Filesystem {
+    kind: FilesystemKind::Luks,
+    label: if version == Some(2) {
+      label(head.get(24..72))
+    } else {
+      None
+    },
+    uuid: label(head.get(168..208)),
+  }))
+}
+
+fn ext<R: Read + Seek>(_: &mut R, _: u64, head: &[u8]) -> ProbeResult {
+  // The superblock is 1 KiB in
+  let Some(sb) = head.get(1024..2048) else {
+    return Ok(None);
+  };
+
+  if u16_le(sb, 56) != Some(0xef53) {
+    return Ok(None);
+  }
+
+  let compat = u32_le(sb, 92).unwrap_or_default();
+  let incompat = u32_le(sb, 96).unwrap_or_default();
+
+  // extents, 64bit or flex_bg mean ext4. A journal without those means ext3.
+  let kind = if incompat & (0x40 | 0x80 | 0x200) != 0 {
+    FilesystemKind::Ext4
+  } else if compat & 0x4 != 0 {
+    FilesystemKind::Ext3
+  } else {
+    FilesystemKind::Ext2
+  };
+
+  Ok(Some(Filesystem {
+    kind,
+    label: label(sb.get(120..136)),
+    uuid: format_uuid(&sb[104..120]),
+  }))
+}
+
+fn xfs<R: Read + Seek>(_: &mut R, _: u64, head: &[u8]) -> ProbeResult {
+  if !head.starts_with(b"XFSB") {
+    return Ok(None);
+  }
+
+  Ok(Some(Filesystem {
+    kind: FilesystemKind::Xfs,
+    label: label(head.get(108..120)),
+    uuid: head.get(32..48).and_then(format_uuid),
+  }))
+}
+
+fn btrfs<R: Read + Seek>(reader: &mut R, offset: u64, _: &[u8]) -> ProbeResult {
+  // The primary superblock is 64 KiB in
+  let Some(sb_offset) = offset.checked_add(0x10000) else {
+    return Ok(None);
+  };
+  let sb = read_at(reader, sb_offset, 0x1000)?;
+
+  if sb.get(0x40..0x48) != Some(b"_BHRfS_M") {
+    return Ok(None);
+  }
+
+  Ok(Some(Filesystem {
+    kind: FilesystemKind::Btrfs,
+    label: label(sb.get(0x12b..0x22b)),
+    uuid: format_uuid(&sb[0x20..0x30]),
+  }))
+}
+
+fn ntfs<R: Read + Seek>(reader: &mut R, offset: u64, head: &[u8]) -> ProbeResult {
+  if head.get(3..11) != Some(b"NTFS    ") {
+    return Ok(None);
+  }
+
+  let serial = u64_le(head, 72).unwrap_or_default();
+
+  Ok(Some(Filesystem {
+    kind: FilesystemKind::Ntfs,
+    label: ntfs_label(reader, offset, head)?,
+    uuid: Some(format!("{serial:016X}")),
+  }))
+}
+
+/// The label is the `$VOLUME_NAME` attribute of `$Volume`, which is MFT record 3
+fn ntfs_label<R: Read + Seek>(
+  reader: &mut R,
+  offset: u64,
+  head: &[u8],
+) -> Result<Option<String>, Box<dyn Error>> {
+  let bytes_per_sector = u16_le(head, 11).unwrap_or_default() as u64;
+  let sectors_per_cluster = head.get(13).copied().unwrap_or_default() as u64;
+  let mft_cluster = u64_le(head, 48).unwrap_or_default();
+  let cluster_size = bytes_per_sector * sectors_per_cluster;
+
+  // Positive values are in clusters, negative values are a power of two in bytes
+  let record_size = match head.get(64).map(|size| *size as i8) {
+    Some(size) if size > 0 => size as u64 * cluster_size,
+    Some(size) if size < 0 && size > -32 => 1 << -size,
+    _ => return Ok(None),
+  };
+
+  if cluster_size == 0 || record_size > 0x10000 {
+    return Ok(None);
+  }
+
+  let Some(record_offset) = mft_cluster
+    .checked_mul(cluster_size)
+    .and_then(|mft| mft.checked_add(3 * record_size))
+    .and_then(|record| record.checked_add(offset))
+  else {
+    return Ok(None);
+  };
+  let record = read_at(reader, record_offset, record_size as usize)?;
+  if !record.starts_with(b"FILE") {
+    return Ok(None);
+  }
+
+  let mut at = u16_le(&record, 20).unwrap_or_default() as usize;
+
+  while let (Some(kind), Some(len)) = (u32_le(&record, at), u32_le(&record, at + 4)) {
+    if kind == 0xffff_ffff || len == 0 {
+      break;
+    }
+
+    // $VOLUME_NAME is always resident
+    if kind == 0x60 {
+      let value_len = u32_le(&record, at + 16).unwrap_or_default() as usize;
+      let value_offset = at + u16_le(&record, at + 20).unwrap_or_default() as usize;
+
+      return Ok(label_utf16(
+        record.get(value_offset..value_offset + value_len),
+      ));
+    }
+
+    at += len as usize;
+  }
+
+  Ok(None)
+}
+
+fn exfat<R: Read + Seek>(reader: &mut R, offset: u64, head: &[u8]) -> ProbeResult {
+  if head.get(3..11) != Some(b"EXFAT   ") {
+    return Ok(None);
+  }
+
+  let serial = u32_le(head, 100).unwrap_or_default();
+
+  Ok(Some(Filesystem {
+    kind: FilesystemKind::Exfat,
+    label: exfat_label(reader, offset, head)?,
+    uuid: Some(volume_serial(serial)),
+  }))
+}
+
+/// The label is an entry in the root directory
+fn exfat_label<R: Read + Seek>(
+  reader: &mut R,
+  offset: u64,
+  head: &[u8],
+) -> Result<Option<String>, Box<dyn Error>> {
+  let heap_offset = u32_le(head, 88).unwrap_or_default() as u64;
+  let root_cluster = u32_le(head, 96).unwrap_or_default() as u64;
+  let sector_shift = head.get(108).copied().unwrap_or_default() as u64;
+  let cluster_shift = head.get(109).copied().unwrap_or_default() as u64;
+
+  if root_cluster < 2 || sector_shift > 12 || sector_shift + cluster_shift > 25 {
+    return Ok(None);
+  }
+
+  // With the shifts capped above this stays under 2^58, so only adding the partition offset can overflow
+  let sector = heap_offset + ((root_cluster - 2) << cluster_shift);
+  let Some(dir_offset) = offset.checked_add(sector << sector_shift) else {
+    return Ok(None);
+  };
+  let dir = read_at(reader, dir_offset, 4096)?;
+
+  for entry in dir.chunks_exact(32) {
+    match entry[0] {
+      // End of directory
+      0x00 => break,
+      0x83 => {
+        let len = (entry[1] as usize).min(11);
+        return Ok(label_utf16(entry.get(2..2 + len * 2)));
+      }
+      _ => {}
+    }
+  }
+
+  Ok(None)
+}
+
+fn vfat<R: Read + Seek>(_: &mut R, _: u64, head: &[u8]) -> ProbeResult {
+  if head.get(510..512) != Some(&[0x55, 0xaa]) {
+    return Ok(None);
+  }
+
+  // FAT32 has a longer BPB, so the volume ID and label are further in
+  let (serial, name) = if head.get(82..87) == Some(b"FAT32") {
+    (67, 71)
+  } else if head.get(54..57) == Some(b"FAT") {
+    (39, 43)
+  } else {
+    return Ok(None);
+  };
+
+  Ok(Some(Filesystem {
+    kind: FilesystemKind::Vfat,
+    label: label(head.get(name..name + 11)).filter(|label| label != "NO NAME"),
+    uuid: u32_le(head, serial).map(volume_serial),
+  }))
+}
+
+fn swap<R: Read + Seek>(reader: &mut R, offset: u64, head: &[u8]) -> ProbeResult {
+  // The signature is at the end of the first page, and page sizes vary
+  for page_size in [4096, 8192, 16384, 65536] {
+    let signature = if page_size <= head.len() {
+      head[page_size - 10..page_size].to_vec()
+    } else {
+      match offset.checked_add(page_size as u64 - 10) {
+        Some(at) => read_at(reader, at, 10)?,
+        None => break,
+      }
+    };
+
+    if signature == b"SWAPSPACE2" {
+      return Ok(Some(Filesystem {
+        kind: FilesystemKind::Swap,
+        label: label(head.get(1052..1068)),
+        uuid: head.get(1036..1052).and_then(format_uuid),
+      }));
+    }
+  }
+
+  Ok(None)
+}
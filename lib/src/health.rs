@@ -0,0 +1,313 @@
+use std::{cmp::Reverse, fmt::Display};
+
+use crate::{
+  attribute::Attribute,
+  nvme::{
+    NvmeSmartLog, WARNING_PMR, WARNING_READ_ONLY, WARNING_RELIABILITY, WARNING_SPARE,
+    WARNING_TEMPERATURE, WARNING_VOLATILE_BACKUP,
+  },
+};
+
+const REALLOCATED_SECTORS: u8 = 5;
+const REALLOCATION_EVENTS: u8 = 196;
+const PENDING_SECTORS: u8 = 197;
+const UNCORRECTABLE_SECTORS: u8 = 198;
+
+/// Attributes whose normalized value is the remaining life of an SSD, in order of preference
+pub(crate) const WEAR_ATTRIBUTES: [u8; 3] = [
+  231, // SSD Life Left
+  233, // Media Wearout Indicator
+  177, // Wear Leveling Count
+];
+
+/// The overall verdict for a drive. These are ordered, so the worst of several can be found with `max()`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HealthStatus {
+  Good,
+  Caution,
+  Bad,
+}
+
+impl Display for HealthStatus {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      HealthStatus::Good => write!(f, "Good"),
+      HealthStatus::Caution => write!(f, "Caution"),
+      HealthStatus::Bad => write!(f, "Bad"),
+    }
+  }
+}
+
+/// Something that contributed to a [`HealthReport`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct HealthReason {
+  /// The attribute or NVMe log field responsible (e.g. `"reallocated-sector-count"`)
+  pub attribute: String,
+  /// The SMART attribute ID, for ATA drives
+  pub id: Option<u8>,
+  pub status: HealthStatus,
+  pub message: String,
+}
+
+impl Display for HealthReason {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.status, self.message)
+  }
+}
+
+/// When to consider a drive worn out or failing
+#[derive(Clone, PartialEq, Debug)]
+pub struct HealthThresholds {
+  /// Bad sector count (reallocated + pending + uncorrectable) at which a drive is Caution
+  pub sectors_caution: u64,
+  /// Bad sector count at which a drive is Bad. This is also where the score reaches 0%.
+  pub sectors_bad: u64,
+  /// Remaining life (%) at or below which a drive is Caution
+  pub life_caution: u8,
+  /// Remaining life (%) at or below which a drive is Bad
+  pub life_bad: u8,
+}
+
+impl Default for HealthThresholds {
+  fn default() -> Self {
+    Self {
+      sectors_caution: 1,
+      sectors_bad: 100,
+      life_caution: 10,
+      life_bad: 0,
+    }
+  }
+}
+
+/// A 0-100% health score and Good/Caution/Bad verdict, in the spirit of CrystalDiskInfo
+#[derive(Clone, PartialEq, Debug)]
+pub struct HealthReport {
+  /// Health from 0 to 100
+  pub score: u8,
+  pub status: HealthStatus,
+  /// What drove the verdict, worst first. This also includes informational entries, like remaining life.
+  pub reasons: Vec<HealthReason>,
+}
+
+impl Display for HealthReport {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} ({}%)", self.status, self.score)
+  }
+}
+
+impl HealthReport {
+  /// Score an ATA drive from its SMART attributes
+  pub fn from_attributes(attributes: &[Attribute], thresholds: &HealthThresholds) -> Self {
+    let mut reasons = vec![];
+    let find = |id: u8| attributes.iter().find(|a| a.id == id);
+
+    // Bad sectors. The thresholds apply to the total, so each kind is rated by it too.
+    let counts = [REALLOCATED_SECTORS, PENDING_SECTORS, UNCORRECTABLE_SECTORS]
+      .into_iter()
+      .filter_map(|id| find(id).map(|attribute| (attribute, attribute.raw_count())))
+      .filter(|(_, count)| *count > 0)
+      .collect::<Vec<_>>();
+    let bad_sectors = counts
+      .iter()
+      .fold(0u64, |total, (_, count)| total.saturating_add(*count));
+
+    for (attribute, count) in counts {
+      reasons.push(HealthReason {
+        attribute: attribute.name.clone(),
+        id: Some(attribute.id),
+        status: sector_status(bad_sectors, thresholds),
+        message: format!("{count} {}", sector_description(attribute.id)),
+      });
+    }
+
+    // Reallocation events overlap with reallocated sectors, so they only add a reason
+    if let Some(attribute) = find(REALLOCATION_EVENTS) {
+      let count = attribute.raw_count();
+
+      if count > 0 {
+        reasons.push(HealthReason {
+          attribute: attribute.name.clone(),
+          id: Some(REALLOCATION_EVENTS),
+          status: HealthStatus::Caution,
+          message: format!("{count} reallocation events"),
+        });
+      }
+    }
+
+    // Attributes at or below their threshold, now or in the past
+    for attribute in attributes.iter().filter(|a| a.threshold > 0) {
+      if attribute.current <= attribute.threshold {
+        reasons.push(HealthReason {
+          attribute: attribute.name.clone(),
+          id: Some(attribute.id),
+          status: HealthStatus::Bad,
+          message: format!(
+            "{} is at or below its threshold ({} <= {})",
+            attribute.name, attribute.current, attribute.threshold
+          ),
+        });
+      } else if attribute.worst <= attribute.threshold {
+        reasons.push(HealthReason {
+          attribute: attribute.name.clone(),
+          id: Some(attribute.id),
+          status: HealthStatus::Caution,
+          message: format!(
+            "{} was at or below its threshold in the past ({} <= {})",
+            attribute.name, attribute.worst, attribute.threshold
+          ),
+        });
+      }
+    }
+
+    let life = WEAR_ATTRIBUTES
+      .iter()
+      .find_map(|id| find(*id))
+      .map(|attribute| {
+        (
+          attribute.name.clone(),
+          Some(attribute.id),
+          attribute.current.min(100),
+        )
+      });
+
+    Self::build(bad_sectors, life, reasons, thresholds)
+  }
+
+  /// Score an NVMe drive from its SMART / Health Information log
+  pub fn from_nvme(log: &NvmeSmartLog, thresholds: &HealthThresholds) -> Self {
+    let mut reasons = vec![];
+    let warnings = [
+      (
+        WARNING_SPARE,
+        HealthStatus::Bad,
+        "Available spare is below its threshold",
+      ),
+      (
+        WARNING_TEMPERATURE,
+        HealthStatus::Caution,
+        "Temperature is outside of its limits",
+      ),
+      (
+        WARNING_RELIABILITY,
+        HealthStatus::Bad,
+        "Reliability is degraded by media or internal errors",
+      ),
+      (
+        WARNING_READ_ONLY,
+        HealthStatus::Bad,
+        "Media has been placed in read-only mode",
+      ),
+      (
+        WARNING_VOLATILE_BACKUP,
+        HealthStatus::Caution,
+        "Volatile memory backup device has failed",
+      ),
+      (
+        WARNING_PMR,
+        HealthStatus::Caution,
+        "Persistent memory region has become read-only or unreliable",
+      ),
+    ];
+
+    for (bit, status, message) in warnings {
+      if log.critical_warning & bit != 0 {
+        reasons.push(HealthReason {
+          attribute: "critical-warning".to_string(),
+          id: None,
+          status,
+          message: message.to_string(),
+        });
+      }
+    }
+
+    let media_errors = log.media_errors.min(u64::MAX as u128) as u64;
+    if media_errors > 0 {
+      reasons.push(HealthReason {
+        attribute: "media-errors".to_string(),
+        id: None,
+        status: sector_status(media_errors, thresholds),
+        message: format!("{media_errors} media and data integrity errors"),
+      });
+    }
+
+    let life = Some((
+      "percentage-used".to_string(),
+      None,
+      100u8.saturating_sub(log.percentage_used),
+    ));
+
+    Self::build(media_errors, life, reasons, thresholds)
+  }
+
+  /// Only the reasons that made the verdict worse than Good
+  pub fn problems(&self) -> impl Iterator<Item = &HealthReason> {
+    self
+      .reasons
+      .iter()
+      .filter(|reason| reason.status > HealthStatus::Good)
+  }
+
+  fn build(
+    bad_sectors: u64,
+    life: Option<(String, Option<u8>, u8)>,
+    mut reasons: Vec<HealthReason>,
+    thresholds: &HealthThresholds,
+  ) -> Self {
+    let sectors_bad = thresholds.sectors_bad.max(1);
+    let sector_score = 100 - (bad_sectors.min(sectors_bad) * 100 / sectors_bad) as u8;
+    let mut score = sector_score;
+
+    if let Some((attribute, id, life)) = life {
+      score = score.min(life);
+
+      let status = if life <= thresholds.life_bad {
+        HealthStatus::Bad
+      } else if life <= thresholds.life_caution {
+        HealthStatus::Caution
+      } else {
+        HealthStatus::Good
+      };
+
+      reasons.push(HealthReason {
+        attribute,
+        id,
+        status,
+        message: format!("{life}% of rated life remaining"),
+      });
+    }
+
+    let status = reasons
+      .iter()
+      .map(|reason| reason.status)
+      .max()
+      .unwrap_or(HealthStatus::Good);
+
+    // Worst first
+    reasons.sort_by_key(|reason| Reverse(reason.status));
+
+    Self {
+      score,
+      status,
+      reasons,
+    }
+  }
+}
+
+fn sector_status(count: u64, thresholds: &HealthThresholds) -> HealthStatus {
+  if count >= thresholds.sectors_bad {
+    HealthStatus::Bad
+  } else if count >= thresholds.sectors_caution {
+    HealthStatus::Caution
+  } else {
+    HealthStatus::Good
+  }
+}
+
+fn sector_description(id: u8) -> &'static str {
+  match id {
+    REALLOCATED_SECTORS => "reallocated sectors",
+    PENDING_SECTORS => "sectors pending reallocation",
+    _ => "uncorrectable sectors",
+  }
+}
@@ -0,0 +1,238 @@
+use std::{
+  collections::BTreeMap,
+  error::Error,
+  fs::{self, File, OpenOptions},
+  io::{BufRead, BufReader, ErrorKind, Write},
+  ops::RangeBounds,
+  os::unix::fs::MetadataExt,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use crate::snapshot::{now, DiskSnapshot};
+
+const EXTENSION: &str = "jsonl";
+
+/// How long to keep history, and how much detail to keep as it ages
+#[derive(Clone, PartialEq, Debug)]
+pub struct RetentionPolicy {
+  /// Snapshots older than this are deleted. `None` keeps everything.
+  pub max_age: Option<Duration>,
+  /// Snapshots older than this are downsampled. `None` never downsamples.
+  pub downsample_after: Option<Duration>,
+  /// Downsampled history keeps the newest snapshot in each interval
+  pub downsample_interval: Duration,
+}
+
+impl Default for RetentionPolicy {
+  fn default() -> Self {
+    Self {
+      // 1 year
+      max_age: Some(Duration::from_secs(60 * 60 * 24 * 365)),
+      // Full detail for a week, then hourly
+      downsample_after: Some(Duration::from_secs(60 * 60 * 24 * 7)),
+      downsample_interval: Duration::from_secs(60 * 60),
+    }
+  }
+}
+
+/// A local store of [`DiskSnapshot`]s. Each disk gets an append-only JSON lines file, named after its stable ID.
+///
+/// Appending and compacting take an exclusive lock on the file, so several processes can share a store.
+#[derive(Clone, Debug)]
+pub struct HistoryStore {
+  pub dir: PathBuf,
+  pub retention: RetentionPolicy,
+}
+
+impl HistoryStore {
+  /// Open (or create) a history store in a directory, using the default [`RetentionPolicy`]
+  pub fn open(dir: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+    Self::with_retention(dir, RetentionPolicy::default())
+  }
+
+  pub fn with_retention(
+    dir: impl AsRef<Path>,
+    retention: RetentionPolicy,
+  ) -> Result<Self, Box<dyn Error>> {
+    let dir = dir.as_ref().to_path_buf();
+    fs::create_dir_all(&dir)?;
+
+    Ok(Self { dir, retention })
+  }
+
+  /// Append a snapshot to its disk's history
+  pub fn record(&self, snapshot: &DiskSnapshot) -> Result<(), Box<dyn Error>> {
+    let mut line = serde_json::to_string(snapshot)?;
+    line.push('\n');
+
+    // A single write, so that a crash can at worst leave a torn last line (which is skipped when reading)
+    let mut file = lock(&self.file_for(&snapshot.id), true)?;
+    file.write_all(line.as_bytes())?;
+
+    Ok(())
+  }
+
+  /// Get all snapshots for a disk with a timestamp (seconds since the Unix epoch) in `range`, oldest first
+  pub fn query(
+    &self,
+    disk_id: impl AsRef<str>,
+    range: impl RangeBounds<u64>,
+  ) -> Result<Vec<DiskSnapshot>, Box<dyn Error>> {
+    let mut snapshots = self
+      .read_all(disk_id.as_ref())?
+      .into_iter()
+      .filter(|snapshot| range.contains(&snapshot.timestamp))
+      .collect::<Vec<DiskSnapshot>>();
+
+    snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+
+    Ok(snapshots)
+  }
+
+  /// Get the most recent snapshot for a disk
+  pub fn latest(&self, disk_id: impl AsRef<str>) -> Result<Option<DiskSnapshot>, Box<dyn Error>> {
+    Ok(
+      self
+        .read_all(disk_id.as_ref())?
+        .into_iter()
+        .max_by_key(|snapshot| snapshot.timestamp),
+    )
+  }
+
+  /// The IDs of all disks with history
+  pub fn disks(&self) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut disks = vec![];
+
+    for entry in fs::read_dir(&self.dir)? {
+      let path = entry?.path();
+
+      if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+        continue;
+      }
+
+      // The file name is encoded, so read the real ID from the file itself
+      if let Some(snapshot) = read_file(&path)?.into_iter().next() {
+        disks.push(snapshot.id);
+      }
+    }
+
+    Ok(disks)
+  }
+
+  /// Apply the [`RetentionPolicy`] to every disk's history, rewriting the files
+  pub fn compact(&self) -> Result<(), Box<dyn Error>> {
+    for id in self.disks()? {
+      self.compact_disk(&id)?;
+    }
+
+    Ok(())
+  }
+
+  /// Apply the [`RetentionPolicy`] to one disk's history
+  pub fn compact_disk(&self, disk_id: impl AsRef<str>) -> Result<(), Box<dyn Error>> {
+    let path = self.file_for(disk_id.as_ref());
+    let now = now();
+    let age = |snapshot: &DiskSnapshot| Duration::from_secs(now.saturating_sub(snapshot.timestamp));
+
+    // Held until the rewritten file replaces this one, so nothing can be appended in between and lost
+    let locked = lock(&path, false)?;
+    let mut snapshots = read_lines(&locked)?;
+
+    snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+
+    if let Some(max_age) = self.retention.max_age {
+      snapshots.retain(|snapshot| age(snapshot) <= max_age);
+    }
+
+    if let Some(after) = self.retention.downsample_after {
+      let interval = self.retention.downsample_interval.as_secs().max(1);
+      let (old, recent): (Vec<DiskSnapshot>, Vec<DiskSnapshot>) = snapshots
+        .into_iter()
+        .partition(|snapshot| age(snapshot) > after);
+
+      // Newest snapshot per interval, since they are sorted oldest first
+      let mut buckets = BTreeMap::new();
+      for snapshot in old {
+        buckets.insert(snapshot.timestamp / interval, snapshot);
+      }
+
+      snapshots = buckets.into_values().chain(recent).collect();
+    }
+
+    // Write to a temporary file first, so a crash can't lose the whole history
+    let tmp = path.with_extension(format!("{EXTENSION}.tmp"));
+    let mut file = File::create(&tmp)?;
+    for snapshot in &snapshots {
+      writeln!(file, "{}", serde_json::to_string(snapshot)?)?;
+    }
+    file.sync_all()?;
+    fs::rename(tmp, path)?;
+    drop(locked);
+
+    Ok(())
+  }
+
+  fn read_all(&self, disk_id: &str) -> Result<Vec<DiskSnapshot>, Box<dyn Error>> {
+    let path = self.file_for(disk_id);
+
+    if !path.exists() {
+      return Ok(vec![]);
+    }
+
+    read_file(&path)
+  }
+
+  /// The file for a disk. Anything that isn't safe in a file name is percent-encoded, so different IDs (eg. `a/b` and
+  /// `a_b`) never share a file.
+  fn file_for(&self, disk_id: &str) -> PathBuf {
+    let mut name = String::new();
+
+    for byte in disk_id.bytes() {
+      if byte.is_ascii_alphanumeric() || b"-_.".contains(&byte) {
+        name.push(byte as char);
+      } else {
+        name.push_str(&format!("%{byte:02X}"));
+      }
+    }
+
+    self.dir.join(format!("{name}.{EXTENSION}"))
+  }
+}
+
+/// Open a history file and take an exclusive lock on it, waiting for any other writer
+fn lock(path: &Path, create: bool) -> Result<File, Box<dyn Error>> {
+  loop {
+    let file = OpenOptions::new()
+      .read(true)
+      .append(true)
+      .create(create)
+      .open(path)?;
+    file.lock()?;
+
+    // Compaction may have replaced the file while we were waiting, in which case this one is already unlinked
+    match fs::metadata(path) {
+      Ok(current) if current.ino() == file.metadata()?.ino() => return Ok(file),
+      Ok(_) => continue,
+      Err(e) if e.kind() == ErrorKind::NotFound => continue,
+      Err(e) => return Err(e.into()),
+    }
+  }
+}
+
+/// Read every snapshot in a file, skipping lines that can't be parsed
+fn read_file(path: &Path) -> Result<Vec<DiskSnapshot>, Box<dyn Error>> {
+  read_lines(&File::open(path)?)
+}
+
+fn read_lines(file: &File) -> Result<Vec<DiskSnapshot>, Box<dyn Error>> {
+  let mut snapshots = vec![];
+
+  for line in BufReader::new(file).lines() {
+    if let Ok(snapshot) = serde_json::from_str::<DiskSnapshot>(&line?) {
+      snapshots.push(snapshot);
+    }
+  }
+
+  Ok(snapshots)
+}
@@ -0,0 +1,220 @@
+use std::{
+  error::Error,
+  fmt::Display,
+  fs,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+#[cfg(target_os = "linux")]
+static BY_ID_PATH: &str = "/dev/disk/by-id";
+
+/// A stable identifier for a physical disk. Unlike the device path (eg. `/dev/sdb`), this stays the same across reboots and hotplugs.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum DiskId {
+  /// World Wide Name, stored as lowercase hex without the `0x` prefix
+  Wwn(String),
+  /// NVMe IEEE Extended Unique Identifier (EUI-64)
+  Eui64(String),
+  /// NVMe Namespace Globally Unique Identifier (NGUID)
+  Nguid(String),
+  /// Fallback for drives without a WWN/EUI. Spaces are replaced with underscores, the same way udev does it.
+  SerialModel { model: String, serial: String },
+}
+
+impl DiskId {
+  /// Get the stable identifier for a disk path (e.g. `"/dev/sda"`) or [`super::disk::Disk`]
+  pub fn for_disk(disk: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+    let drive = disk
+      .as_ref()
+      .file_name()
+      .unwrap_or_default()
+      .to_str()
+      .unwrap_or_default()
+      .to_string();
+
+    // /dev/disk/by-id is the most complete source, since udev has already done the work for us
+    let mut ids = by_id_links()
+      .unwrap_or_default()
+      .into_iter()
+      .filter(|(_, target)| target == &drive)
+      .filter_map(|(name, _)| DiskId::from_by_id_name(&name))
+      .collect::<Vec<DiskId>>();
+
+    // Otherwise, fall back to sysfs
+    if ids.is_empty() {
+      ids = sysfs_ids(&drive);
+    }
+
+    ids.sort_by_key(|id| (id.priority(), id.to_string().len()));
+    ids
+      .into_iter()
+      .next()
+      .ok_or(format!("No stable identifier found for {drive}").into())
+  }
+
+  /// Parse the name of a `/dev/disk/by-id` link (e.g. `"wwn-0x5000c500a1b2c3d4"`). Partition links are ignored.
+  pub fn from_by_id_name(name: impl AsRef<str>) -> Option<Self> {
+    let name = name.as_ref();
+
+    if name.contains("-part") {
+      return None;
+    }
+
+    if let Some(wwn) = name.strip_prefix("wwn-0x") {
+      return Some(DiskId::Wwn(wwn.to_lowercase()));
+    }
+
+    if let Some(eui) = name.strip_prefix("nvme-eui.") {
+      return DiskId::from_eui(eui);
+    }
+
+    // eg. ata-Samsung_SSD_860_EVO_500GB_S3Z1NB0K123456
+    for prefix in ["ata-", "nvme-", "usb-"] {
+      if let Some(rest) = name.strip_prefix(prefix) {
+        let (model, serial) = rest.rsplit_once('_')?;
+        return Some(DiskId::serial_model(model, serial));
+      }
+    }
+
+    None
+  }
+
+  /// Create a [`DiskId::SerialModel`], normalizing whitespace the same way udev does
+  pub fn serial_model(model: impl AsRef<str>, serial: impl AsRef<str>) -> Self {
+    let normalize = |s: &str| s.split_whitespace().collect::<Vec<&str>>().join("_");
+
+    DiskId::SerialModel {
+      model: normalize(model.as_ref()),
+      serial: normalize(serial.as_ref()),
+    }
+  }
+
+  /// Find the `/dev/disk/by-id` link for this identifier
+  pub fn by_id_path(&self) -> Option<PathBuf> {
+    let mut links = by_id_links().ok()?;
+    // Shortest name first, so that eg. nvme-Model_Serial wins over nvme-Model_Serial_1
+    links.sort_by_key(|(name, _)| name.len());
+
+    links
+      .into_iter()
+      .find(|(name, _)| DiskId::from_by_id_name(name).as_ref() == Some(self))
+      .map(|(name, _)| PathBuf::from(BY_ID_PATH).join(name))
+  }
+
+  /// Resolve this identifier to the current device path (e.g. `"/dev/sdc"`)
+  pub fn resolve(&self) -> Result<PathBuf, Box<dyn Error>> {
+    let link = self
+      .by_id_path()
+      .ok_or(format!("No disk found with id {self}"))?;
+
+    Ok(fs::canonicalize(link)?)
+  }
+
+  fn from_eui(eui: &str) -> Option<Self> {
+    let eui = eui.to_lowercase();
+
+    match eui.len() {
+      16 => Some(DiskId::Eui64(eui)),
+      32 => Some(DiskId::Nguid(eui)),
+      _ => None,
+    }
+  }
+
+  fn priority(&self) -> u8 {
+    match self {
+      DiskId::Wwn(_) => 0,
+      DiskId::Eui64(_) => 1,
+      DiskId::Nguid(_) => 2,
+      DiskId::SerialModel { .. } => 3,
+    }
+  }
+}
+
+impl Display for DiskId {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DiskId::Wwn(wwn) => write!(f, "wwn-0x{wwn}"),
+      DiskId::Eui64(eui) => write!(f, "eui.{eui}"),
+      DiskId::Nguid(nguid) => write!(f, "nguid.{nguid}"),
+      DiskId::SerialModel { model, serial } => write!(f, "{model}_{serial}"),
+    }
+  }
+}
+
+impl FromStr for DiskId {
+  type Err = Box<dyn Error>;
+
+  /// Parse the format produced by [`Display`]
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    if let Some(wwn) = s.strip_prefix("wwn-0x") {
+      return Ok(DiskId::Wwn(wwn.to_lowercase()));
+    }
+
+    if let Some(eui) = s.strip_prefix("eui.") {
+      return Ok(DiskId::Eui64(eui.to_lowercase()));
+    }
+
+    if let Some(nguid) = s.strip_prefix("nguid.") {
+      return Ok(DiskId::Nguid(nguid.to_lowercase()));
+    }
+
+    let (model, serial) = s.rsplit_once('_').ok_or(format!("Invalid disk id: {s}"))?;
+
+    Ok(DiskId::serial_model(model, serial))
+  }
+}
+
+/// List all `/dev/disk/by-id` links as `(link name, target device name)`
+fn by_id_links() -> Result<Vec<(String, String)>, Box<dyn Error>> {
+  let mut links = vec![];
+
+  for entry in fs::read_dir(BY_ID_PATH)? {
+    let entry = entry?;
+    let name = entry.file_name().to_string_lossy().to_string();
+    // The link should be something like ../../sdX
+    let target = fs::read_link(entry.path())
+      .unwrap_or_default()
+      .file_name()
+      .unwrap_or_default()
+      .to_string_lossy()
+      .to_string();
+
+    links.push((name, target));
+  }
+
+  Ok(links)
+}
+
+fn sysfs_ids(drive: &str) -> Vec<DiskId> {
+  let read = |file: &str| {
+    fs::read_to_string(format!("/sys/block/{drive}/{file}"))
+      .map(|s| s.trim().to_string())
+      .ok()
+      .filter(|s| !s.is_empty())
+  };
+  let mut ids = vec![];
+
+  // SCSI/ATA use device/wwid (eg. naa.5000c500a1b2c3d4), NVMe uses wwid (eg. eui.0025388b91b2c3d4)
+  for wwid in [read("device/wwid"), read("wwid")].into_iter().flatten() {
+    if let Some(naa) = wwid.strip_prefix("naa.") {
+      ids.push(DiskId::Wwn(naa.to_lowercase()));
+    } else if let Some(eui) = wwid.strip_prefix("eui.") {
+      ids.extend(DiskId::from_eui(eui));
+    }
+  }
+
+  // NVMe also exposes these directly, all-zero means "not reported"
+  for eui in [read("eui"), read("nguid")].into_iter().flatten() {
+    let eui = eui.replace([' ', '-'], "");
+    if eui.chars().any(|c| c != '0') {
+      ids.extend(DiskId::from_eui(&eui));
+    }
+  }
+
+  if let (Some(model), Some(serial)) = (read("device/model"), read("device/serial")) {
+    ids.push(DiskId::serial_model(model, serial));
+  }
+
+  ids
+}
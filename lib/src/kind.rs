@@ -5,6 +5,7 @@ use std::{
 
 /// A struct representing the kind of disk (SSD, HDD, etc.)
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DiskKind {
   SSD,
   HDD,
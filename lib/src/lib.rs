@@ -3,13 +3,37 @@
 use std::{error::Error, path::PathBuf};
 
 use disk::{get_disk_paths, Disk};
+use md::MdArray;
+use power::ReadPolicy;
 
+#[cfg(feature = "alerts")]
+pub mod alert;
 pub mod ata;
 pub mod attribute;
 pub mod benchmark;
+pub mod cache;
 pub mod disk;
+pub mod endurance;
+pub mod filesystem;
+pub mod health;
+#[cfg(feature = "history")]
+pub mod history;
+pub mod id;
 pub mod kind;
+pub mod md;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod mount;
+pub mod nvme;
+pub mod partition;
+pub mod power;
+pub mod scan;
+pub mod selftest;
+pub mod sgio;
+pub mod snapshot;
+pub mod stack;
 pub mod sysfs;
+pub mod trend;
 
 // Re-export libatasmart
 pub use libatasmart;
@@ -20,11 +44,16 @@ static DEV_PATH: &str = "/dev";
 
 /// List all disks on the system
 pub fn list_disks() -> Result<Vec<Disk>, Box<dyn Error>> {
+  list_disks_with_policy(ReadPolicy::default())
+}
+
+/// List all disks on the system, using the given [`ReadPolicy`] for each. Use [`ReadPolicy::SkipIfSleeping`] to avoid spinning up drives in standby.
+pub fn list_disks_with_policy(read_policy: ReadPolicy) -> Result<Vec<Disk>, Box<dyn Error>> {
   let mut list = vec![];
   let disks = get_disk_paths()?;
 
   for disk in disks {
-    let d = Disk::new(PathBuf::from(format!("{}/{}", DEV_PATH, disk)));
+    let d = Disk::with_policy(PathBuf::from(format!("{}/{}", DEV_PATH, disk)), read_policy);
 
     if let Ok(d) = d {
       list.push(d);
@@ -33,3 +62,9 @@ pub fn list_disks() -> Result<Vec<Disk>, Box<dyn Error>> {
 
   Ok(list)
 }
+
+/// List all md software RAID arrays on the system. The arrays themselves aren't in [`list_disks`], since they have no
+/// SMART data of their own, but [`MdArray::disks`] opens the disks behind them.
+pub fn list_md_arrays() -> Result<Vec<MdArray>, Box<dyn Error>> {
+  md::arrays()
+}
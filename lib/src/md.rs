@@ -0,0 +1,435 @@
+use std::{
+  collections::BTreeSet,
+  error::Error,
+  fs,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use crate::{disk::Disk, stack::whole_disk};
+
+#[cfg(target_os = "linux")]
+static MDSTAT_PATH: &str = "/proc/mdstat";
+#[cfg(target_os = "linux")]
+static SYS_BLOCK_PATH: &str = "/sys/block";
+
+/// A Linux software RAID array (e.g. `/dev/md0`)
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MdArray {
+  /// e.g. `"md0"`
+  pub name: String,
+  pub path: PathBuf,
+  /// e.g. `"raid1"`. `None` for inactive arrays, which haven't been assembled yet.
+  pub level: Option<String>,
+  /// e.g. `"clean"`, `"active"` or `"inactive"`
+  pub state: String,
+  /// How many members the array should have
+  pub raid_disks: usize,
+  /// How many members are missing or failed
+  pub degraded: usize,
+  /// A resync, recovery, check or reshape, if one is running
+  pub sync: Option<MdSync>,
+  pub members: Vec<MdMember>,
+}
+
+/// A running resync, recovery, check or reshape
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MdSync {
+  /// e.g. `"resync"`, `"recover"`, `"check"` or `"reshape"`
+  pub action: String,
+  /// Progress in 512 byte sectors
+  pub done: u64,
+  pub total: u64,
+  /// Bytes per second
+  pub speed: Option<u64>,
+}
+
+/// The role of a member within an array
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MdRole {
+  /// An active member, in the given slot
+  Active(usize),
+  Spare,
+  Journal,
+  /// Failed, or not in a slot for some other reason
+  #[default]
+  None,
+}
+
+/// A device that is part of an [`MdArray`]
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MdMember {
+  /// e.g. `"sda1"`
+  pub name: String,
+  pub path: PathBuf,
+  /// The whole disk this member lives on (e.g. `/dev/sda` for `/dev/sda1`)
+  pub disk: PathBuf,
+  pub role: MdRole,
+  /// Flags from the kernel, e.g. `"in_sync"`, `"faulty"`, `"spare"` or `"write_mostly"`
+  pub state: Vec<String>,
+}
+
+impl MdSync {
+  /// Progress from 0 to 100
+  pub fn progress(&self) -> f64 {
+    if self.total == 0 {
+      return 0.;
+    }
+
+    self.done as f64 / self.total as f64 * 100.
+  }
+
+  /// Estimated time left at the current speed
+  pub fn remaining(&self) -> Option<Duration> {
+    let speed = self.speed.filter(|speed| *speed > 0)?;
+    let bytes = self.total.saturating_sub(self.done) * 512;
+
+    Some(Duration::from_secs(bytes / speed))
+  }
+}
+
+impl MdMember {
+  pub fn is_faulty(&self) -> bool {
+    self.state.iter().any(|state| state == "faulty")
+  }
+
+  /// Open the disk behind this member, to read its SMART data
+  pub fn open_disk(&self) -> Result<Disk, Box<dyn Error>> {
+    Disk::new(self.disk.clone())
+  }
+}
+
+impl MdArray {
+  /// Read an array by name (e.g. `"md0"`)
+  pub fn for_name(name: impl AsRef<str>) -> Result<Self, Box<dyn Error>> {
+    let name = name.as_ref();
+
+    arrays()?
+      .into_iter()
+      .find(|array| array.name == name)
+      .ok_or(format!("No md array named {name}").into())
+  }
+
+  pub fn is_degraded(&self) -> bool {
+    self.degraded > 0 || self.members.iter().any(|member| member.is_faulty())
+  }
+
+  /// Whether a disk (e.g. `/dev/sda`) or one of its partitions is part of this array
+  pub fn contains(&self, disk: impl AsRef<Path>) -> bool {
+    let disk = disk.as_ref();
+
+    self
+      .members
+      .iter()
+      .any(|member| member.disk == disk || member.path == disk)
+  }
+
+  /// Open every disk behind this array. Disks that fail to open are skipped.
+  pub fn disks(&self) -> Vec<Disk> {
+    // Several partitions of one disk can be members, in any slot
+    let paths = self
+      .members
+      .iter()
+      .map(|member| member.disk.clone())
+      .collect::<BTreeSet<PathBuf>>();
+
+    paths
+      .into_iter()
+      .filter_map(|path| Disk::new(path).ok())
+      .collect()
+  }
+
+  /// Fill in what sysfs knows, which is more precise than `/proc/mdstat`
+  fn read_sysfs(&mut self) {
+    let md = Path::new(SYS_BLOCK_PATH).join(&self.name).join("md");
+    let read = |file: &str| {
+      fs::read_to_string(md.join(file))
+        .ok()
+        .map(|value| value.trim().to_string())
+    };
+
+    if !md.exists() {
+      return;
+    }
+
+    if let Some(level) = read("level") {
+      self.level = Some(level).filter(|level| !level.is_empty());
+    }
+
+    if let Some(state) = read("array_state") {
+      self.state = state;
+    }
+
+    if let Some(raid_disks) = read("raid_disks").and_then(|n| n.parse().ok()) {
+      self.raid_disks = raid_disks;
+    }
+
+    if let Some(degraded) = read("degraded").and_then(|n| n.parse().ok()) {
+      self.degraded = degraded;
+    }
+
+    match read("sync_action").as_deref() {
+      None => {}
+      Some("idle") | Some("frozen") => self.sync = None,
+      Some(action) => {
+        // "done / total", in sectors
+        let (done, total) = read("sync_completed")
+          .and_then(|completed| {
+            let (done, total) = completed.split_once('/')?;
+            Some((done.trim().parse().ok()?, total.trim().parse().ok()?))
+          })
+          .unwrap_or_default();
+
+        self.sync = Some(MdSync {
+          action: action.to_string(),
+          done,
+          total,
+          speed: read("sync_speed")
+            .and_then(|speed| speed.parse::<u64>().ok())
+            .map(|kb| kb * 1024),
+        });
+      }
+    }
+
+    let Ok(entries) = fs::read_dir(&md) else {
+      return;
+    };
+
+    let mut members = entries
+      .flatten()
+      .filter_map(|entry| {
+        let name = entry
+          .file_name()
+          .to_string_lossy()
+          .strip_prefix("dev-")?
+          .to_string();
+        let dir = entry.path();
+        let read = |file: &str| {
+          fs::read_to_string(dir.join(file))
+            .ok()
+            .map(|value| value.trim().to_string())
+        };
+
+        let state = read("state")
+          .unwrap_or_default()
+          .split(',')
+          .filter(|flag| !flag.is_empty())
+          .map(|flag| flag.to_string())
+          .collect::<Vec<String>>();
+
+        let role = match read("slot").and_then(|slot| slot.parse().ok()) {
+          Some(slot) => MdRole::Active(slot),
+          None if state.iter().any(|s| s == "journal") => MdRole::Journal,
+          None if state.iter().any(|s| s == "spare") => MdRole::Spare,
+          None => MdRole::None,
+        };
+
+        Some(MdMember {
+          path: PathBuf::from("/dev").join(&name),
+          disk: whole_disk(Path::new("/dev").join(&name)),
+          name,
+          role,
+          state,
+        })
+      })
+      .collect::<Vec<MdMember>>();
+
+    if !members.is_empty() {
+      members.sort_by_key(member_order);
+      self.members = members;
+    }
+  }
+}
+
+/// List every md array on the system. Returns an empty list if the md driver isn't loaded.
+pub fn arrays() -> Result<Vec<MdArray>, Box<dyn Error>> {
+  let contents = match fs::read_to_string(MDSTAT_PATH) {
+    Ok(contents) => contents,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+    Err(e) => return Err(e.into()),
+  };
+
+  let mut arrays = parse_mdstat(&contents);
+
+  for array in arrays.iter_mut() {
+    for member in array.members.iter_mut() {
+      member.disk = whole_disk(&member.path);
+    }
+
+    array.read_sysfs();
+  }
+
+  Ok(arrays)
+}
+
+/// List the arrays a disk (e.g. `/dev/sda`) belongs to
+pub fn arrays_for_disk(disk: impl AsRef<Path>) -> Result<Vec<MdArray>, Box<dyn Error>> {
+  Ok(
+    arrays()?
+      .into_iter()
+      .filter(|array| array.contains(&disk))
+      .collect(),
+  )
+}
+
+/// Parse the contents of `/proc/mdstat`. Member disks are assumed to be the members themselves, since mdstat doesn't
+/// say which disk a partition is on.
+pub fn parse_mdstat(contents: &str) -> Vec<MdArray> {
+  let mut arrays: Vec<MdArray> = vec![];
+
+  for line in contents.lines() {
+    // eg. "md0 : active raid1 sdb1[1] sda1[0](F)"
+    if let Some((name, rest)) = line.split_once(" : ") {
+      let name = name.trim();
+      if !name.starts_with("md") {
+        continue;
+      }
+
+      let mut words = rest.split_whitespace().peekable();
+      let state = words.next().unwrap_or_default().to_string();
+
+      // Skip "(read-only)" and "(auto-read-only)"
+      while words.peek().is_some_and(|word| word.starts_with('(')) {
+        words.next();
+      }
+
+      let level = if state == "inactive" {
+        None
+      } else {
+        words.next().map(|level| level.to_string())
+      };
+
+      let mut members = words.filter_map(parse_member).collect::<Vec<MdMember>>();
+      members.sort_by_key(member_order);
+
+      arrays.push(MdArray {
+        name: name.to_string(),
+        path: PathBuf::from("/dev").join(name),
+        level,
+        state,
+        raid_disks: members
+          .iter()
+          .filter(|m| matches!(m.role, MdRole::Active(_)))
+          .count(),
+        members,
+        ..Default::default()
+      });
+
+      continue;
+    }
+
+    let Some(array) = arrays.last_mut() else {
+      continue;
+    };
+    let line = line.trim();
+
+    // eg. "1953258496 blocks super 1.2 [2/1] [U_]"
+    if let Some(status) = line
+      .split_whitespace()
+      .find(|word| word.starts_with('[') && word.contains('/'))
+    {
+      let counts = status.trim_matches(|c| c == '[' || c == ']');
+      if let Some((total, working)) = counts.split_once('/') {
+        if let (Ok(total), Ok(working)) = (total.parse::<usize>(), working.parse::<usize>()) {
+          array.raid_disks = total;
+          array.degraded = total.saturating_sub(working);
+        }
+      }
+    }
+
+    // eg. "[=>...]  recovery =  8.1% (79178624/976629248) finish=87.4min speed=171093K/sec"
+    if let Some((action, progress)) = line
+      .trim_start_matches(|c| "[=>.] ".contains(c))
+      .split_once(" = ")
+    {
+      let action = action.trim();
+      if !["resync", "recovery", "check", "repair", "reshape"].contains(&action) {
+        continue;
+      }
+
+      let (done, total) = progress
+        .split_whitespace()
+        .find_map(|word| {
+          let (done, total) = word
+            .trim_matches(|c| c == '(' || c == ')')
+            .split_once('/')?;
+          Some((done.parse::<u64>().ok()?, total.parse::<u64>().ok()?))
+        })
+        .unwrap_or_default();
+
+      let speed = progress.split_whitespace().find_map(|word| {
+        let kb = word.strip_prefix("speed=")?.strip_suffix("K/sec")?;
+        kb.parse::<u64>().ok().map(|kb| kb * 1024)
+      });
+
+      array.sync = Some(MdSync {
+        // sysfs calls it "recover"
+        action: if action == "recovery" {
+          "recover".to_string()
+        } else {
+          action.to_string()
+        },
+        // mdstat counts in 1K blocks
+        done: done * 2,
+        total: total * 2,
+        speed,
+      });
+    }
+  }
+
+  arrays
+}
+
+/// Parse a member like `"sda1[0]"` or `"sdb1[1](F)"`
+fn parse_member(word: &str) -> Option<MdMember> {
+  let (name, rest) = word.split_once('[')?;
+  let (slot, flags) = rest.split_once(']')?;
+  let slot = slot.parse::<usize>().ok()?;
+
+  let mut state = vec![];
+  let mut role = MdRole::Active(slot);
+
+  for flag in flags.split(')').map(|flag| flag.trim_start_matches('(')) {
+    match flag {
+      "F" => {
+        state.push("faulty".to_string());
+        role = MdRole::None;
+      }
+      "S" => {
+        state.push("spare".to_string());
+        role = MdRole::Spare;
+      }
+      "J" => {
+        state.push("journal".to_string());
+        role = MdRole::Journal;
+      }
+      "W" => state.push("write_mostly".to_string()),
+      "R" => state.push("replacement".to_string()),
+      _ => {}
+    }
+  }
+
+  if role == MdRole::Active(slot) {
+    state.insert(0, "in_sync".to_string());
+  }
+
+  Some(MdMember {
+    name: name.to_string(),
+    path: PathBuf::from("/dev").join(name),
+    disk: PathBuf::from("/dev").join(name),
+    role,
+    state,
+  })
+}
+
+/// Active members by slot, then everything else
+fn member_order(member: &MdMember) -> (usize, String) {
+  match member.role {
+    MdRole::Active(slot) => (slot, member.name.clone()),
+    _ => (usize::MAX, member.name.clone()),
+  }
+}
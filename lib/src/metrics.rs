@@ -0,0 +1,392 @@
+use std::fmt::Write;
+
+use crate::{health::HealthStatus, snapshot::DiskSnapshot};
+
+/// Content type of the Prometheus text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+const PREFIX: &str = "glacierdisk";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Kind {
+  Gauge,
+  Counter,
+}
+
+struct Family {
+  name: String,
+  help: &'static str,
+  kind: Kind,
+  samples: Vec<(String, f64)>,
+}
+
+/// Collects samples, grouped by metric, in the order they were first added
+#[derive(Default)]
+struct Families(Vec<Family>);
+
+impl Families {
+  fn add(&mut self, name: &str, help: &'static str, kind: Kind, labels: &str, value: f64) {
+    let name = format!("{PREFIX}_{name}");
+    let index = match self.0.iter().position(|family| family.name == name) {
+      Some(index) => index,
+      None => {
+        self.0.push(Family {
+          name,
+          help,
+          kind,
+          samples: vec![],
+        });
+        self.0.len() - 1
+      }
+    };
+
+    self.0[index].samples.push((labels.to_string(), value));
+  }
+
+  fn gauge(&mut self, name: &str, help: &'static str, labels: &str, value: f64) {
+    self.add(name, help, Kind::Gauge, labels, value);
+  }
+
+  fn counter(&mut self, name: &str, help: &'static str, labels: &str, value: f64) {
+    self.add(name, help, Kind::Counter, labels, value);
+  }
+}
+
+/// Render snapshots in the Prometheus text format. Every metric is labelled with `device`, `model`, `serial` and `kind`.
+pub fn render(snapshots: &[DiskSnapshot]) -> String {
+  let mut families = Families::default();
+
+  for snapshot in snapshots {
+    add_snapshot(&mut families, snapshot);
+  }
+
+  // Writing to a String can't fail
+  let mut out = String::new();
+  for family in families.0 {
+    let kind = match family.kind {
+      Kind::Gauge => "gauge",
+      Kind::Counter => "counter",
+    };
+
+    let _ = writeln!(out, "# HELP {} {}", family.name, family.help);
+    let _ = writeln!(out, "# TYPE {} {kind}", family.name);
+
+    for (labels, value) in family.samples {
+      let _ = writeln!(out, "{}{{{labels}}} {}", family.name, format_value(value));
+    }
+  }
+
+  out
+}
+
+fn add_snapshot(families: &mut Families, snapshot: &DiskSnapshot) {
+  let labels = [
+    ("device", snapshot.path.display().to_string()),
+    ("model", snapshot.model.clone().unwrap_or_default()),
+    ("serial", snapshot.serial.clone().unwrap_or_default()),
+    (
+      "kind",
+      snapshot
+        .kind
+        .as_ref()
+        .map(|kind| kind.to_string())
+        .unwrap_or_default(),
+    ),
+  ];
+  let base = format_labels(&labels);
+  let with = |extra: &[(&str, String)]| format!("{base},{}", format_labels(extra));
+
+  families.gauge(
+    "info",
+    "Disk identity, always 1",
+    &with(&[("id", snapshot.id.clone())]),
+    1.,
+  );
+  families.gauge(
+    "sleeping",
+    "Whether the disk was in standby, in which case there is no SMART data",
+    &base,
+    snapshot.sleeping as u8 as f64,
+  );
+  families.gauge(
+    "snapshot_timestamp_seconds",
+    "When the SMART data was read",
+    &base,
+    snapshot.timestamp as f64,
+  );
+
+  if let Some(temperature) = snapshot.temperature {
+    families.gauge(
+      "temperature_celsius",
+      "Disk temperature",
+      &base,
+      temperature,
+    );
+  }
+
+  if let Some(hours) = snapshot.power_on_hours {
+    families.counter(
+      "power_on_hours_total",
+      "Hours the disk has been powered on",
+      &base,
+      hours as f64,
+    );
+  }
+
+  if let Some(health) = snapshot.health {
+    families.gauge(
+      "health_score",
+      "Health score from 0 to 100",
+      &base,
+      health as f64,
+    );
+  }
+
+  if let Some(status) = snapshot.health_status {
+    families.gauge(
+      "health_status",
+      "Health verdict: 0 = Good, 1 = Caution, 2 = Bad",
+      &base,
+      match status {
+        HealthStatus::Good => 0.,
+        HealthStatus::Caution => 1.,
+        HealthStatus::Bad => 2.,
+      },
+    );
+  }
+
+  for attribute in &snapshot.attributes {
+    let labels = with(&[
+      ("attribute_id", attribute.id.to_string()),
+      ("attribute_name", attribute.name.clone()),
+    ]);
+
+    families.gauge(
+      "smart_attribute_value",
+      "Normalized SMART attribute value",
+      &labels,
+      attribute.current as f64,
+    );
+    families.gauge(
+      "smart_attribute_worst",
+      "Worst normalized SMART attribute value",
+      &labels,
+      attribute.worst as f64,
+    );
+    families.gauge(
+      "smart_attribute_threshold",
+      "SMART attribute failure threshold",
+      &labels,
+      attribute.threshold as f64,
+    );
+    families.gauge(
+      "smart_attribute_raw",
+      "Raw SMART attribute value (48 bits)",
+      &labels,
+      attribute.raw as f64,
+    );
+
+    if let (Some(decoded), Some(unit)) = (attribute.decoded, &attribute.unit) {
+      families.gauge(
+        "smart_attribute_decoded",
+        "SMART attribute raw value decoded into the unit in the unit label",
+        &with(&[
+          ("attribute_id", attribute.id.to_string()),
+          ("attribute_name", attribute.name.clone()),
+          ("unit", unit.clone()),
+        ]),
+        decoded,
+      );
+    }
+  }
+
+  if let Some(log) = &snapshot.nvme {
+    families.gauge(
+      "nvme_critical_warning",
+      "NVMe critical warning bitfield",
+      &base,
+      log.critical_warning as f64,
+    );
+    families.gauge(
+      "nvme_available_spare_percent",
+      "NVMe remaining spare capacity",
+      &base,
+      log.available_spare as f64,
+    );
+    families.gauge(
+      "nvme_available_spare_threshold_percent",
+      "NVMe spare capacity warning threshold",
+      &base,
+      log.available_spare_threshold as f64,
+    );
+    families.gauge(
+      "nvme_percentage_used",
+      "NVMe estimate of life used, can go over 100",
+      &base,
+      log.percentage_used as f64,
+    );
+
+    let counters: [(&str, &'static str, u128); 10] = [
+      (
+        "nvme_data_units_read_total",
+        "NVMe data read, in units of 512000 bytes",
+        log.data_units_read,
+      ),
+      (
+        "nvme_data_units_written_total",
+        "NVMe data written, in units of 512000 bytes",
+        log.data_units_written,
+      ),
+      (
+        "nvme_host_read_commands_total",
+        "NVMe read commands completed",
+        log.host_read_commands,
+      ),
+      (
+        "nvme_host_write_commands_total",
+        "NVMe write commands completed",
+        log.host_write_commands,
+      ),
+      (
+        "nvme_controller_busy_time_minutes_total",
+        "Minutes the NVMe controller was busy",
+        log.controller_busy_time,
+      ),
+      (
+        "nvme_power_cycles_total",
+        "NVMe power cycles",
+        log.power_cycles,
+      ),
+      (
+        "nvme_unsafe_shutdowns_total",
+        "NVMe unsafe shutdowns",
+        log.unsafe_shutdowns,
+      ),
+      (
+        "nvme_media_errors_total",
+        "NVMe unrecovered data integrity errors",
+        log.media_errors,
+      ),
+      (
+        "nvme_error_log_entries_total",
+        "NVMe error log entries",
+        log.error_log_entries,
+      ),
+      (
+        "nvme_power_on_hours_total",
+        "NVMe power on hours",
+        log.power_on_hours,
+      ),
+    ];
+
+    for (name, help, value) in counters {
+      families.counter(name, help, &base, value as f64);
+    }
+  }
+
+  if let Some(stat) = &snapshot.stat {
+    let counters: [(&str, &'static str, u64); 14] = [
+      ("stat_read_ios_total", "Read I/Os completed", stat.read_io),
+      (
+        "stat_write_ios_total",
+        "Write I/Os completed",
+        stat.write_io,
+      ),
+      (
+        "stat_discard_ios_total",
+        "Discard I/Os completed",
+        stat.discard_io,
+      ),
+      (
+        "stat_read_merges_total",
+        "Read I/Os merged",
+        stat.read_merge,
+      ),
+      (
+        "stat_write_merges_total",
+        "Write I/Os merged",
+        stat.write_merge,
+      ),
+      (
+        "stat_discard_merges_total",
+        "Discard I/Os merged",
+        stat.discard_merge,
+      ),
+      (
+        "stat_read_sectors_total",
+        "512 byte sectors read",
+        stat.read_sectors,
+      ),
+      (
+        "stat_write_sectors_total",
+        "512 byte sectors written",
+        stat.write_sectors,
+      ),
+      (
+        "stat_discard_sectors_total",
+        "512 byte sectors discarded",
+        stat.discard_sectors,
+      ),
+      (
+        "stat_read_ticks_milliseconds_total",
+        "Milliseconds spent reading",
+        stat.read_ticks,
+      ),
+      (
+        "stat_write_ticks_milliseconds_total",
+        "Milliseconds spent writing",
+        stat.write_ticks,
+      ),
+      (
+        "stat_discard_ticks_milliseconds_total",
+        "Milliseconds spent discarding",
+        stat.discard_ticks,
+      ),
+      (
+        "stat_io_ticks_milliseconds_total",
+        "Milliseconds spent doing I/O",
+        stat.io_ticks,
+      ),
+      (
+        "stat_time_in_queue_milliseconds_total",
+        "Weighted milliseconds spent doing I/O",
+        stat.time_in_queue,
+      ),
+    ];
+
+    for (name, help, value) in counters {
+      families.counter(name, help, &base, value as f64);
+    }
+
+    families.gauge(
+      "stat_in_flight",
+      "I/Os currently in flight",
+      &base,
+      stat.in_flight as f64,
+    );
+  }
+}
+
+fn format_labels(labels: &[(&str, String)]) -> String {
+  labels
+    .iter()
+    .map(|(name, value)| {
+      let value = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+      format!("{name}=\"{value}\"")
+    })
+    .collect::<Vec<String>>()
+    .join(",")
+}
+
+/// Rust writes infinity as `inf`, but Prometheus only accepts `+Inf`, `-Inf` and `NaN`
+fn format_value(value: f64) -> String {
+  match value {
+    f64::INFINITY => "+Inf".into(),
+    f64::NEG_INFINITY => "-Inf".into(),
+    value if value.is_nan() => "NaN".into(),
+    value => value.to_string(),
+  }
+}
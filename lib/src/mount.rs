@@ -0,0 +1,219 @@
+use std::{
+  error::Error,
+  ffi::CString,
+  fmt::Display,
+  fs,
+  os::unix::ffi::OsStrExt,
+  path::{Path, PathBuf},
+};
+
+use crate::stack;
+
+#[cfg(target_os = "linux")]
+static MOUNTINFO_PATH: &str = "/proc/self/mountinfo";
+
+/// A mounted filesystem, from `/proc/self/mountinfo`
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mount {
+  /// Where the filesystem is mounted, e.g. `/home`
+  pub mount_point: PathBuf,
+  /// e.g. `"ext4"` or `"btrfs"`
+  pub fs_type: String,
+  /// Options for this mount point, e.g. `["rw", "relatime"]`
+  pub options: Vec<String>,
+  /// Options for the filesystem as a whole, e.g. `["subvol=/@home"]`
+  pub super_options: Vec<String>,
+  /// The directory within the filesystem that is mounted. `/` unless this is a bind mount or btrfs subvolume.
+  pub root: PathBuf,
+  /// What was passed to `mount`, e.g. `"/dev/mapper/vg-root"`
+  pub source: String,
+  /// The block device the filesystem is on, e.g. `/dev/sda1` or `/dev/dm-0`
+  pub device: Option<PathBuf>,
+  /// `major:minor`. Filesystems that span several devices (e.g. btrfs) get an anonymous one with major 0.
+  pub device_number: (u32, u32),
+}
+
+/// Space and inode usage of a filesystem, from `statvfs`
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FsUsage {
+  /// In bytes
+  pub total: u64,
+  pub used: u64,
+  /// What an unprivileged user can still write. Usually less than `total - used`, since ext4 reserves some for root.
+  pub available: u64,
+  pub inodes_total: u64,
+  pub inodes_used: u64,
+  pub inodes_available: u64,
+}
+
+impl FsUsage {
+  /// How full the filesystem is, from 0 to 100. Like `df`, this counts reserved space as unavailable.
+  pub fn percent_used(&self) -> f64 {
+    let usable = self.used + self.available;
+    if usable == 0 {
+      return 0.;
+    }
+
+    self.used as f64 / usable as f64 * 100.
+  }
+}
+
+impl Mount {
+  /// Get the space and inode usage of this filesystem
+  pub fn usage(&self) -> Result<FsUsage, Box<dyn Error>> {
+    usage(&self.mount_point)
+  }
+
+  pub fn is_read_only(&self) -> bool {
+    self.options.iter().any(|option| option == "ro")
+  }
+
+  /// The btrfs subvolume, e.g. `"/@home"`
+  pub fn subvolume(&self) -> Option<&str> {
+    self
+      .super_options
+      .iter()
+      .find_map(|option| option.strip_prefix("subvol="))
+  }
+}
+
+impl Display for Mount {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.mount_point.display())
+  }
+}
+
+/// Get the space and inode usage of the filesystem a path is on
+pub fn usage(path: impl AsRef<Path>) -> Result<FsUsage, Box<dyn Error>> {
+  let path = path.as_ref();
+  let c_path = CString::new(path.as_os_str().as_bytes())?;
+  let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+  if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+    let e = std::io::Error::last_os_error();
+    return Err(format!("Failed to statvfs {} ({e})", path.display()).into());
+  }
+
+  let block_size = stat.f_frsize as u64;
+
+  Ok(FsUsage {
+    total: stat.f_blocks as u64 * block_size,
+    used: (stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64) * block_size,
+    available: stat.f_bavail as u64 * block_size,
+    inodes_total: stat.f_files as u64,
+    inodes_used: (stat.f_files as u64).saturating_sub(stat.f_ffree as u64),
+    inodes_available: stat.f_favail as u64,
+  })
+}
+
+/// List every mounted filesystem
+pub fn mounts() -> Result<Vec<Mount>, Box<dyn Error>> {
+  let mut mounts = parse_mountinfo(&fs::read_to_string(MOUNTINFO_PATH)?);
+
+  for mount in mounts.iter_mut() {
+    let (major, minor) = mount.device_number;
+    if major != 0 {
+      mount.device = stack::device_for_number(major, minor).or(mount.device.take());
+    }
+  }
+
+  Ok(mounts)
+}
+
+/// List the filesystems stored on a disk (e.g. `/dev/sda`), including those on its partitions and any LVM, LUKS or md
+/// devices on top of it. Bind mounts of the same filesystem are listed separately.
+pub fn mounts_for_disk(disk: impl AsRef<Path>) -> Result<Vec<Mount>, Box<dyn Error>> {
+  let disk = stack::whole_disk(disk);
+  let numbers = stack::stacked_devices(&disk)
+    .into_iter()
+    .chain([disk.clone()])
+    .filter_map(stack::device_number)
+    .collect::<Vec<(u32, u32)>>();
+
+  Ok(
+    mounts()?
+      .into_iter()
+      .filter(|mount| {
+        if numbers.contains(&mount.device_number) {
+          return true;
+        }
+
+        // Anonymous device numbers don't map to a block device, so fall back to the source
+        mount.device_number.0 == 0
+          && mount
+            .device
+            .as_ref()
+            .is_some_and(|device| stack::physical_disks(device).contains(&disk))
+      })
+      .collect(),
+  )
+}
+
+/// Parse the contents of `/proc/self/mountinfo`. See `proc(5)` for the format.
+pub fn parse_mountinfo(contents: &str) -> Vec<Mount> {
+  contents
+    .lines()
+    .filter_map(|line| {
+      // eg. "36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue"
+      let (fields, fs_fields) = line.split_once(" - ")?;
+      let mut fields = fields.split_whitespace().skip(2);
+      let (major, minor) = fields.next()?.split_once(':')?;
+      let root = fields.next()?;
+      let mount_point = fields.next()?;
+      let options = fields.next()?;
+
+      let mut fs_fields = fs_fields.split_whitespace();
+      let fs_type = fs_fields.next()?;
+      let source = unescape(fs_fields.next().unwrap_or_default());
+      let super_options = fs_fields.next().unwrap_or_default();
+
+      Some(Mount {
+        mount_point: PathBuf::from(unescape(mount_point)),
+        fs_type: fs_type.to_string(),
+        options: split_options(options),
+        super_options: split_options(super_options),
+        root: PathBuf::from(unescape(root)),
+        device: source.starts_with("/dev/").then(|| PathBuf::from(&source)),
+        source,
+        device_number: (major.parse().ok()?, minor.parse().ok()?),
+      })
+    })
+    .collect()
+}
+
+fn split_options(options: &str) -> Vec<String> {
+  options
+    .split(',')
+    .filter(|option| !option.is_empty())
+    .map(unescape)
+    .collect()
+}
+
+/// The kernel escapes spaces, tabs, newlines and backslashes as octal, e.g. `\040` for a space
+fn unescape(value: &str) -> String {
+  let bytes = value.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let octal = bytes
+      .get(i + 1..i + 4)
+      .filter(|digits| bytes[i] == b'\\' && digits.iter().all(|d| (b'0'..=b'7').contains(d)))
+      .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+
+    match octal {
+      Some(byte) => {
+        out.push(byte);
+        i += 4;
+      }
+      None => {
+        out.push(bytes[i]);
+        i += 1;
+      }
+    }
+  }
+
+  String::from_utf8_lossy(&out).to_string()
+}
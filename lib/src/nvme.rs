@@ -0,0 +1,185 @@
+use std::{error::Error, fs::File, os::unix::io::AsRawFd, path::Path};
+
+use crate::sgio::open_device;
+
+/// `_IOWR('N', 0x41, struct nvme_admin_cmd)`
+const NVME_IOCTL_ADMIN_CMD: libc::c_ulong = 0xc048_4e41;
+const NVME_ADMIN_GET_LOG_PAGE: u8 = 0x02;
+const LOG_SMART_HEALTH: u32 = 0x02;
+const LOG_LEN: usize = 512;
+const TIMEOUT_MS: u32 = 15_000;
+
+/// Critical warning bits from the SMART / Health Information log
+pub const WARNING_SPARE: u8 = 0x01;
+pub const WARNING_TEMPERATURE: u8 = 0x02;
+pub const WARNING_RELIABILITY: u8 = 0x04;
+pub const WARNING_READ_ONLY: u8 = 0x08;
+pub const WARNING_VOLATILE_BACKUP: u8 = 0x10;
+pub const WARNING_PMR: u8 = 0x20;
+
+/// Mirror of the kernel's `nvme_admin_cmd` struct
+#[repr(C)]
+#[derive(Default)]
+struct NvmeAdminCmd {
+  opcode: u8,
+  flags: u8,
+  rsvd1: u16,
+  nsid: u32,
+  cdw2: u32,
+  cdw3: u32,
+  metadata: u64,
+  addr: u64,
+  metadata_len: u32,
+  data_len: u32,
+  cdw10: u32,
+  cdw11: u32,
+  cdw12: u32,
+  cdw13: u32,
+  cdw14: u32,
+  cdw15: u32,
+  timeout_ms: u32,
+  result: u32,
+}
+
+/// The NVMe SMART / Health Information log page (log identifier 02h)
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NvmeSmartLog {
+  /// Bitfield, see the `WARNING_*` constants
+  pub critical_warning: u8,
+  /// Composite temperature in Kelvin
+  pub temperature: u16,
+  /// Remaining spare capacity, as a percentage
+  pub available_spare: u8,
+  /// When `available_spare` falls below this, the spare warning is raised
+  pub available_spare_threshold: u8,
+  /// Vendor estimate of life used. This can go over 100.
+  pub percentage_used: u8,
+  /// In units of 1000 512-byte blocks
+  pub data_units_read: u128,
+  /// In units of 1000 512-byte blocks
+  pub data_units_written: u128,
+  pub host_read_commands: u128,
+  pub host_write_commands: u128,
+  pub controller_busy_time: u128,
+  pub power_cycles: u128,
+  pub power_on_hours: u128,
+  pub unsafe_shutdowns: u128,
+  pub media_errors: u128,
+  pub error_log_entries: u128,
+}
+
+impl NvmeSmartLog {
+  /// Read the SMART / Health Information log from an NVMe namespace or controller (e.g. `"/dev/nvme0n1"`)
+  pub fn for_disk(disk: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+    let file = open_device(disk)?;
+    let mut data = [0u8; LOG_LEN];
+
+    get_log_page(&file, LOG_SMART_HEALTH, &mut data)?;
+
+    Ok(Self::from_bytes(&data))
+  }
+
+  /// Decode the raw 512 byte log page
+  pub fn from_bytes(data: &[u8; LOG_LEN]) -> Self {
+    let u128_at = |offset: usize| {
+      let mut bytes = [0u8; 16];
+      bytes.copy_from_slice(&data[offset..offset + 16]);
+      u128::from_le_bytes(bytes)
+    };
+
+    Self {
+      critical_warning: data[0],
+      temperature: u16::from_le_bytes([data[1], data[2]]),
+      available_spare: data[3],
+      available_spare_threshold: data[4],
+      percentage_used: data[5],
+      data_units_read: u128_at(32),
+      data_units_written: u128_at(48),
+      host_read_commands: u128_at(64),
+      host_write_commands: u128_at(80),
+      controller_busy_time: u128_at(96),
+      power_cycles: u128_at(112),
+      power_on_hours: u128_at(128),
+      unsafe_shutdowns: u128_at(144),
+      media_errors: u128_at(160),
+      error_log_entries: u128_at(176),
+    }
+  }
+
+  /// Bytes written by the host
+  pub fn bytes_written(&self) -> u128 {
+    self.data_units_written * 1000 * 512
+  }
+
+  /// Bytes read by the host
+  pub fn bytes_read(&self) -> u128 {
+    self.data_units_read * 1000 * 512
+  }
+
+  /// Composite temperature in Celsius
+  pub fn temperature_celsius(&self) -> f32 {
+    self.temperature as f32 - 273.15
+  }
+}
+
+/// Issue a Get Log Page admin command for the whole controller
+pub(crate) fn get_log_page(
+  file: &File,
+  log_id: u32,
+  data: &mut [u8],
+) -> Result<(), Box<dyn Error>> {
+  // Number of dwords, zero based
+  let numd = (data.len() / 4) as u32 - 1;
+
+  admin_command(
+    file,
+    NVME_ADMIN_GET_LOG_PAGE,
+    0xffff_ffff,
+    log_id | (numd << 16),
+    data,
+  )
+  .map_err(|e| format!("Get Log Page {log_id:#04x} failed ({e})").into())
+}
+
+/// Issue an admin command, with `data` as the buffer to transfer (in either direction)
+pub(crate) fn admin_command(
+  file: &File,
+  opcode: u8,
+  nsid: u32,
+  cdw10: u32,
+  data: &mut [u8],
+) -> Result<(), Box<dyn Error>> {
+  let mut cmd = NvmeAdminCmd {
+    opcode,
+    nsid,
+    addr: if data.is_empty() {
+      0
+    } else {
+      data.as_mut_ptr() as u64
+    },
+    data_len: data.len() as u32,
+    cdw10,
+    timeout_ms: TIMEOUT_MS,
+    ..Default::default()
+  };
+
+  let ret = unsafe {
+    libc::ioctl(
+      file.as_raw_fd(),
+      NVME_IOCTL_ADMIN_CMD as _,
+      &mut cmd as *mut NvmeAdminCmd,
+    )
+  };
+
+  if ret < 0 {
+    return Err(std::io::Error::last_os_error().into());
+  }
+
+  // A positive return is the NVMe status code
+  if ret > 0 {
+    return Err(format!("status {ret:#x}").into());
+  }
+
+  Ok(())
+}
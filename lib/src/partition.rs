@@ -0,0 +1,408 @@
+use std::{
+  error::Error,
+  fmt::Display,
+  fs::{self, File},
+  io::{Read, Seek},
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  filesystem::{self, hex, label_utf16, read_at, Filesystem},
+  stack,
+};
+
+#[cfg(target_os = "linux")]
+static SYS_CLASS_BLOCK_PATH: &str = "/sys/class/block";
+
+/// Partition start and size are always reported in these, whatever the disk's real sector size is
+pub const SECTOR_SIZE: u64 = 512;
+
+/// MBR partition types that point to a chain of logical partitions
+const MBR_EXTENDED: [u8; 3] = [0x05, 0x0f, 0x85];
+const MBR_PROTECTIVE: u8 = 0xee;
+/// Only a handful of logical partitions are sane, so don't follow a looping EBR chain forever
+const MAX_LOGICAL: u32 = 128;
+
+/// The kind of partition table on a disk
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PartitionTableKind {
+  Gpt,
+  /// Also called DOS or msdos
+  Mbr,
+}
+
+impl Display for PartitionTableKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PartitionTableKind::Gpt => write!(f, "GPT"),
+      PartitionTableKind::Mbr => write!(f, "MBR"),
+    }
+  }
+}
+
+/// A partition table, read from a disk or an image file
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartitionTable {
+  pub kind: PartitionTableKind,
+  /// The GPT disk GUID, or the MBR disk signature
+  pub id: Option<String>,
+  pub partitions: Vec<Partition>,
+}
+
+/// A partition, and the filesystem on it
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Partition {
+  /// As the kernel numbers them, e.g. `1` for `/dev/sda1`. MBR logical partitions start at 5.
+  pub number: u32,
+  /// e.g. `/dev/sda1`. `None` when reading an image file, or if the kernel doesn't know about the partition.
+  pub path: Option<PathBuf>,
+  /// In 512 byte sectors
+  pub start: u64,
+  /// In 512 byte sectors
+  pub size: u64,
+  /// GPT only, e.g. `"0fc63daf-8483-4772-8e79-3d69d8477de4"` for a Linux filesystem
+  pub type_guid: Option<String>,
+  /// GPT only. The unique GUID of this partition (PARTUUID).
+  pub guid: Option<String>,
+  /// GPT only
+  pub name: Option<String>,
+  /// MBR only, e.g. `0x83` for Linux
+  pub mbr_type: Option<u8>,
+  pub filesystem: Option<Filesystem>,
+}
+
+impl Partition {
+  pub fn size_bytes(&self) -> u64 {
+    self.size.saturating_mul(SECTOR_SIZE)
+  }
+
+  /// A readable name for the partition type, e.g. `"EFI System"`
+  pub fn type_name(&self) -> Option<&'static str> {
+    if let Some(guid) = &self.type_guid {
+      return gpt_type_name(guid);
+    }
+
+    self.mbr_type.and_then(mbr_type_name)
+  }
+}
+
+impl PartitionTable {
+  /// Read the partition table from a disk or image file, and probe each partition for a filesystem. `sector_size` is
+  /// the logical sector size of the disk, which is 512 for almost everything except 4Kn drives.
+  ///
+  /// Returns `None` if there is no partition table.
+  pub fn read<R: Read + Seek>(
+    reader: &mut R,
+    sector_size: u64,
+  ) -> Result<Option<Self>, Box<dyn Error>> {
+    if !sector_size.is_power_of_two() || !(SECTOR_SIZE..=65536).contains(&sector_size) {
+      return Err(format!("Invalid sector size {sector_size}").into());
+    }
+
+    let mbr = read_at(reader, 0, 512)?;
+    if mbr.len() < 512 || mbr[510..512] != [0x55, 0xaa] {
+      return Ok(None);
+    }
+
+    let entries = mbr_entries(&mbr);
+
+    // A protective MBR means the real table is GPT
+    let mut table = if entries.iter().any(|(kind, _, _)| *kind == MBR_PROTECTIVE) {
+      read_gpt(reader, sector_size)?
+    } else {
+      None
+    };
+
+    if table.is_none() {
+      if looks_like_boot_sector(&mbr) {
+        return Ok(None);
+      }
+
+      table = Some(read_mbr(reader, &mbr, sector_size)?);
+    }
+
+    let Some(mut table) = table else {
+      return Ok(None);
+    };
+
+    for partition in table.partitions.iter_mut() {
+      partition.filesystem = match partition.start.checked_mul(SECTOR_SIZE) {
+        Some(offset) => filesystem::probe(reader, offset)?,
+        None => None,
+      };
+    }
+
+    Ok(Some(table))
+  }
+}
+
+/// Get the partitions on a disk (e.g. `/dev/sda`). What the kernel knows (from sysfs) is combined with our own read of
+/// the partition table, which has the GPT type and name.
+pub fn partitions(disk: impl AsRef<Path>) -> Result<Vec<Partition>, Box<dyn Error>> {
+  let disk = stack::whole_disk(disk);
+  let name = stack::kernel_name(&disk);
+  let sector_size = fs::read_to_string(
+    Path::new(SYS_CLASS_BLOCK_PATH)
+      .join(&name)
+      .join("queue/logical_block_size"),
+  )
+  .ok()
+  .and_then(|size| size.trim().parse().ok())
+  .unwrap_or(SECTOR_SIZE);
+
+  let mut file = File::open(&disk)?;
+  // A table we can't make sense of still leaves what the kernel found
+  let mut partitions = PartitionTable::read(&mut file, sector_size)
+    .ok()
+    .flatten()
+    .map(|table| table.partitions)
+    .unwrap_or_default();
+
+  for path in stack::partitions(&disk) {
+    let sys = Path::new(SYS_CLASS_BLOCK_PATH).join(stack::kernel_name(&path));
+    let read =
+      |file: &str| -> Option<u64> { fs::read_to_string(sys.join(file)).ok()?.trim().parse().ok() };
+    let Some(number) = read("partition") else {
+      continue;
+    };
+
+    match partitions.iter_mut().find(|p| p.number as u64 == number) {
+      Some(partition) => partition.path = Some(path),
+      // The kernel understands more partition tables than we do
+      None => {
+        let start = read("start").unwrap_or_default();
+        partitions.push(Partition {
+          number: number as u32,
+          path: Some(path),
+          start,
+          size: read("size").unwrap_or_default(),
+          filesystem: filesystem::probe(&mut file, start.saturating_mul(SECTOR_SIZE))
+            .ok()
+            .flatten(),
+          ..Default::default()
+        });
+      }
+    }
+  }
+
+  partitions.sort_by_key(|partition| partition.number);
+
+  Ok(partitions)
+}
+
+/// The four primary entries, as (type, first sector, sector count)
+fn mbr_entries(mbr: &[u8]) -> Vec<(u8, u64, u64)> {
+  (0..4)
+    .map(|i| {
+      let entry = &mbr[446 + i * 16..446 + (i + 1) * 16];
+      (
+        entry[4],
+        u32::from_le_bytes(entry[8..12].try_into().unwrap()) as u64,
+        u32::from_le_bytes(entry[12..16].try_into().unwrap()) as u64,
+      )
+    })
+    .collect()
+}
+
+/// Unpartitioned media (e.g. a USB stick formatted as one big FAT filesystem) also ends in 0x55aa
+fn looks_like_boot_sector(mbr: &[u8]) -> bool {
+  matches!(&mbr[3..11], b"NTFS    " | b"EXFAT   ")
+    || matches!(&mbr[54..59], b"FAT12" | b"FAT16")
+    || &mbr[82..87] == b"FAT32"
+}
+
+fn read_mbr<R: Read + Seek>(
+  reader: &mut R,
+  mbr: &[u8],
+  sector_size: u64,
+) -> Result<PartitionTable, Box<dyn Error>> {
+  let to_512 = sector_size / SECTOR_SIZE;
+  let mut partitions = vec![];
+
+  for (i, (kind, start, size)) in mbr_entries(mbr).into_iter().enumerate() {
+    if kind == 0 || size == 0 {
+      continue;
+    }
+
+    partitions.push(Partition {
+      number: i as u32 + 1,
+      start: start * to_512,
+      size: size * to_512,
+      mbr_type: Some(kind),
+      ..Default::default()
+    });
+
+    if MBR_EXTENDED.contains(&kind) {
+      read_logical(reader, start, sector_size, &mut partitions)?;
+    }
+  }
+
+  let signature = u32::from_le_bytes(mbr[440..444].try_into()?);
+
+  Ok(PartitionTable {
+    kind: PartitionTableKind::Mbr,
+    id: (signature != 0).then(|| format!("{signature:08x}")),
+    partitions,
+  })
+}
+
+/// Follow the chain of extended boot records. Each has a logical partition (relative to itself), and a link to the next
+/// (relative to the start of the extended partition).
+fn read_logical<R: Read + Seek>(
+  reader: &mut R,
+  extended_start: u64,
+  sector_size: u64,
+  partitions: &mut Vec<Partition>,
+) -> Result<(), Box<dyn Error>> {
+  let to_512 = sector_size / SECTOR_SIZE;
+  let mut ebr_start = extended_start;
+
+  for number in 5..5 + MAX_LOGICAL {
+    let ebr = read_at(reader, ebr_start * sector_size, 512)?;
+    if ebr.len() < 512 || ebr[510..512] != [0x55, 0xaa] {
+      break;
+    }
+
+    let entries = mbr_entries(&ebr);
+    let (kind, start, size) = entries[0];
+    if kind != 0 && size != 0 {
+      partitions.push(Partition {
+        number,
+        start: (ebr_start + start) * to_512,
+        size: size * to_512,
+        mbr_type: Some(kind),
+        ..Default::default()
+      });
+    }
+
+    let (next_kind, next_start, _) = entries[1];
+    if !MBR_EXTENDED.contains(&next_kind) || next_start == 0 {
+      break;
+    }
+
+    ebr_start = extended_start + next_start;
+  }
+
+  Ok(())
+}
+
+fn read_gpt<R: Read + Seek>(
+  reader: &mut R,
+  sector_size: u64,
+) -> Result<Option<PartitionTable>, Box<dyn Error>> {
+  // The header is in LBA 1
+  let header = read_at(reader, sector_size, 92)?;
+  if header.len() < 92 || &header[0..8] != b"EFI PART" {
+    return Ok(None);
+  }
+
+  let entries_lba = u64::from_le_bytes(header[72..80].try_into()?);
+  let count = u32::from_le_bytes(header[80..84].try_into()?) as u64;
+  let entry_size = u32::from_le_bytes(header[84..88].try_into()?) as u64;
+
+  // The spec requires 128 * 2^n. Nothing uses more than 128, so anything huge is corruption.
+  if !entry_size.is_power_of_two() || !(128..=4096).contains(&entry_size) || count > 1024 {
+    return Err(format!("Invalid GPT header ({count} entries of {entry_size} bytes)").into());
+  }
+
+  let entries_offset = entries_lba
+    .checked_mul(sector_size)
+    .ok_or(format!("Invalid GPT header (entries at LBA {entries_lba})"))?;
+
+  let to_512 = sector_size / SECTOR_SIZE;
+  let entries = read_at(reader, entries_offset, (count * entry_size) as usize)?;
+  let mut partitions = vec![];
+
+  for (i, entry) in entries.chunks_exact(entry_size as usize).enumerate() {
+    // Unused entries are all zeroes
+    if entry[0..16].iter().all(|b| *b == 0) {
+      continue;
+    }
+
+    let first = u64::from_le_bytes(entry[32..40].try_into()?);
+    let last = u64::from_le_bytes(entry[40..48].try_into()?);
+    let (Some(start), Some(size)) = (
+      first.checked_mul(to_512),
+      last
+        .checked_add(1)
+        .and_then(|end| end.saturating_sub(first).checked_mul(to_512)),
+    ) else {
+      return Err(format!("Invalid GPT entry {} (LBA {first} to {last})", i + 1).into());
+    };
+
+    partitions.push(Partition {
+      number: i as u32 + 1,
+      start,
+      size,
+      type_guid: Some(format_guid(&entry[0..16])),
+      guid: Some(format_guid(&entry[16..32])),
+      name: label_utf16(entry.get(56..128)),
+      ..Default::default()
+    });
+  }
+
+  Ok(Some(PartitionTable {
+    kind: PartitionTableKind::Gpt,
+    id: Some(format_guid(&header[56..72])),
+    partitions,
+  }))
+}
+
+/// GUIDs on disk are mixed-endian: the first three groups are little-endian
+fn format_guid(bytes: &[u8]) -> String {
+  let mut swapped = bytes[0..16].to_vec();
+  swapped[0..4].reverse();
+  swapped[4..6].reverse();
+  swapped[6..8].reverse();
+  let hex = hex(&swapped);
+
+  format!(
+    "{}-{}-{}-{}-{}",
+    &hex[0..8],
+    &hex[8..12],
+    &hex[12..16],
+    &hex[16..20],
+    &hex[20..32]
+  )
+}
+
+fn gpt_type_name(guid: &str) -> Option<&'static str> {
+  match guid {
+    "c12a7328-f81f-11d2-ba4b-00a0c93ec93b" => Some("EFI System"),
+    "21686148-6449-6e6f-744e-656564454649" => Some("BIOS boot"),
+    "0fc63daf-8483-4772-8e79-3d69d8477de4" => Some("Linux filesystem"),
+    "4f68bce3-e8cd-4db1-96e7-fbcaf984b709" => Some("Linux root (x86-64)"),
+    "933ac7e1-2eb4-4f13-b844-0e14e2aef915" => Some("Linux home"),
+    "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f" => Some("Linux swap"),
+    "e6d6d379-f507-44c2-a23c-238f2a3df928" => Some("Linux LVM"),
+    "a19d880f-05fc-4d3b-a006-743f0f84911e" => Some("Linux RAID"),
+    "ca7d7ccb-63ed-4c53-861c-1742536059cc" => Some("Linux LUKS"),
+    "bc13c2ff-59e6-4262-a352-b275fd6f7172" => Some("Linux extended boot"),
+    "e3c9e316-0b5c-4db8-817d-f92df00215ae" => Some("Microsoft reserved"),
+    "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7" => Some("Microsoft basic data"),
+    "de94bba4-06d1-4d40-a16a-bfd50179d6ac" => Some("Windows recovery"),
+    "48465300-0000-11aa-aa11-00306543ecac" => Some("Apple HFS+"),
+    "7c3457ef-0000-11aa-aa11-00306543ecac" => Some("Apple APFS"),
+    _ => None,
+  }
+}
+
+fn mbr_type_name(kind: u8) -> Option<&'static str> {
+  match kind {
+    0x01 | 0x04 | 0x06 | 0x0b | 0x0c | 0x0e => Some("FAT"),
+    0x05 | 0x0f | 0x85 => Some("Extended"),
+    0x07 => Some("NTFS/exFAT"),
+    0x27 => Some("Windows recovery"),
+    0x82 => Some("Linux swap"),
+    0x83 => Some("Linux"),
+    0x8e => Some("Linux LVM"),
+    0xa5 => Some("FreeBSD"),
+    0xee => Some("GPT protective"),
+    0xef => Some("EFI System"),
+    0xfd => Some("Linux RAID"),
+    _ => None,
+  }
+}
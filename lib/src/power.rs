@@ -0,0 +1,253 @@
+use std::{error::Error, fmt::Display, path::Path, time::Duration};
+
+use crate::{
+  ata::AtaIdentify,
+  kind::{disk_class, DiskKind},
+  sgio::{ata_command, hdio_drive_cmd, open_device, AtaProtocol, AtaTaskfile},
+};
+
+const ATA_CHECK_POWER_MODE: u8 = 0xe5;
+const ATA_IDLE: u8 = 0xe3;
+const ATA_STANDBY_IMMEDIATE: u8 = 0xe0;
+const ATA_SET_FEATURES: u8 = 0xef;
+
+const FEATURE_APM_ENABLE: u8 = 0x05;
+const FEATURE_APM_DISABLE: u8 = 0x85;
+const FEATURE_AAM_ENABLE: u8 = 0x42;
+const FEATURE_AAM_DISABLE: u8 = 0xc2;
+
+/// The power state of a drive, as reported by CHECK POWER MODE
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PowerState {
+  Active,
+  Idle,
+  /// Spun down. Reading SMART data will spin the drive back up.
+  Standby,
+  /// The drive didn't report a state (eg. it doesn't support the command)
+  Unknown,
+}
+
+impl PowerState {
+  /// Whether the drive is spun down
+  pub fn is_sleeping(&self) -> bool {
+    *self == PowerState::Standby
+  }
+}
+
+impl Display for PowerState {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PowerState::Active => write!(f, "Active"),
+      PowerState::Idle => write!(f, "Idle"),
+      PowerState::Standby => write!(f, "Standby"),
+      PowerState::Unknown => write!(f, "Unknown"),
+    }
+  }
+}
+
+/// Whether reading SMART data is allowed to wake a sleeping drive
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ReadPolicy {
+  /// Always read, even if that means spinning the drive up
+  #[default]
+  Always,
+  /// Don't touch drives that are in standby
+  SkipIfSleeping,
+}
+
+impl ReadPolicy {
+  /// Whether a drive at `path` should be read under this policy
+  pub fn allows(&self, path: impl AsRef<Path>) -> bool {
+    match self {
+      ReadPolicy::Always => true,
+      ReadPolicy::SkipIfSleeping => !power_state(path)
+        .map(|state| state.is_sleeping())
+        .unwrap_or(false),
+    }
+  }
+}
+
+/// Get the power state of a drive without waking it up
+pub fn power_state(path: impl AsRef<Path>) -> Result<PowerState, Box<dyn Error>> {
+  let path = path.as_ref();
+
+  // NVMe drives don't spin down, and APST transitions are invisible to us anyway
+  if disk_class(path) == DiskKind::NVME {
+    return Ok(PowerState::Active);
+  }
+
+  let file = open_device(path)?;
+  let taskfile = AtaTaskfile {
+    command: ATA_CHECK_POWER_MODE,
+    ..Default::default()
+  };
+
+  let count = match ata_command(&file, &taskfile, AtaProtocol::NonData, &mut []) {
+    Ok(result) => result.count,
+    Err(_) => hdio_drive_cmd(&file, ATA_CHECK_POWER_MODE, 0, 0)?[2],
+  };
+
+  Ok(power_state_from_count(count))
+}
+
+/// Decode the sector count register returned by CHECK POWER MODE
+fn power_state_from_count(count: u8) -> PowerState {
+  match count {
+    0x00 | 0x01 => PowerState::Standby,
+    // Idle, or one of the ACS-3 idle_a/b/c states
+    0x80..=0x83 => PowerState::Idle,
+    // Active or idle, plus the old NV cache states
+    0xff | 0x40 | 0x41 => PowerState::Active,
+    _ => PowerState::Unknown,
+  }
+}
+
+/// Power management features and current levels, as reported by IDENTIFY DEVICE
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct PowerFeatures {
+  /// Supports the power management feature set (standby timer, STANDBY IMMEDIATE, etc.)
+  pub power_management: bool,
+  /// Supports Advanced Power Management
+  pub apm_supported: bool,
+  /// Current APM level, if APM is enabled
+  pub apm_level: Option<u8>,
+  /// Supports Automatic Acoustic Management
+  pub aam_supported: bool,
+  /// Current AAM level, if AAM is enabled
+  pub aam_level: Option<u8>,
+  /// The vendor's recommended AAM level
+  pub aam_recommended: Option<u8>,
+}
+
+impl PowerFeatures {
+  /// Decode the power management bits of IDENTIFY DEVICE
+  pub fn from_identify(identify: &AtaIdentify) -> Self {
+    let apm_supported = identify.bit(83, 3);
+    let aam_supported = identify.bit(83, 9);
+
+    Self {
+      power_management: identify.bit(82, 3),
+      apm_supported,
+      apm_level: (apm_supported && identify.bit(86, 3))
+        .then_some((identify.words[91] & 0xff) as u8),
+      aam_supported,
+      aam_level: (aam_supported && identify.bit(86, 9))
+        .then_some((identify.words[94] & 0xff) as u8),
+      aam_recommended: aam_supported.then_some((identify.words[94] >> 8) as u8),
+    }
+  }
+
+  /// Read the power management features of a disk
+  pub fn for_disk(disk: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+    Ok(Self::from_identify(&AtaIdentify::for_disk(disk)?))
+  }
+}
+
+/// Set the Advanced Power Management level, like `hdparm -B`. 1-127 allow spin-down, 128-254 do not, and 255 disables APM.
+pub fn set_apm_level(disk: impl AsRef<Path>, level: u8) -> Result<(), Box<dyn Error>> {
+  let disk = disk.as_ref();
+
+  if !PowerFeatures::for_disk(disk)?.apm_supported {
+    return Err(format!("{} does not support APM", disk.display()).into());
+  }
+
+  if level == 0 {
+    return Err("APM level must be between 1 and 255".into());
+  }
+
+  let taskfile = if level == 255 {
+    set_features(FEATURE_APM_DISABLE, 0)
+  } else {
+    set_features(FEATURE_APM_ENABLE, level)
+  };
+
+  run_non_data(disk, &taskfile)
+}
+
+/// Set the Automatic Acoustic Management level, like `hdparm -M`. 128 is quietest, 254 is fastest, and 0 disables AAM.
+pub fn set_aam_level(disk: impl AsRef<Path>, level: u8) -> Result<(), Box<dyn Error>> {
+  let disk = disk.as_ref();
+
+  if !PowerFeatures::for_disk(disk)?.aam_supported {
+    return Err(format!("{} does not support AAM", disk.display()).into());
+  }
+
+  let taskfile = match level {
+    0 => set_features(FEATURE_AAM_DISABLE, 0),
+    128..=254 => set_features(FEATURE_AAM_ENABLE, level),
+    _ => return Err("AAM level must be 0 (off) or between 128 and 254".into()),
+  };
+
+  run_non_data(disk, &taskfile)
+}
+
+/// Set the standby (spin-down) timeout, like `hdparm -S`. A zero duration disables the timer.
+/// The drive only supports certain steps, so the timeout is rounded up to the nearest one.
+pub fn set_standby_timeout(
+  disk: impl AsRef<Path>,
+  timeout: Duration,
+) -> Result<(), Box<dyn Error>> {
+  let disk = disk.as_ref();
+
+  if !PowerFeatures::for_disk(disk)?.power_management {
+    return Err(format!("{} does not support power management", disk.display()).into());
+  }
+
+  let taskfile = AtaTaskfile {
+    command: ATA_IDLE,
+    count: standby_timeout_value(timeout)?,
+    ..Default::default()
+  };
+
+  run_non_data(disk, &taskfile)
+}
+
+/// Put the drive into standby immediately, like `hdparm -y`
+pub fn standby_now(disk: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+  let disk = disk.as_ref();
+
+  if !PowerFeatures::for_disk(disk)?.power_management {
+    return Err(format!("{} does not support power management", disk.display()).into());
+  }
+
+  let taskfile = AtaTaskfile {
+    command: ATA_STANDBY_IMMEDIATE,
+    ..Default::default()
+  };
+
+  run_non_data(disk, &taskfile)
+}
+
+/// Encode a standby timeout the same way `hdparm -S` does:
+/// * 0 disables the timer
+/// * 1-240 are multiples of 5 seconds (up to 20 minutes)
+/// * 241-251 are multiples of 30 minutes (up to 5.5 hours)
+pub fn standby_timeout_value(timeout: Duration) -> Result<u8, Box<dyn Error>> {
+  let secs = timeout.as_secs();
+
+  match secs {
+    0 => Ok(0),
+    1..=1200 => Ok(secs.div_ceil(5) as u8),
+    1201..=19800 => Ok(240 + secs.div_ceil(1800) as u8),
+    _ => Err("Standby timeout can be at most 5.5 hours".into()),
+  }
+}
+
+fn set_features(feature: u8, count: u8) -> AtaTaskfile {
+  AtaTaskfile {
+    command: ATA_SET_FEATURES,
+    feature,
+    count,
+    ..Default::default()
+  }
+}
+
+fn run_non_data(disk: &Path, taskfile: &AtaTaskfile) -> Result<(), Box<dyn Error>> {
+  let file = open_device(disk)?;
+
+  if ata_command(&file, taskfile, AtaProtocol::NonData, &mut []).is_err() {
+    hdio_drive_cmd(&file, taskfile.command, taskfile.feature, taskfile.count)?;
+  }
+
+  Ok(())
+}
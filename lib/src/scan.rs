@@ -0,0 +1,428 @@
+use std::{
+  error::Error,
+  fs::{self, File, OpenOptions},
+  io::{self, Seek, SeekFrom},
+  os::unix::{
+    fs::{FileExt, OpenOptionsExt},
+    io::AsRawFd,
+  },
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc,
+  },
+  thread,
+  time::{Duration, Instant},
+};
+
+use crate::{benchmark::direct::AlignedBuf, sysfs::sector_size};
+
+const RUNNING: u8 = 0;
+const PAUSED: u8 = 1;
+const CANCELLED: u8 = 2;
+
+/// How often to record a throughput sample
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Anything that can be surface scanned. This is implemented for [`File`] (block devices and images), and can be
+/// implemented by a wrapper that injects read errors to simulate bad sectors.
+pub trait ScanSource {
+  /// Total size in bytes
+  fn size(&mut self) -> io::Result<u64>;
+  /// Fill `buf` with the data at `offset`. Any error marks the range as unreadable.
+  fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+}
+
+impl ScanSource for File {
+  fn size(&mut self) -> io::Result<u64> {
+    // Block devices report a length of 0 in their metadata, so seek to the end instead
+    let size = self.seek(SeekFrom::End(0))?;
+    self.seek(SeekFrom::Start(0))?;
+    Ok(size)
+  }
+
+  fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+    // If the file couldn't be opened with O_DIRECT, make sure this comes from the disk and not the page cache. A no-op
+    // otherwise.
+    unsafe {
+      libc::posix_fadvise(
+        self.as_raw_fd(),
+        offset as libc::off_t,
+        buf.len() as libc::off_t,
+        libc::POSIX_FADV_DONTNEED,
+      )
+    };
+
+    self.read_exact_at(buf, offset)
+  }
+}
+
+/// The configuration for a surface scan
+#[derive(Clone, Debug)]
+pub struct ScanConfig {
+  /// How much to read at once, in bytes
+  pub chunk_size: usize,
+  /// The smallest unit that can fail. If `None`, this is read from sysfs, or 512 for images.
+  pub sector_size: Option<u64>,
+  /// Byte offset to start scanning from
+  pub start: u64,
+  /// Byte offset to stop scanning at. If `None`, scans to the end.
+  pub end: Option<u64>,
+}
+
+impl Default for ScanConfig {
+  fn default() -> Self {
+    Self {
+      // 1mb chunks
+      chunk_size: 1024 * 1024,
+      sector_size: None,
+      start: 0,
+      end: None,
+    }
+  }
+}
+
+/// A handle for pausing, resuming or cancelling a running scan from another thread
+#[derive(Clone, Debug, Default)]
+pub struct ScanControl {
+  state: Arc<AtomicU8>,
+}
+
+impl ScanControl {
+  pub fn pause(&self) {
+    // Don't un-cancel a cancelled scan
+    let _ = self
+      .state
+      .compare_exchange(RUNNING, PAUSED, Ordering::SeqCst, Ordering::SeqCst);
+  }
+
+  pub fn resume(&self) {
+    let _ = self
+      .state
+      .compare_exchange(PAUSED, RUNNING, Ordering::SeqCst, Ordering::SeqCst);
+  }
+
+  pub fn cancel(&self) {
+    self.state.store(CANCELLED, Ordering::SeqCst);
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.state.load(Ordering::SeqCst) == PAUSED
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.state.load(Ordering::SeqCst) == CANCELLED
+  }
+}
+
+/// The progress of a running scan
+#[derive(Clone, Debug)]
+pub struct ScanProgress {
+  pub bytes_scanned: u64,
+  pub total_bytes: u64,
+  /// Time spent scanning, not including time spent paused
+  pub elapsed: Duration,
+  /// Throughput of the most recent chunk, in bytes per second
+  pub throughput: f64,
+  pub bad_sectors: usize,
+}
+
+impl ScanProgress {
+  /// Progress from 0 to 100
+  pub fn percent(&self) -> f64 {
+    if self.total_bytes == 0 {
+      return 100.;
+    }
+
+    self.bytes_scanned as f64 / self.total_bytes as f64 * 100.
+  }
+}
+
+/// A throughput measurement at a point in the scan
+#[derive(Clone, Debug)]
+pub struct ThroughputSample {
+  pub elapsed: Duration,
+  /// Byte offset the sample was taken at
+  pub offset: u64,
+  /// Bytes per second since the previous sample
+  pub throughput: f64,
+}
+
+/// Where an unreadable sector is within a partition
+#[derive(Clone, PartialEq, Debug)]
+pub struct PartitionOffset {
+  /// Partition device name (eg. `sda2`)
+  pub name: String,
+  pub number: u32,
+  /// Byte offset of the sector from the start of the partition
+  pub offset: u64,
+}
+
+/// A sector that could not be read
+#[derive(Clone, PartialEq, Debug)]
+pub struct BadSector {
+  /// Logical block address, in units of the scan's sector size
+  pub lba: u64,
+  /// Byte offset from the start of the device or image
+  pub offset: u64,
+  /// The partition containing this sector, if any
+  pub partition: Option<PartitionOffset>,
+}
+
+/// The result of a surface scan
+#[derive(Clone, Debug)]
+pub struct ScanResult {
+  pub bytes_scanned: u64,
+  pub total_bytes: u64,
+  pub elapsed: Duration,
+  pub sector_size: u64,
+  /// Whether the scan was cancelled before it finished
+  pub cancelled: bool,
+  pub bad_sectors: Vec<BadSector>,
+  pub throughput: Vec<ThroughputSample>,
+}
+
+/// A read-only scan of an entire block device or image, which finds unreadable sectors
+pub struct SurfaceScan<S: ScanSource = File> {
+  pub config: ScanConfig,
+  source: S,
+  path: Option<PathBuf>,
+  control: ScanControl,
+}
+
+impl SurfaceScan<File> {
+  /// Scan a block device (e.g. `"/dev/sda"`) or image file. The file is only ever opened for reading.
+  ///
+  /// Reads bypass the page cache with O_DIRECT, so every sector really is read from the disk, and a bad sector only
+  /// fails its own read instead of the whole page (and readahead) around it. Filesystems that don't support O_DIRECT
+  /// (eg. for images on tmpfs) fall back to buffered reads with readahead turned off.
+  pub fn new(path: impl AsRef<Path>, config: ScanConfig) -> Result<Self, Box<dyn Error>> {
+    let path = path.as_ref();
+    let file = match OpenOptions::new()
+      .read(true)
+      .custom_flags(libc::O_DIRECT)
+      .open(path)
+    {
+      Ok(file) => file,
+      Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {
+        let file = File::open(path)?;
+        unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_RANDOM) };
+        file
+      }
+      Err(e) => return Err(e.into()),
+    };
+
+    Ok(Self {
+      config,
+      source: file,
+      path: Some(path.to_path_buf()),
+      control: ScanControl::default(),
+    })
+  }
+}
+
+impl<S: ScanSource> SurfaceScan<S> {
+  /// Scan any [`ScanSource`]. There is no partition context, since there is no device to look it up from.
+  pub fn with_source(source: S, config: ScanConfig) -> Self {
+    Self {
+      config,
+      source,
+      path: None,
+      control: ScanControl::default(),
+    }
+  }
+
+  /// Get a handle to pause, resume or cancel the scan
+  pub fn control(&self) -> ScanControl {
+    self.control.clone()
+  }
+
+  /// Run the scan, calling `on_progress` after every chunk
+  pub fn run(
+    &mut self,
+    mut on_progress: impl FnMut(&ScanProgress),
+  ) -> Result<ScanResult, Box<dyn Error>> {
+    let sector_size = self.sector_size();
+    // O_DIRECT reads have to start and end on sector boundaries
+    let chunk_size = (self.config.chunk_size as u64).div_ceil(sector_size).max(1) * sector_size;
+    let size = self.source.size()?;
+    let start = self.config.start.min(size) / sector_size * sector_size;
+    let end = self.config.end.unwrap_or(size).min(size);
+    let total_bytes = end.saturating_sub(start);
+    let partitions = self
+      .path
+      .as_deref()
+      .map(partition_ranges)
+      .unwrap_or_default();
+
+    let mut buf = AlignedBuf::new(chunk_size as usize);
+    let mut bad_sectors = vec![];
+    let mut throughput = vec![];
+    let mut offset = start;
+    let mut paused_for = Duration::ZERO;
+    let begin = Instant::now();
+    let mut last_sample = (Duration::ZERO, start);
+
+    while offset < end {
+      // Wait while paused, and don't count it towards the elapsed time
+      if self.control.is_paused() {
+        let paused_at = Instant::now();
+        while self.control.is_paused() {
+          thread::sleep(Duration::from_millis(100));
+        }
+        paused_for += paused_at.elapsed();
+      }
+
+      if self.control.is_cancelled() {
+        break;
+      }
+
+      let len = chunk_size.min(end - offset);
+      let chunk_start = Instant::now();
+
+      if self
+        .source
+        .read_at(offset, &mut buf[..len as usize])
+        .is_err()
+      {
+        for bad in self.bisect(offset, len, sector_size, &mut buf) {
+          bad_sectors.push(BadSector {
+            lba: bad / sector_size,
+            offset: bad,
+            partition: partition_for(&partitions, bad),
+          });
+        }
+      }
+
+      offset += len;
+
+      let elapsed = begin.elapsed().saturating_sub(paused_for);
+      let chunk_secs = chunk_start.elapsed().as_secs_f64();
+
+      let since_sample = elapsed.saturating_sub(last_sample.0);
+
+      if since_sample >= SAMPLE_INTERVAL || offset >= end {
+        let secs = since_sample.as_secs_f64();
+        throughput.push(ThroughputSample {
+          elapsed,
+          offset,
+          throughput: if secs > 0. {
+            (offset - last_sample.1) as f64 / secs
+          } else {
+            0.
+          },
+        });
+        last_sample = (elapsed, offset);
+      }
+
+      on_progress(&ScanProgress {
+        bytes_scanned: offset - start,
+        total_bytes,
+        elapsed,
+        throughput: if chunk_secs > 0. {
+          len as f64 / chunk_secs
+        } else {
+          0.
+        },
+        bad_sectors: bad_sectors.len(),
+      });
+    }
+
+    Ok(ScanResult {
+      bytes_scanned: offset - start,
+      total_bytes,
+      elapsed: begin.elapsed().saturating_sub(paused_for),
+      sector_size,
+      cancelled: self.control.is_cancelled(),
+      bad_sectors,
+      throughput,
+    })
+  }
+
+  /// Split a failing range in half until each failing piece is a single sector, returning the byte offsets of the bad sectors
+  fn bisect(&mut self, offset: u64, len: u64, sector_size: u64, buf: &mut [u8]) -> Vec<u64> {
+    if len <= sector_size {
+      return vec![offset];
+    }
+
+    // Split on a sector boundary
+    let half = (len / sector_size).div_ceil(2) * sector_size;
+    let mut bad = vec![];
+
+    for (offset, len) in [(offset, half), (offset + half, len - half)] {
+      if len == 0 {
+        continue;
+      }
+
+      if self
+        .source
+        .read_at(offset, &mut buf[..len as usize])
+        .is_err()
+      {
+        bad.extend(self.bisect(offset, len, sector_size, buf));
+      }
+    }
+
+    bad
+  }
+
+  fn sector_size(&self) -> u64 {
+    if let Some(size) = self.config.sector_size {
+      return size.max(1);
+    }
+
+    let size = self.path.as_deref().map(sector_size).unwrap_or(0);
+    if size == 0 {
+      512
+    } else {
+      size
+    }
+  }
+}
+
+/// Partitions of a block device as `(name, number, start, size)`, in bytes
+fn partition_ranges(disk: &Path) -> Vec<(String, u32, u64, u64)> {
+  let drive = disk
+    .file_name()
+    .unwrap_or_default()
+    .to_str()
+    .unwrap_or_default();
+  let mut partitions = vec![];
+  let Ok(entries) = fs::read_dir(format!("/sys/class/block/{drive}")) else {
+    return partitions;
+  };
+
+  for entry in entries.flatten() {
+    let path = entry.path();
+    let read = |file: &str| {
+      fs::read_to_string(path.join(file))
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+    };
+
+    // sysfs always uses 512 byte sectors here, regardless of the disk
+    if let (Some(number), Some(start), Some(size)) =
+      (read("partition"), read("start"), read("size"))
+    {
+      partitions.push((
+        entry.file_name().to_string_lossy().to_string(),
+        number as u32,
+        start * 512,
+        size * 512,
+      ));
+    }
+  }
+
+  partitions
+}
+
+fn partition_for(partitions: &[(String, u32, u64, u64)], offset: u64) -> Option<PartitionOffset> {
+  partitions
+    .iter()
+    .find(|(_, _, start, size)| offset >= *start && offset < start + size)
+    .map(|(name, number, start, _)| PartitionOffset {
+      name: name.clone(),
+      number: *number,
+      offset: offset - start,
+    })
+}
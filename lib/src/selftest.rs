@@ -0,0 +1,338 @@
+use std::{error::Error, fmt::Display, path::Path, str::FromStr, time::Duration};
+
+use crate::{
+  kind::{disk_class, DiskKind},
+  nvme::{admin_command, get_log_page},
+  sgio::{ata_command, open_device, AtaProtocol, AtaTaskfile},
+};
+
+const ATA_SMART: u8 = 0xb0;
+const SMART_READ_DATA: u8 = 0xd0;
+const SMART_EXECUTE_OFFLINE_IMMEDIATE: u8 = 0xd4;
+const SMART_ABORT: u8 = 0x7f;
+
+const NVME_ADMIN_IDENTIFY: u8 = 0x06;
+const NVME_ADMIN_DEVICE_SELF_TEST: u8 = 0x14;
+const NVME_SELF_TEST_ABORT: u32 = 0x0f;
+const LOG_DEVICE_SELF_TEST: u32 = 0x06;
+/// NVMe doesn't report how long a short test takes, but the spec requires it to finish within two minutes
+const NVME_SHORT_TEST_MINUTES: u64 = 2;
+
+/// A SMART self-test
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum SelfTestKind {
+  /// A quick check of the electronics and a sample of the surface, usually a couple of minutes
+  Short,
+  /// A full surface scan, which can take hours on large hard drives
+  Extended,
+  /// Checks for damage during shipping. ATA only.
+  Conveyance,
+}
+
+impl SelfTestKind {
+  pub const ALL: [SelfTestKind; 3] = [
+    SelfTestKind::Short,
+    SelfTestKind::Extended,
+    SelfTestKind::Conveyance,
+  ];
+
+  fn code(&self) -> u8 {
+    match self {
+      SelfTestKind::Short => 1,
+      SelfTestKind::Extended => 2,
+      SelfTestKind::Conveyance => 3,
+    }
+  }
+}
+
+impl Display for SelfTestKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SelfTestKind::Short => write!(f, "short"),
+      SelfTestKind::Extended => write!(f, "extended"),
+      SelfTestKind::Conveyance => write!(f, "conveyance"),
+    }
+  }
+}
+
+impl FromStr for SelfTestKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "short" => Ok(SelfTestKind::Short),
+      "extended" | "long" => Ok(SelfTestKind::Extended),
+      "conveyance" => Ok(SelfTestKind::Conveyance),
+      _ => Err(format!("Unknown self-test: {s}")),
+    }
+  }
+}
+
+/// The outcome of the most recent self-test
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum SelfTestResult {
+  /// The last test passed, or no test has ever been run
+  Passed,
+  InProgress,
+  /// Aborted by the host
+  Aborted,
+  /// Interrupted by a reset or power cycle
+  Interrupted,
+  /// The drive found a problem. The string says what kind.
+  Failed(String),
+}
+
+impl SelfTestResult {
+  pub fn is_failure(&self) -> bool {
+    matches!(self, SelfTestResult::Failed(_))
+  }
+}
+
+impl Display for SelfTestResult {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SelfTestResult::Passed => write!(f, "Passed"),
+      SelfTestResult::InProgress => write!(f, "In progress"),
+      SelfTestResult::Aborted => write!(f, "Aborted"),
+      SelfTestResult::Interrupted => write!(f, "Interrupted"),
+      SelfTestResult::Failed(reason) => write!(f, "Failed ({reason})"),
+    }
+  }
+}
+
+/// Self-test support and the state of the most recent test
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelfTestStatus {
+  pub result: SelfTestResult,
+  /// Which test is running or last ran. ATA drives don't report this.
+  pub kind: Option<SelfTestKind>,
+  /// How much of the running test is left, in percent
+  pub remaining: u8,
+  /// The tests the drive supports, and roughly how long each takes
+  pub supported: Vec<(SelfTestKind, Duration)>,
+}
+
+impl SelfTestStatus {
+  /// Read the self-test status of a disk (e.g. `"/dev/sda"`). Note that this may wake a drive in standby.
+  pub fn for_disk(disk: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+    let disk = disk.as_ref();
+
+    if disk_class(disk) == DiskKind::NVME {
+      return nvme_status(disk);
+    }
+
+    let file = open_device(disk)?;
+    let mut data = [0u8; 512];
+    ata_command(
+      &file,
+      &smart_taskfile(SMART_READ_DATA, 0),
+      AtaProtocol::PioIn,
+      &mut data,
+    )?;
+
+    Ok(Self::from_smart_data(&data))
+  }
+
+  /// Decode the self-test fields of the ATA SMART READ DATA sector
+  pub fn from_smart_data(data: &[u8; 512]) -> Self {
+    let result = match data[363] >> 4 {
+      0 => SelfTestResult::Passed,
+      1 => SelfTestResult::Aborted,
+      2 => SelfTestResult::Interrupted,
+      3 => SelfTestResult::Failed("fatal error".into()),
+      4 => SelfTestResult::Failed("unknown test element".into()),
+      5 => SelfTestResult::Failed("electrical element".into()),
+      6 => SelfTestResult::Failed("servo or seek element".into()),
+      7 => SelfTestResult::Failed("read element".into()),
+      8 => SelfTestResult::Failed("handling damage".into()),
+      15 => SelfTestResult::InProgress,
+      status => SelfTestResult::Failed(format!("status {status}")),
+    };
+
+    let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+    // An extended polling time of 0xff means the real value is in the following word
+    let extended = match data[373] {
+      0xff => u16::from_le_bytes([data[375], data[376]]) as u64,
+      value => value as u64,
+    };
+
+    let mut supported = vec![];
+    if data[367] & 0x10 != 0 {
+      supported.push((SelfTestKind::Short, minutes(data[372] as u64)));
+      supported.push((SelfTestKind::Extended, minutes(extended)));
+    }
+    if data[367] & 0x20 != 0 {
+      supported.push((SelfTestKind::Conveyance, minutes(data[374] as u64)));
+    }
+
+    Self {
+      remaining: if result == SelfTestResult::InProgress {
+        (data[363] & 0x0f) * 10
+      } else {
+        0
+      },
+      result,
+      kind: None,
+      supported,
+    }
+  }
+
+  /// Whether the drive supports a kind of test
+  pub fn supports(&self, kind: SelfTestKind) -> bool {
+    self
+      .supported
+      .iter()
+      .any(|(supported, _)| *supported == kind)
+  }
+
+  /// Roughly how long a kind of test takes
+  pub fn duration(&self, kind: SelfTestKind) -> Option<Duration> {
+    self
+      .supported
+      .iter()
+      .find(|(supported, _)| *supported == kind)
+      .map(|(_, duration)| *duration)
+  }
+}
+
+/// Start a self-test in the background. Check on it with [`SelfTestStatus::for_disk`].
+pub fn start(disk: impl AsRef<Path>, kind: SelfTestKind) -> Result<(), Box<dyn Error>> {
+  let disk = disk.as_ref();
+  let file = open_device(disk)?;
+
+  if disk_class(disk) == DiskKind::NVME {
+    if kind == SelfTestKind::Conveyance {
+      return Err("NVMe drives don't support conveyance self-tests".into());
+    }
+
+    return admin_command(
+      &file,
+      NVME_ADMIN_DEVICE_SELF_TEST,
+      0xffff_ffff,
+      kind.code() as u32,
+      &mut [],
+    )
+    .map_err(|e| format!("Failed to start {kind} self-test ({e})").into());
+  }
+
+  ata_command(
+    &file,
+    &smart_taskfile(SMART_EXECUTE_OFFLINE_IMMEDIATE, kind.code()),
+    AtaProtocol::NonData,
+    &mut [],
+  )?;
+
+  Ok(())
+}
+
+/// Abort a running self-test
+pub fn abort(disk: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+  let disk = disk.as_ref();
+  let file = open_device(disk)?;
+
+  if disk_class(disk) == DiskKind::NVME {
+    return admin_command(
+      &file,
+      NVME_ADMIN_DEVICE_SELF_TEST,
+      0xffff_ffff,
+      NVME_SELF_TEST_ABORT,
+      &mut [],
+    )
+    .map_err(|e| format!("Failed to abort self-test ({e})").into());
+  }
+
+  ata_command(
+    &file,
+    &smart_taskfile(SMART_EXECUTE_OFFLINE_IMMEDIATE, SMART_ABORT),
+    AtaProtocol::NonData,
+    &mut [],
+  )?;
+
+  Ok(())
+}
+
+/// The registers for a SMART subcommand. The magic LBA values are required by the spec.
+fn smart_taskfile(feature: u8, lba_low: u8) -> AtaTaskfile {
+  AtaTaskfile {
+    command: ATA_SMART,
+    feature,
+    count: 1,
+    lba_low,
+    lba_mid: 0x4f,
+    lba_high: 0xc2,
+    ..Default::default()
+  }
+}
+
+fn nvme_status(disk: &Path) -> Result<SelfTestStatus, Box<dyn Error>> {
+  let file = open_device(disk)?;
+
+  // Identify Controller has whether self-tests are supported at all, and how long an extended test takes
+  let mut identify = [0u8; 4096];
+  admin_command(&file, NVME_ADMIN_IDENTIFY, 0, 1, &mut identify)
+    .map_err(|e| format!("Identify Controller failed ({e})"))?;
+
+  let mut supported = vec![];
+  if identify[256] & 0x10 != 0 {
+    let extended = u16::from_le_bytes([identify[316], identify[317]]) as u64;
+    supported.push((
+      SelfTestKind::Short,
+      Duration::from_secs(NVME_SHORT_TEST_MINUTES * 60),
+    ));
+    supported.push((SelfTestKind::Extended, Duration::from_secs(extended * 60)));
+  } else {
+    return Ok(SelfTestStatus {
+      result: SelfTestResult::Passed,
+      kind: None,
+      remaining: 0,
+      supported,
+    });
+  }
+
+  // The current operation, then 20 results of 28 bytes each, newest first
+  let mut log = [0u8; 564];
+  get_log_page(&file, LOG_DEVICE_SELF_TEST, &mut log)?;
+
+  let kind = |code: u8| match code {
+    1 => Some(SelfTestKind::Short),
+    2 => Some(SelfTestKind::Extended),
+    _ => None,
+  };
+
+  if log[0] & 0x0f != 0 {
+    return Ok(SelfTestStatus {
+      result: SelfTestResult::InProgress,
+      kind: kind(log[0] & 0x0f),
+      remaining: 100u8.saturating_sub(log[1] & 0x7f),
+      supported,
+    });
+  }
+
+  let newest = log[4];
+  let result = match newest & 0x0f {
+    0 | 0x0f => SelfTestResult::Passed,
+    1 => SelfTestResult::Aborted,
+    2..=4 | 8 | 9 => SelfTestResult::Interrupted,
+    5 => SelfTestResult::Failed("fatal error".into()),
+    6 => SelfTestResult::Failed("unknown segment".into()),
+    7 => SelfTestResult::Failed(format!("segment {}", log[5])),
+    status => SelfTestResult::Failed(format!("status {status}")),
+  };
+
+  Ok(SelfTestStatus {
+    result,
+    // An unused entry means no test has run
+    kind: if newest & 0x0f == 0x0f {
+      None
+    } else {
+      kind(newest >> 4)
+    },
+    remaining: 0,
+    supported,
+  })
+}
@@ -0,0 +1,298 @@
+use std::{
+  error::Error,
+  fs::{File, OpenOptions},
+  os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+  path::Path,
+};
+
+const SG_IO: libc::c_ulong = 0x2285;
+const HDIO_DRIVE_CMD: libc::c_ulong = 0x031f;
+
+const SG_DXFER_NONE: libc::c_int = -1;
+const SG_DXFER_TO_DEV: libc::c_int = -2;
+const SG_DXFER_FROM_DEV: libc::c_int = -3;
+
+const ATA_PASS_THROUGH_16: u8 = 0x85;
+const ATA_STATUS_ERR: u8 = 0x01;
+const SENSE_LEN: usize = 32;
+const TIMEOUT_MS: u32 = 15_000;
+
+/// Mirror of the kernel's `sg_io_hdr` struct
+#[repr(C)]
+struct SgIoHdr {
+  interface_id: libc::c_int,
+  dxfer_direction: libc::c_int,
+  cmd_len: libc::c_uchar,
+  mx_sb_len: libc::c_uchar,
+  iovec_count: libc::c_ushort,
+  dxfer_len: libc::c_uint,
+  dxferp: *mut libc::c_void,
+  cmdp: *const libc::c_uchar,
+  sbp: *mut libc::c_uchar,
+  timeout: libc::c_uint,
+  flags: libc::c_uint,
+  pack_id: libc::c_int,
+  usr_ptr: *mut libc::c_void,
+  status: libc::c_uchar,
+  masked_status: libc::c_uchar,
+  msg_status: libc::c_uchar,
+  sb_len_wr: libc::c_uchar,
+  host_status: libc::c_ushort,
+  driver_status: libc::c_ushort,
+  resid: libc::c_int,
+  duration: libc::c_uint,
+  info: libc::c_uint,
+}
+
+/// The data transfer protocol of an ATA command
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AtaProtocol {
+  /// No data transfer
+  NonData,
+  /// PIO data from the device (eg. IDENTIFY DEVICE)
+  PioIn,
+  /// PIO data to the device
+  PioOut,
+}
+
+/// The registers of a 28-bit ATA command, both for issuing a command and reading back the result
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct AtaTaskfile {
+  pub command: u8,
+  pub feature: u8,
+  pub count: u8,
+  pub lba_low: u8,
+  pub lba_mid: u8,
+  pub lba_high: u8,
+  pub device: u8,
+  /// Only set in results
+  pub status: u8,
+  /// Only set in results
+  pub error: u8,
+}
+
+/// Open a block device for issuing commands. This does not do any I/O, so it will not wake a sleeping drive.
+pub fn open_device(path: impl AsRef<Path>) -> Result<File, Box<dyn Error>> {
+  let file = OpenOptions::new()
+    .read(true)
+    .custom_flags(libc::O_NONBLOCK)
+    .open(path)?;
+
+  Ok(file)
+}
+
+/// Issue an ATA command using SCSI ATA PASS-THROUGH (16), which works for anything behind libata (and most USB bridges).
+/// `data` must be a multiple of 512 bytes for PIO commands, and is ignored for non-data commands.
+pub fn ata_command(
+  file: &File,
+  taskfile: &AtaTaskfile,
+  protocol: AtaProtocol,
+  data: &mut [u8],
+) -> Result<AtaTaskfile, Box<dyn Error>> {
+  let mut cdb = [0u8; 16];
+  let mut sense = [0u8; SENSE_LEN];
+
+  // Bytes 1 and 2 are the protocol and the transfer flags
+  let (proto, flags, direction) = match protocol {
+    // CK_COND, so that we always get the result registers back
+    AtaProtocol::NonData => (3, 0x20, SG_DXFER_NONE),
+    // T_DIR = from device, BYT_BLOK = blocks, T_LENGTH = sector count
+    AtaProtocol::PioIn => (4, 0x0e, SG_DXFER_FROM_DEV),
+    AtaProtocol::PioOut => (5, 0x06, SG_DXFER_TO_DEV),
+  };
+
+  cdb[0] = ATA_PASS_THROUGH_16;
+  cdb[1] = proto << 1;
+  cdb[2] = flags;
+  cdb[4] = taskfile.feature;
+  cdb[6] = taskfile.count;
+  cdb[8] = taskfile.lba_low;
+  cdb[10] = taskfile.lba_mid;
+  cdb[12] = taskfile.lba_high;
+  cdb[13] = taskfile.device;
+  cdb[14] = taskfile.command;
+
+  let data: &mut [u8] = if protocol == AtaProtocol::NonData {
+    &mut []
+  } else {
+    data
+  };
+  let sb_len_wr = sg_io(file, &cdb, direction, data, &mut sense)?;
+
+  // ILLEGAL REQUEST, eg. a plain SCSI disk with no ATA translation
+  if sense_key(&sense) == 0x05 {
+    return Err("Device does not support ATA pass-through".into());
+  }
+
+  let result = parse_ata_sense(&sense).unwrap_or_default();
+
+  if result.status & ATA_STATUS_ERR != 0 {
+    return Err(
+      format!(
+        "ATA command {:#04x} aborted (error {:#04x})",
+        taskfile.command, result.error
+      )
+      .into(),
+    );
+  }
+
+  // If there is no sense data at all, the device didn't understand the pass-through
+  if sb_len_wr == 0 && protocol == AtaProtocol::NonData {
+    return Err("Device did not return ATA registers".into());
+  }
+
+  Ok(result)
+}
+
+/// The data direction of a SCSI command
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScsiDirection {
+  None,
+  FromDevice,
+  ToDevice,
+}
+
+/// Issue a raw SCSI command. Any sense data other than "no sense" or "recovered error" is treated as a failure.
+pub fn scsi_command(
+  file: &File,
+  cdb: &[u8],
+  direction: ScsiDirection,
+  data: &mut [u8],
+) -> Result<(), Box<dyn Error>> {
+  let mut sense = [0u8; SENSE_LEN];
+  let direction = match direction {
+    ScsiDirection::None => SG_DXFER_NONE,
+    ScsiDirection::FromDevice => SG_DXFER_FROM_DEV,
+    ScsiDirection::ToDevice => SG_DXFER_TO_DEV,
+  };
+
+  sg_io(file, cdb, direction, data, &mut sense)?;
+
+  match sense_key(&sense) {
+    0x00 | 0x01 => Ok(()),
+    key => Err(format!("SCSI command {:#04x} failed (sense key {key:#x})", cdb[0]).into()),
+  }
+}
+
+/// Issue an ATA command using the legacy `HDIO_DRIVE_CMD` ioctl. Returns `[status, error, count, 0]`.
+/// This is a fallback for devices that don't support SG_IO.
+pub fn hdio_drive_cmd(
+  file: &File,
+  command: u8,
+  feature: u8,
+  count: u8,
+) -> Result<[u8; 4], Box<dyn Error>> {
+  // libata treats args[1] as the sector count for anything other than SMART
+  let mut args = [command, count, feature, 0];
+
+  let ret = unsafe { libc::ioctl(file.as_raw_fd(), HDIO_DRIVE_CMD as _, args.as_mut_ptr()) };
+  if ret < 0 {
+    return Err(std::io::Error::last_os_error().into());
+  }
+
+  Ok(args)
+}
+
+/// Run a command through the SG_IO ioctl, returning how many bytes of sense data were written
+fn sg_io(
+  file: &File,
+  cdb: &[u8],
+  direction: libc::c_int,
+  data: &mut [u8],
+  sense: &mut [u8; SENSE_LEN],
+) -> Result<u8, Box<dyn Error>> {
+  let (dxferp, dxfer_len) = if data.is_empty() {
+    (std::ptr::null_mut(), 0)
+  } else {
+    (data.as_mut_ptr() as *mut libc::c_void, data.len() as u32)
+  };
+
+  let mut hdr = SgIoHdr {
+    interface_id: 'S' as libc::c_int,
+    dxfer_direction: if data.is_empty() {
+      SG_DXFER_NONE
+    } else {
+      direction
+    },
+    cmd_len: cdb.len() as u8,
+    mx_sb_len: SENSE_LEN as u8,
+    iovec_count: 0,
+    dxfer_len,
+    dxferp,
+    cmdp: cdb.as_ptr(),
+    sbp: sense.as_mut_ptr(),
+    timeout: TIMEOUT_MS,
+    flags: 0,
+    pack_id: 0,
+    usr_ptr: std::ptr::null_mut(),
+    status: 0,
+    masked_status: 0,
+    msg_status: 0,
+    sb_len_wr: 0,
+    host_status: 0,
+    driver_status: 0,
+    resid: 0,
+    duration: 0,
+    info: 0,
+  };
+
+  let ret = unsafe { libc::ioctl(file.as_raw_fd(), SG_IO as _, &mut hdr as *mut SgIoHdr) };
+  if ret < 0 {
+    return Err(std::io::Error::last_os_error().into());
+  }
+
+  // 0x08 is DRIVER_SENSE, which just means there is sense data for us to look at
+  if hdr.host_status != 0 || (hdr.driver_status & !0x08) != 0 {
+    return Err(
+      format!(
+        "SG_IO failed (host status {:#x}, driver status {:#x})",
+        hdr.host_status, hdr.driver_status
+      )
+      .into(),
+    );
+  }
+
+  Ok(hdr.sb_len_wr)
+}
+
+/// Get the sense key from either descriptor or fixed format sense data
+fn sense_key(sense: &[u8; SENSE_LEN]) -> u8 {
+  match sense[0] & 0x7f {
+    0x72 | 0x73 => sense[1] & 0x0f,
+    0x70 | 0x71 => sense[2] & 0x0f,
+    _ => 0,
+  }
+}
+
+/// Read the ATA registers out of the "ATA Status Return" sense descriptor
+fn parse_ata_sense(sense: &[u8; SENSE_LEN]) -> Option<AtaTaskfile> {
+  match sense[0] & 0x7f {
+    // Descriptor format
+    0x72 => {
+      let desc = &sense[8..];
+      if desc[0] != 0x09 {
+        return None;
+      }
+
+      Some(AtaTaskfile {
+        error: desc[3],
+        count: desc[5],
+        lba_low: desc[7],
+        lba_mid: desc[9],
+        lba_high: desc[11],
+        device: desc[12],
+        status: desc[13],
+        ..Default::default()
+      })
+    }
+    // Fixed format, only has room for some of the registers. ASC/ASCQ 00/1D is "ATA pass through information available"
+    0x70 if sense[12] == 0x00 && sense[13] == 0x1d => Some(AtaTaskfile {
+      error: sense[3],
+      status: sense[4],
+      device: sense[5],
+      count: sense[6],
+      ..Default::default()
+    }),
+    _ => None,
+  }
+}
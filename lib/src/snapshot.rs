@@ -0,0 +1,273 @@
+use std::{
+  fmt::Write,
+  fs,
+  path::{Path, PathBuf},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+  attribute::Attribute,
+  disk::Disk,
+  health::{HealthReport, HealthStatus, HealthThresholds},
+  kind::DiskKind,
+  nvme::NvmeSmartLog,
+  sysfs::DiskStat,
+};
+
+/// A single SMART attribute as recorded in a [`DiskSnapshot`]
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotAttribute {
+  pub id: u8,
+  pub name: String,
+  pub current: u8,
+  pub worst: u8,
+  pub threshold: u8,
+  /// The full 48-bit raw value
+  pub raw: u64,
+  /// The raw value decoded into `unit`, see [`Attribute::decoded`]
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub decoded: Option<f64>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub unit: Option<String>,
+}
+
+impl From<&Attribute> for SnapshotAttribute {
+  fn from(attribute: &Attribute) -> Self {
+    Self {
+      id: attribute.id,
+      name: attribute.name.clone(),
+      current: attribute.current,
+      worst: attribute.worst,
+      threshold: attribute.threshold,
+      raw: attribute.raw_value(),
+      decoded: attribute.decoded().map(|(value, _)| value),
+      unit: attribute.decoded().map(|(_, unit)| unit.to_string()),
+    }
+  }
+}
+
+impl From<&SnapshotAttribute> for Attribute {
+  fn from(attribute: &SnapshotAttribute) -> Self {
+    let mut raw = [0u8; 6];
+    raw.copy_from_slice(&attribute.raw.to_le_bytes()[..6]);
+
+    Self {
+      id: attribute.id,
+      name: attribute.name.clone(),
+      current: attribute.current,
+      worst: attribute.worst,
+      threshold: attribute.threshold,
+      raw,
+      ..Default::default()
+    }
+  }
+}
+
+/// A point-in-time reading of a disk, which can be stored and compared against later readings
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiskSnapshot {
+  /// The stable [`crate::id::DiskId`] as a string, or the device path if the disk doesn't have one
+  pub id: String,
+  pub path: PathBuf,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub model: Option<String>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub serial: Option<String>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub kind: Option<DiskKind>,
+  /// Seconds since the Unix epoch
+  pub timestamp: u64,
+  /// Whether the disk was asleep, in which case there is no SMART data
+  pub sleeping: bool,
+  /// Temperature in Celsius
+  pub temperature: Option<f64>,
+  pub power_on_hours: Option<u64>,
+  /// Health score from 0 to 100
+  pub health: Option<u8>,
+  pub health_status: Option<HealthStatus>,
+  pub attributes: Vec<SnapshotAttribute>,
+  /// Only set for NVMe drives
+  pub nvme: Option<NvmeSmartLog>,
+  pub stat: Option<DiskStat>,
+}
+
+impl DiskSnapshot {
+  /// Read everything we can from a disk. Nothing is read from a sleeping disk, other than kernel stats.
+  pub fn capture(disk: &mut Disk) -> Self {
+    let mut snapshot = Self {
+      id: disk
+        .id
+        .as_ref()
+        .map(|id| id.to_string())
+        .unwrap_or(disk.path.to_string_lossy().to_string()),
+      path: disk.path.clone(),
+      model: disk.model().ok().map(|model| model.trim().to_string()),
+      serial: sysfs_serial(&disk.path),
+      kind: Some(disk.kind.clone()),
+      timestamp: now(),
+      sleeping: disk.is_sleeping(),
+      stat: DiskStat::from_disk(&disk.path).ok(),
+      ..Default::default()
+    };
+
+    if snapshot.sleeping {
+      return snapshot;
+    }
+
+    if let Ok(health) = disk.health() {
+      snapshot.health = Some(health.score);
+      snapshot.health_status = Some(health.status);
+    }
+
+    if disk.kind == DiskKind::NVME {
+      if let Ok(log) = disk.nvme_smart_log() {
+        snapshot.temperature = Some(log.temperature_celsius() as f64);
+        snapshot.power_on_hours = Some(log.power_on_hours.min(u64::MAX as u128) as u64);
+        snapshot.nvme = Some(log);
+      }
+
+      return snapshot;
+    }
+
+    snapshot.attributes = disk
+      .get_all_attributes()
+      .iter()
+      .map(SnapshotAttribute::from)
+      .collect();
+
+    if let Some(mut raw) = disk.raw_disk() {
+      // SATA drives don't expose their serial in sysfs
+      if let Ok(identity) = raw.identify_parse() {
+        snapshot
+          .serial
+          .get_or_insert(identity.serial.trim().to_string());
+      }
+
+      // libatasmart reports these in mkelvin and milliseconds
+      snapshot.temperature = raw
+        .get_temperature()
+        .ok()
+        .filter(|t| *t > 0)
+        .map(|t| t as f64 / 1000. - 273.15);
+      snapshot.power_on_hours = raw.get_power_on().ok().map(|ms| ms / 1000 / 60 / 60);
+    }
+
+    snapshot
+  }
+
+  /// Find an attribute by ID
+  pub fn attribute(&self, id: u8) -> Option<&SnapshotAttribute> {
+    self.attributes.iter().find(|a| a.id == id)
+  }
+
+  /// Find an attribute by name (e.g. `"reallocated-sector-count"`)
+  pub fn attribute_by_name(&self, name: impl AsRef<str>) -> Option<&SnapshotAttribute> {
+    self.attributes.iter().find(|a| a.name == name.as_ref())
+  }
+
+  /// Rebuild the full health report (with reasons) from the recorded data. `None` if there is no SMART data.
+  pub fn health_report(&self, thresholds: &HealthThresholds) -> Option<HealthReport> {
+    if let Some(log) = &self.nvme {
+      return Some(HealthReport::from_nvme(log, thresholds));
+    }
+
+    if self.attributes.is_empty() {
+      return None;
+    }
+
+    let attributes = self
+      .attributes
+      .iter()
+      .map(Attribute::from)
+      .collect::<Vec<Attribute>>();
+
+    Some(HealthReport::from_attributes(&attributes, thresholds))
+  }
+
+  /// A plain text health report, for emails and logs
+  pub fn report(&self) -> String {
+    // Writing to a String can't fail
+    let mut out = String::new();
+    let model = self.model.as_deref().unwrap_or("Unknown model");
+
+    let _ = writeln!(out, "{model} ({})", self.path.display());
+    let _ = writeln!(out, "ID: {}", self.id);
+
+    if self.sleeping {
+      let _ = writeln!(out, "Drive is in standby, no SMART data was read");
+      return out;
+    }
+
+    match self.health_report(&HealthThresholds::default()) {
+      Some(health) => {
+        let _ = writeln!(out, "Health: {health}");
+        for reason in health.problems() {
+          let _ = writeln!(out, "  - {reason}");
+        }
+      }
+      None => {
+        let _ = writeln!(out, "Health: Unknown");
+      }
+    }
+
+    if let Some(temperature) = self.temperature {
+      let _ = writeln!(out, "Temperature: {temperature:.0} °C");
+    }
+
+    if let Some(hours) = self.power_on_hours {
+      let _ = writeln!(out, "Power on hours: {hours}");
+    }
+
+    if let Some(log) = &self.nvme {
+      let _ = writeln!(out, "\nNVMe health log:");
+      let _ = writeln!(out, "  Critical warning: {:#04x}", log.critical_warning);
+      let _ = writeln!(
+        out,
+        "  Available spare: {}% (threshold {}%)",
+        log.available_spare, log.available_spare_threshold
+      );
+      let _ = writeln!(out, "  Percentage used: {}%", log.percentage_used);
+      let _ = writeln!(out, "  Media errors: {}", log.media_errors);
+      let _ = writeln!(out, "  Error log entries: {}", log.error_log_entries);
+      let _ = writeln!(out, "  Unsafe shutdowns: {}", log.unsafe_shutdowns);
+    }
+
+    if !self.attributes.is_empty() {
+      let _ = writeln!(
+        out,
+        "\n{:>3}  {:<32} {:>5} {:>5} {:>6}  Raw",
+        "ID", "Name", "Value", "Worst", "Thresh"
+      );
+
+      for a in &self.attributes {
+        let _ = writeln!(
+          out,
+          "{:>3}  {:<32} {:>5} {:>5} {:>6}  {}",
+          a.id, a.name, a.current, a.worst, a.threshold, a.raw
+        );
+      }
+    }
+
+    out
+  }
+}
+
+/// The serial number from sysfs, which NVMe and some SCSI drives have
+fn sysfs_serial(path: &Path) -> Option<String> {
+  let name = path.file_name()?.to_str()?;
+
+  fs::read_to_string(format!("/sys/block/{name}/device/serial"))
+    .ok()
+    .map(|serial| serial.trim().to_string())
+    .filter(|serial| !serial.is_empty())
+}
+
+/// Seconds since the Unix epoch
+pub(crate) fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or_default()
+}
@@ -0,0 +1,174 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+#[cfg(target_os = "linux")]
+static SYS_CLASS_BLOCK_PATH: &str = "/sys/class/block";
+#[cfg(target_os = "linux")]
+static SYS_DEV_BLOCK_PATH: &str = "/sys/dev/block";
+#[cfg(target_os = "linux")]
+static DEV_PATH: &str = "/dev";
+
+/// Device-mapper (LVM, LUKS) and md devices can stack several levels deep, so stop somewhere sane if sysfs has a loop
+const MAX_DEPTH: usize = 16;
+
+/// The kernel name of a block device, e.g. `"dm-0"` for `/dev/mapper/vg-root`, or `"sda1"` for `/dev/sda1`
+pub fn kernel_name(device: impl AsRef<Path>) -> String {
+  let device = device.as_ref();
+
+  fs::canonicalize(device)
+    .unwrap_or(device.to_path_buf())
+    .file_name()
+    .unwrap_or_default()
+    .to_string_lossy()
+    .to_string()
+}
+
+/// The `major:minor` device number of a block device, e.g. `(8, 1)` for `/dev/sda1`
+pub fn device_number(device: impl AsRef<Path>) -> Option<(u32, u32)> {
+  let dev = fs::read_to_string(
+    Path::new(SYS_CLASS_BLOCK_PATH)
+      .join(kernel_name(device))
+      .join("dev"),
+  )
+  .ok()?;
+  let (major, minor) = dev.trim().split_once(':')?;
+
+  Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// The block device with a given device number, e.g. `/dev/sda1` for `(8, 1)`
+pub fn device_for_number(major: u32, minor: u32) -> Option<PathBuf> {
+  let dir =
+    fs::canonicalize(Path::new(SYS_DEV_BLOCK_PATH).join(format!("{major}:{minor}"))).ok()?;
+
+  Some(Path::new(DEV_PATH).join(dir.file_name()?))
+}
+
+/// The devices directly underneath a device, e.g. the LUKS partition under a `dm-crypt` mapping
+pub fn slaves(device: impl AsRef<Path>) -> Vec<PathBuf> {
+  list_dir(device.as_ref(), "slaves")
+}
+
+/// The devices directly on top of a device, e.g. the LVM volumes on a physical volume
+pub fn holders(device: impl AsRef<Path>) -> Vec<PathBuf> {
+  list_dir(device.as_ref(), "holders")
+}
+
+/// The partitions on a disk, e.g. `/dev/sda1` and `/dev/sda2` for `/dev/sda`
+pub fn partitions(disk: impl AsRef<Path>) -> Vec<PathBuf> {
+  let name = kernel_name(disk);
+  let Ok(entries) = fs::read_dir(Path::new(SYS_CLASS_BLOCK_PATH).join(&name)) else {
+    return vec![];
+  };
+
+  let mut partitions = entries
+    .flatten()
+    .filter(|entry| entry.path().join("partition").exists())
+    .map(|entry| Path::new(DEV_PATH).join(entry.file_name()))
+    .collect::<Vec<PathBuf>>();
+  partitions.sort();
+
+  partitions
+}
+
+/// The whole disk a device is on, e.g. `/dev/sda` for `/dev/sda1`. Anything that isn't a partition is returned as-is.
+pub fn whole_disk(device: impl AsRef<Path>) -> PathBuf {
+  let name = kernel_name(device);
+
+  // Partitions live inside their disk's directory, eg. /sys/devices/.../block/sda/sda1
+  fs::canonicalize(Path::new(SYS_CLASS_BLOCK_PATH).join(&name))
+    .ok()
+    .filter(|dir| dir.join("partition").exists())
+    .and_then(|dir| Some(dir.parent()?.file_name()?.to_string_lossy().to_string()))
+    .map(|disk| Path::new(DEV_PATH).join(disk))
+    .unwrap_or(Path::new(DEV_PATH).join(name))
+}
+
+/// Walk down from a device to the physical disks it's stored on, e.g. `/dev/mapper/vg-root` -> `dm-1` (LVM) ->
+/// `dm-0` (LUKS) -> `/dev/sda2` -> `/dev/sda`. A device spanning several disks (LVM, md) returns all of them.
+pub fn physical_disks(device: impl AsRef<Path>) -> Vec<PathBuf> {
+  let mut disks = vec![];
+  walk_down(
+    &Path::new(DEV_PATH).join(kernel_name(device)),
+    0,
+    &mut disks,
+  );
+
+  disks.sort();
+  disks.dedup();
+  disks
+}
+
+/// Walk up from a disk to everything stored on it: its partitions, and any device-mapper or md devices on top of those.
+/// The disk itself is not included.
+pub fn stacked_devices(disk: impl AsRef<Path>) -> Vec<PathBuf> {
+  let disk = Path::new(DEV_PATH).join(kernel_name(disk));
+  let mut devices = vec![];
+
+  for device in partitions(&disk).into_iter().chain([disk.clone()]) {
+    walk_up(&device, 0, &mut devices);
+  }
+
+  devices.retain(|device| *device != disk);
+  devices.sort();
+  devices.dedup();
+  devices
+}
+
+/// Whether `upper` is `lower`, or is stored on it through any number of device-mapper or md layers. e.g.
+/// `/dev/mapper/vg-root` is stacked on `/dev/sda2` if that's the LUKS partition under the LVM volume group.
+pub fn is_stacked_on(upper: impl AsRef<Path>, lower: impl AsRef<Path>) -> bool {
+  let lower = kernel_name(lower);
+  let mut devices = vec![(Path::new(DEV_PATH).join(kernel_name(upper)), 0)];
+
+  while let Some((device, depth)) = devices.pop() {
+    if kernel_name(&device) == lower {
+      return true;
+    }
+
+    if depth < MAX_DEPTH {
+      devices.extend(slaves(&device).into_iter().map(|slave| (slave, depth + 1)));
+    }
+  }
+
+  false
+}
+
+fn walk_down(device: &Path, depth: usize, disks: &mut Vec<PathBuf>) {
+  let below = slaves(device);
+
+  if below.is_empty() || depth >= MAX_DEPTH {
+    disks.push(whole_disk(device));
+    return;
+  }
+
+  for slave in below {
+    walk_down(&slave, depth + 1, disks);
+  }
+}
+
+fn walk_up(device: &Path, depth: usize, devices: &mut Vec<PathBuf>) {
+  devices.push(device.to_path_buf());
+
+  if depth >= MAX_DEPTH {
+    return;
+  }
+
+  for holder in holders(device) {
+    walk_up(&holder, depth + 1, devices);
+  }
+}
+
+fn list_dir(device: &Path, dir: &str) -> Vec<PathBuf> {
+  let name = kernel_name(device);
+  let Ok(entries) = fs::read_dir(Path::new(SYS_CLASS_BLOCK_PATH).join(name).join(dir)) else {
+    return vec![];
+  };
+
+  entries
+    .flatten()
+    .map(|entry| Path::new(DEV_PATH).join(entry.file_name()))
+    .collect()
+}
@@ -1,7 +1,8 @@
 use std::path::{Path, PathBuf};
 
 /// A struct containing kernel-provided disk statistics, by reading from `/sys/block/<disk>/stat`.
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DiskStat {
   pub read_io: u64,
   pub write_io: u64,
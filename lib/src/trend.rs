@@ -0,0 +1,307 @@
+use std::{fmt::Display, time::Duration};
+
+use crate::{health::WEAR_ATTRIBUTES, snapshot::DiskSnapshot};
+
+const DAY: f64 = 60. * 60. * 24.;
+/// Forecasts need at least this many points
+const MIN_POINTS: usize = 3;
+/// Confidence is scaled down until there are this many points
+const FULL_CONFIDENCE_POINTS: usize = 10;
+
+/// A degradation signal that can be forecast from history
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TrendMetric {
+  /// Reallocated sector count (5)
+  Reallocated,
+  /// Current pending sector count (197)
+  Pending,
+  /// Offline uncorrectable sector count (198)
+  Uncorrectable,
+  /// UDMA CRC error count (199), usually a cabling problem
+  CrcErrors,
+  /// Remaining life from the SSD wear indicator, in percent. Unlike the others, this goes down.
+  WearRemaining,
+}
+
+impl TrendMetric {
+  pub const ALL: [TrendMetric; 5] = [
+    TrendMetric::Reallocated,
+    TrendMetric::Pending,
+    TrendMetric::Uncorrectable,
+    TrendMetric::CrcErrors,
+    TrendMetric::WearRemaining,
+  ];
+
+  /// Read this metric from a snapshot
+  pub fn value(&self, snapshot: &DiskSnapshot) -> Option<f64> {
+    let raw = |id: u8| snapshot.attribute(id).map(|a| (a.raw & 0xffff_ffff) as f64);
+
+    match self {
+      TrendMetric::Reallocated => raw(5),
+      TrendMetric::Pending => raw(197),
+      TrendMetric::Uncorrectable => raw(198),
+      TrendMetric::CrcErrors => raw(199),
+      TrendMetric::WearRemaining => {
+        if let Some(log) = &snapshot.nvme {
+          return Some(100u8.saturating_sub(log.percentage_used) as f64);
+        }
+
+        WEAR_ATTRIBUTES
+          .iter()
+          .find_map(|id| snapshot.attribute(*id))
+          .map(|a| a.current.min(100) as f64)
+      }
+    }
+  }
+
+  /// Whether this metric gets worse as it decreases
+  pub fn decreasing(&self) -> bool {
+    *self == TrendMetric::WearRemaining
+  }
+}
+
+impl Display for TrendMetric {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      TrendMetric::Reallocated => write!(f, "reallocated sectors"),
+      TrendMetric::Pending => write!(f, "pending sectors"),
+      TrendMetric::Uncorrectable => write!(f, "uncorrectable sectors"),
+      TrendMetric::CrcErrors => write!(f, "CRC errors"),
+      TrendMetric::WearRemaining => write!(f, "wear"),
+    }
+  }
+}
+
+/// Which curve fit the history best
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TrendModel {
+  Linear,
+  /// Growing by a constant factor over time
+  Exponential,
+}
+
+/// A least-squares fit of `y = intercept + slope * x`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Fit {
+  pub slope: f64,
+  pub intercept: f64,
+  /// Coefficient of determination, from 0 (no fit) to 1 (perfect fit)
+  pub r_squared: f64,
+}
+
+impl Fit {
+  /// Fit a line to `(x, y)` points. Returns `None` if there are fewer than two distinct `x` values.
+  pub fn linear(points: &[(f64, f64)]) -> Option<Self> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let ss_xx = points
+      .iter()
+      .map(|(x, _)| (x - mean_x).powi(2))
+      .sum::<f64>();
+    let ss_xy = points
+      .iter()
+      .map(|(x, y)| (x - mean_x) * (y - mean_y))
+      .sum::<f64>();
+
+    if points.len() < 2 || ss_xx == 0. {
+      return None;
+    }
+
+    let slope = ss_xy / ss_xx;
+    let intercept = mean_y - slope * mean_x;
+    let ss_tot = points
+      .iter()
+      .map(|(_, y)| (y - mean_y).powi(2))
+      .sum::<f64>();
+    let ss_res = points
+      .iter()
+      .map(|(x, y)| (y - (intercept + slope * x)).powi(2))
+      .sum::<f64>();
+
+    Some(Self {
+      slope,
+      intercept,
+      // A flat line is a perfect fit for flat data
+      r_squared: if ss_tot == 0. {
+        1.
+      } else {
+        (1. - ss_res / ss_tot).max(0.)
+      },
+    })
+  }
+}
+
+/// A forecast for one metric
+#[derive(Clone, PartialEq, Debug)]
+pub struct Forecast {
+  pub metric: TrendMetric,
+  pub model: TrendModel,
+  /// The most recent value
+  pub current: f64,
+  /// Change per day, from the linear fit
+  pub rate_per_day: f64,
+  /// How long it takes the value to double, if it is growing exponentially
+  pub doubling_time: Option<Duration>,
+  /// Whether the rate of degradation is increasing
+  pub accelerating: bool,
+  pub threshold: Option<f64>,
+  /// How long until `threshold` is crossed at the current trend
+  pub eta: Option<Duration>,
+  /// From 0 to 1. This is the goodness of fit, scaled down when there is little history.
+  pub confidence: f64,
+}
+
+impl Forecast {
+  /// Whether the metric is getting worse at all
+  pub fn degrading(&self) -> bool {
+    if self.metric.decreasing() {
+      self.rate_per_day < 0.
+    } else {
+      self.rate_per_day > 0.
+    }
+  }
+}
+
+impl Display for Forecast {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let days = |d: Duration| d.as_secs_f64() / DAY;
+
+    if let Some(doubling) = self.doubling_time {
+      let days = days(doubling);
+      let period = if (6.0..=8.0).contains(&days) {
+        "weekly".to_string()
+      } else if (0.5..=1.5).contains(&days) {
+        "daily".to_string()
+      } else {
+        format!("every ~{days:.0} days")
+      };
+
+      write!(f, "{} doubling {period}", self.metric)?;
+    } else if !self.degrading() {
+      write!(f, "{} stable at {}", self.metric, self.current)?;
+    } else {
+      write!(
+        f,
+        "{} changing by {:+.2}/day",
+        self.metric, self.rate_per_day
+      )?;
+    }
+
+    if let (Some(threshold), Some(eta)) = (self.threshold, self.eta) {
+      write!(f, ", hits {threshold} in ~{:.0} days", days(eta))?;
+    }
+
+    write!(f, " ({:.0}% confidence)", self.confidence * 100.)
+  }
+}
+
+/// Forecast a metric from history (in any order). `threshold` is the value to estimate the crossing time of, eg. 10 for
+/// 10% wear remaining. Returns `None` if there isn't enough history.
+pub fn forecast(
+  history: &[DiskSnapshot],
+  metric: TrendMetric,
+  threshold: Option<f64>,
+) -> Option<Forecast> {
+  let mut points = history
+    .iter()
+    .filter_map(|snapshot| Some((snapshot.timestamp, metric.value(snapshot)?)))
+    .collect::<Vec<(u64, f64)>>();
+  points.sort_by_key(|(timestamp, _)| *timestamp);
+
+  if points.len() < MIN_POINTS {
+    return None;
+  }
+
+  // Days since the first point
+  let start = points[0].0;
+  let points = points
+    .into_iter()
+    .map(|(timestamp, value)| ((timestamp - start) as f64 / DAY, value))
+    .collect::<Vec<(f64, f64)>>();
+  let (_, current) = *points.last()?;
+
+  let linear = Fit::linear(&points)?;
+
+  // Exponential growth is a straight line in log space. Only makes sense for counts that are growing.
+  let exponential = if metric.decreasing() {
+    None
+  } else {
+    let logs = points
+      .iter()
+      .filter(|(_, y)| *y > 0.)
+      .map(|(x, y)| (*x, y.ln()))
+      .collect::<Vec<(f64, f64)>>();
+
+    (logs.len() >= MIN_POINTS)
+      .then(|| Fit::linear(&logs))
+      .flatten()
+      .filter(|fit| fit.slope > 0. && fit.r_squared > linear.r_squared)
+  };
+
+  // Compare the rate over the first and second halves
+  let half = points.len() / 2;
+  let accelerating = match (Fit::linear(&points[..=half]), Fit::linear(&points[half..])) {
+    (Some(first), Some(second)) if metric.decreasing() => {
+      second.slope < first.slope * 1.5 && second.slope < 0.
+    }
+    (Some(first), Some(second)) => second.slope > first.slope * 1.5 && second.slope > 0.,
+    _ => false,
+  } || exponential.is_some();
+
+  let (model, r_squared) = match exponential {
+    Some(fit) => (TrendModel::Exponential, fit.r_squared),
+    None => (TrendModel::Linear, linear.r_squared),
+  };
+
+  // Days from now until the threshold is crossed
+  let eta = threshold.and_then(|threshold| {
+    let days = match exponential {
+      Some(fit) if current > 0. && threshold > current => {
+        (threshold.ln() - current.ln()) / fit.slope
+      }
+      _ if linear.slope != 0. => (threshold - current) / linear.slope,
+      _ => return None,
+    };
+
+    // A tiny slope can put it further out than a Duration can hold, which is as good as never
+    Duration::try_from_secs_f64(days * DAY).ok()
+  });
+
+  let samples = (points.len() as f64 / FULL_CONFIDENCE_POINTS as f64).min(1.);
+
+  Some(Forecast {
+    metric,
+    model,
+    current,
+    rate_per_day: linear.slope,
+    doubling_time: exponential
+      .and_then(|fit| Duration::try_from_secs_f64(2f64.ln() / fit.slope * DAY).ok()),
+    accelerating,
+    threshold,
+    eta,
+    confidence: r_squared * samples,
+  })
+}
+
+/// Forecast every [`TrendMetric`] that has enough history, using the given thresholds for sector counts and remaining wear.
+/// Only metrics that are actually degrading are returned.
+pub fn forecast_all(
+  history: &[DiskSnapshot],
+  sector_threshold: f64,
+  wear_threshold: f64,
+) -> Vec<Forecast> {
+  TrendMetric::ALL
+    .iter()
+    .filter_map(|metric| {
+      let threshold = match metric {
+        TrendMetric::WearRemaining => Some(wear_threshold),
+        TrendMetric::CrcErrors => None,
+        _ => Some(sector_threshold),
+      };
+
+      forecast(history, *metric, threshold)
+    })
+    .filter(|forecast| forecast.degrading())
+    .collect()
+}
@@ -0,0 +1,184 @@
+use std::{
+  fs::{self, File, OpenOptions},
+  path::PathBuf,
+};
+
+use libglacierdisk::benchmark::{BenchmarkType, IoEngine, SyncEngine, UringEngine};
+
+const BLOCK: usize = 4096;
+
+/// A file of `blocks` blocks in the temp dir, removed on drop
+struct Scratch {
+  path: PathBuf,
+  file: File,
+}
+
+impl Scratch {
+  fn new(name: &str, blocks: usize) -> Self {
+    let path = std::env::temp_dir().join(format!("glacierdisk-{name}-{}.bin", std::process::id()));
+    let file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .create(true)
+      .truncate(true)
+      .open(&path)
+      .unwrap();
+    file.set_len((blocks * BLOCK) as u64).unwrap();
+
+    Self { path, file }
+  }
+}
+
+impl Drop for Scratch {
+  fn drop(&mut self) {
+    fs::remove_file(&self.path).unwrap_or_default();
+  }
+}
+
+fn offsets(blocks: usize) -> Vec<u64> {
+  (0..blocks).map(|block| (block * BLOCK) as u64).collect()
+}
+
+/// Every engine the kernel supports. io_uring is often blocked in containers.
+fn engines() -> Vec<(&'static str, Box<dyn IoEngine>)> {
+  let mut engines: Vec<(&str, Box<dyn IoEngine>)> = vec![("sync", Box::new(SyncEngine))];
+
+  if UringEngine::supported() {
+    engines.push(("io_uring", Box::new(UringEngine)));
+  } else {
+    eprintln!("io_uring isn't supported here, only testing the sync engine");
+  }
+
+  engines
+}
+
+fn run(
+  engine: &dyn IoEngine,
+  file: &File,
+  kind: &BenchmarkType,
+  offsets: &[u64],
+  queue_depth: usize,
+) -> u64 {
+  engine
+    .prepare(file, kind, offsets, BLOCK, queue_depth)
+    .unwrap()
+    .run()
+    .unwrap()
+}
+
+#[test]
+fn same_ops() {
+  let offsets = offsets(64);
+
+  for (name, engine) in engines() {
+    let scratch = Scratch::new(&format!("same-{name}"), 64);
+
+    for queue_depth in [1, 4, 32] {
+      assert_eq!(
+        run(
+          engine.as_ref(),
+          &scratch.file,
+          &BenchmarkType::Write,
+          &offsets,
+          queue_depth
+        ),
+        64,
+        "{name} writes at QD{queue_depth}"
+      );
+      assert_eq!(
+        run(
+          engine.as_ref(),
+          &scratch.file,
+          &BenchmarkType::Read,
+          &offsets,
+          queue_depth
+        ),
+        64,
+        "{name} reads at QD{queue_depth}"
+      );
+    }
+
+    // Every block was written over the zeroes it started with
+    let written = fs::read(&scratch.path).unwrap();
+    assert_eq!(written.len(), 64 * BLOCK);
+    assert!(written
+      .chunks(BLOCK)
+      .all(|block| block.iter().any(|byte| *byte != 0)));
+  }
+}
+
+#[test]
+fn short_file() {
+  // Reads past the end stop the benchmark early, rather than failing it
+  let offsets = offsets(16);
+
+  for (name, engine) in engines() {
+    let scratch = Scratch::new(&format!("short-{name}"), 4);
+
+    assert_eq!(
+      run(
+        engine.as_ref(),
+        &scratch.file,
+        &BenchmarkType::Read,
+        &offsets,
+        1
+      ),
+      4,
+      "{name}"
+    );
+
+    // With more in flight, some past the end may already be queued, but never more than the file holds
+    let ops = run(
+      engine.as_ref(),
+      &scratch.file,
+      &BenchmarkType::Read,
+      &offsets,
+      8,
+    );
+    assert!((1..=4).contains(&ops), "{name} read {ops} blocks");
+  }
+}
+
+#[test]
+fn deep_queue() {
+  // More slots than blocks
+  let offsets = offsets(3);
+
+  for (name, engine) in engines() {
+    let scratch = Scratch::new(&format!("deep-{name}"), 3);
+
+    assert_eq!(
+      run(
+        engine.as_ref(),
+        &scratch.file,
+        &BenchmarkType::Write,
+        &offsets,
+        128
+      ),
+      3,
+      "{name}"
+    );
+    assert_eq!(
+      run(
+        engine.as_ref(),
+        &scratch.file,
+        &BenchmarkType::Read,
+        &offsets,
+        128
+      ),
+      3,
+      "{name}"
+    );
+    assert_eq!(
+      run(
+        engine.as_ref(),
+        &scratch.file,
+        &BenchmarkType::Read,
+        &[],
+        128
+      ),
+      0,
+      "{name} with nothing to do"
+    );
+  }
+}
@@ -0,0 +1,292 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use libglacierdisk::filesystem::{probe, Filesystem, FilesystemKind};
+
+const UUID: [u8; 16] = [
+  0x1b, 0x4e, 0x28, 0xba, 0x2f, 0xa1, 0x11, 0xd2, 0x88, 0x3f, 0x00, 0x16, 0xd3, 0xcc, 0xa4, 0x27,
+];
+const UUID_STR: &str = "1b4e28ba-2fa1-11d2-883f-0016d3cca427";
+
+fn put(image: &mut [u8], at: usize, bytes: &[u8]) {
+  image[at..at + bytes.len()].copy_from_slice(bytes);
+}
+
+fn utf16(text: &str) -> Vec<u8> {
+  text
+    .encode_utf16()
+    .flat_map(|unit| unit.to_le_bytes())
+    .collect()
+}
+
+/// An image that starts `base` bytes into an otherwise empty device, for filesystems near the end of a huge disk
+struct Shifted {
+  image: Cursor<Vec<u8>>,
+  base: u64,
+}
+
+impl Read for Shifted {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    self.image.read(buf)
+  }
+}
+
+impl Seek for Shifted {
+  fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+    let SeekFrom::Start(offset) = pos else {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Only seeking from the start is supported",
+      ));
+    };
+
+    // Anything before the image reads as the end
+    let len = self.image.get_ref().len() as u64;
+    self.image.seek(SeekFrom::Start(
+      offset.checked_sub(self.base).unwrap_or(len),
+    ))?;
+
+    Ok(offset)
+  }
+}
+
+fn probe_image(image: Vec<u8>) -> Option<Filesystem> {
+  probe(&mut Cursor::new(image), 0).unwrap()
+}
+
+fn ext_image(compat: u32, incompat: u32) -> Vec<u8> {
+  let mut image = vec![0; 8192];
+  put(&mut image, 1024 + 56, &0xef53u16.to_le_bytes());
+  put(&mut image, 1024 + 92, &compat.to_le_bytes());
+  put(&mut image, 1024 + 96, &incompat.to_le_bytes());
+  put(&mut image, 1024 + 104, &UUID);
+  put(&mut image, 1024 + 120, b"rootfs");
+  image
+}
+
+fn ntfs_image(mft_cluster: u64) -> Vec<u8> {
+  let mut image = vec![0; 32768];
+  put(&mut image, 3, b"NTFS    ");
+  put(&mut image, 11, &512u16.to_le_bytes());
+  // 4 KiB clusters
+  image[13] = 8;
+  put(&mut image, 48, &mft_cluster.to_le_bytes());
+  // 2^10 byte MFT records
+  image[64] = -10i8 as u8;
+  put(&mut image, 72, &0x1122334455667788u64.to_le_bytes());
+
+  // $Volume is MFT record 3, with a $VOLUME_NAME attribute
+  let record = 4 * 4096 + 3 * 1024;
+  let name = utf16("Windows");
+  put(&mut image, record, b"FILE");
+  put(&mut image, record + 20, &56u16.to_le_bytes());
+  let attribute = record + 56;
+  put(&mut image, attribute, &0x60u32.to_le_bytes());
+  put(&mut image, attribute + 4, &40u32.to_le_bytes());
+  put(
+    &mut image,
+    attribute + 16,
+    &(name.len() as u32).to_le_bytes(),
+  );
+  put(&mut image, attribute + 20, &24u16.to_le_bytes());
+  put(&mut image, attribute + 24, &name);
+  put(&mut image, attribute + 40, &0xffff_ffffu32.to_le_bytes());
+  image
+}
+
+fn exfat_image(heap_offset: u32, root_cluster: u32) -> Vec<u8> {
+  let mut image = vec![0; 32768];
+  put(&mut image, 3, b"EXFAT   ");
+  put(&mut image, 88, &heap_offset.to_le_bytes());
+  put(&mut image, 96, &root_cluster.to_le_bytes());
+  put(&mut image, 100, &0xa1b2c3d4u32.to_le_bytes());
+  // 512 byte sectors, 8 sectors per cluster
+  image[108] = 9;
+  image[109] = 3;
+
+  // Root directory at sector 32 + (4 - 2) * 8
+  let root = 48 * 512;
+  let name = utf16("CAMERA");
+  image[root] = 0x83;
+  image[root + 1] = 6;
+  put(&mut image, root + 2, &name);
+  image
+}
+
+#[test]
+fn ext() {
+  let ext4 = probe_image(ext_image(0x4, 0x40)).unwrap();
+  assert_eq!(ext4.kind, FilesystemKind::Ext4);
+  assert_eq!(ext4.label.as_deref(), Some("rootfs"));
+  assert_eq!(ext4.uuid.as_deref(), Some(UUID_STR));
+
+  assert_eq!(
+    probe_image(ext_image(0x4, 0)).unwrap().kind,
+    FilesystemKind::Ext3
+  );
+  assert_eq!(
+    probe_image(ext_image(0, 0)).unwrap().kind,
+    FilesystemKind::Ext2
+  );
+}
+
+#[test]
+fn xfs() {
+  let mut image = vec![0; 8192];
+  put(&mut image, 0, b"XFSB");
+  put(&mut image, 32, &UUID);
+  put(&mut image, 108, b"data");
+
+  let xfs = probe_image(image).unwrap();
+  assert_eq!(xfs.kind, FilesystemKind::Xfs);
+  assert_eq!(xfs.label.as_deref(), Some("data"));
+  assert_eq!(xfs.uuid.as_deref(), Some(UUID_STR));
+}
+
+#[test]
+fn btrfs() {
+  let mut image = vec![0; 0x11000];
+  put(&mut image, 0x10000 + 0x20, &UUID);
+  put(&mut image, 0x10000 + 0x40, b"_BHRfS_M");
+  put(&mut image, 0x10000 + 0x12b, b"pool");
+
+  let btrfs = probe_image(image).unwrap();
+  assert_eq!(btrfs.kind, FilesystemKind::Btrfs);
+  assert_eq!(btrfs.label.as_deref(), Some("pool"));
+  assert_eq!(btrfs.uuid.as_deref(), Some(UUID_STR));
+}
+
+#[test]
+fn vfat() {
+  let mut fat32 = vec![0; 8192];
+  put(&mut fat32, 67, &0xa1b2c3d4u32.to_le_bytes());
+  put(&mut fat32, 71, b"EFI        ");
+  put(&mut fat32, 82, b"FAT32   ");
+  put(&mut fat32, 510, &[0x55, 0xaa]);
+
+  let fat32 = probe_image(fat32).unwrap();
+  assert_eq!(fat32.kind, FilesystemKind::Vfat);
+  assert_eq!(fat32.label.as_deref(), Some("EFI"));
+  assert_eq!(fat32.uuid.as_deref(), Some("A1B2-C3D4"));
+
+  let mut fat16 = vec![0; 8192];
+  put(&mut fat16, 39, &0x12345678u32.to_le_bytes());
+  put(&mut fat16, 43, b"NO NAME    ");
+  put(&mut fat16, 54, b"FAT16   ");
+  put(&mut fat16, 510, &[0x55, 0xaa]);
+
+  let fat16 = probe_image(fat16).unwrap();
+  assert_eq!(fat16.kind, FilesystemKind::Vfat);
+  assert_eq!(fat16.label, None);
+  assert_eq!(fat16.uuid.as_deref(), Some("1234-5678"));
+}
+
+#[test]
+fn exfat() {
+  let exfat = probe_image(exfat_image(32, 4)).unwrap();
+  assert_eq!(exfat.kind, FilesystemKind::Exfat);
+  assert_eq!(exfat.label.as_deref(), Some("CAMERA"));
+  assert_eq!(exfat.uuid.as_deref(), Some("A1B2-C3D4"));
+}
+
+#[test]
+fn ntfs() {
+  let ntfs = probe_image(ntfs_image(4)).unwrap();
+  assert_eq!(ntfs.kind, FilesystemKind::Ntfs);
+  assert_eq!(ntfs.label.as_deref(), Some("Windows"));
+  assert_eq!(ntfs.uuid.as_deref(), Some("1122334455667788"));
+}
+
+#[test]
+fn swap() {
+  let mut image = vec![0; 8192];
+  put(&mut image, 1036, &UUID);
+  put(&mut image, 1052, b"swap0");
+  put(&mut image, 4086, b"SWAPSPACE2");
+
+  let swap = probe_image(image).unwrap();
+  assert_eq!(swap.kind, FilesystemKind::Swap);
+  assert_eq!(swap.label.as_deref(), Some("swap0"));
+  assert_eq!(swap.uuid.as_deref(), Some(UUID_STR));
+}
+
+#[test]
+fn luks() {
+  let luks = |version: u16| {
+    let mut image = vec![0; 8192];
+    put(&mut image, 0, b"LUKS\xba\xbe");
+    put(&mut image, 6, &version.to_be_bytes());
+    put(&mut image, 24, b"vault");
+    put(&mut image, 168, UUID_STR.as_bytes());
+    probe_image(image).unwrap()
+  };
+
+  let luks2 = luks(2);
+  assert_eq!(luks2.kind, FilesystemKind::Luks);
+  assert_eq!(luks2.label.as_deref(), Some("vault"));
+  assert_eq!(luks2.uuid.as_deref(), Some(UUID_STR));
+
+  // The label field is the cipher name in LUKS1
+  assert_eq!(luks(1).label, None);
+}
+
+#[test]
+fn nothing() {
+  assert_eq!(probe_image(vec![]), None);
+  assert_eq!(probe_image(vec![0; 0x20000]), None);
+}
+
+#[test]
+fn truncated() {
+  // The ext superblock is cut off partway through
+  let mut ext = ext_image(0, 0x40);
+  ext.truncate(1100);
+  assert_eq!(probe_image(ext), None);
+
+  // The NTFS MFT is past the end of the image
+  let mut ntfs = ntfs_image(4);
+  ntfs.truncate(4096);
+  let ntfs = probe_image(ntfs).unwrap();
+  assert_eq!(ntfs.kind, FilesystemKind::Ntfs);
+  assert_eq!(ntfs.label, None);
+}
+
+#[test]
+fn corrupt() {
+  // An MFT cluster number that overflows when converted to bytes
+  let ntfs = probe_image(ntfs_image(u64::MAX)).unwrap();
+  assert_eq!(ntfs.label, None);
+
+  // A root directory far past the end of the image
+  let exfat = probe_image(exfat_image(u32::MAX, u32::MAX)).unwrap();
+  assert_eq!(exfat.label, None);
+
+  // Filesystems so close to the end of the device that the offsets of their other structures overflow
+  let base = u64::MAX - 8192;
+  let exfat = probe(
+    &mut Shifted {
+      image: Cursor::new(exfat_image(32, 4)),
+      base,
+    },
+    base,
+  )
+  .unwrap()
+  .unwrap();
+  assert_eq!(exfat.kind, FilesystemKind::Exfat);
+  assert_eq!(exfat.label, None);
+
+  let ntfs = probe(
+    &mut Shifted {
+      image: Cursor::new(ntfs_image(4)),
+      base,
+    },
+    base,
+  )
+  .unwrap()
+  .unwrap();
+  assert_eq!(ntfs.label, None);
+
+  assert_eq!(
+    probe(&mut Cursor::new(vec![]), u64::MAX - 10).unwrap(),
+    None
+  );
+}
@@ -0,0 +1,289 @@
+use libglacierdisk::{
+  attribute::Attribute,
+  health::{HealthReport, HealthStatus, HealthThresholds},
+  nvme::{
+    NvmeSmartLog, WARNING_PMR, WARNING_READ_ONLY, WARNING_RELIABILITY, WARNING_SPARE,
+    WARNING_TEMPERATURE, WARNING_VOLATILE_BACKUP,
+  },
+};
+
+use HealthStatus::{Bad, Caution, Good};
+
+/// A healthy attribute, with a raw count
+fn attribute(id: u8, name: &str, raw: u64) -> Attribute {
+  let mut bytes = [0u8; 6];
+  bytes.copy_from_slice(&raw.to_le_bytes()[..6]);
+
+  Attribute {
+    id,
+    name: name.to_string(),
+    current: 100,
+    worst: 100,
+    raw: bytes,
+    ..Default::default()
+  }
+}
+
+/// An attribute with a normalized value and threshold
+fn normalized(id: u8, name: &str, current: u8, worst: u8, threshold: u8) -> Attribute {
+  Attribute {
+    current,
+    worst,
+    threshold,
+    ..attribute(id, name, 0)
+  }
+}
+
+fn reallocated(count: u64) -> Attribute {
+  attribute(5, "reallocated-sector-count", count)
+}
+
+fn pending(count: u64) -> Attribute {
+  attribute(197, "current-pending-sector", count)
+}
+
+fn uncorrectable(count: u64) -> Attribute {
+  attribute(198, "offline-uncorrectable", count)
+}
+
+#[test]
+fn ata() {
+  let cases = [
+    ("nothing", vec![], Good, 100),
+    (
+      "no bad sectors",
+      vec![reallocated(0), pending(0)],
+      Good,
+      100,
+    ),
+    ("one reallocated", vec![reallocated(1)], Caution, 99),
+    ("one pending", vec![pending(1)], Caution, 99),
+    ("one uncorrectable", vec![uncorrectable(1)], Caution, 99),
+    ("just under bad", vec![reallocated(99)], Caution, 1),
+    ("at bad", vec![reallocated(100)], Bad, 0),
+    ("way past bad", vec![reallocated(1_000_000)], Bad, 0),
+    (
+      "bad in total",
+      vec![reallocated(50), pending(30), uncorrectable(20)],
+      Bad,
+      0,
+    ),
+    (
+      "vendor data in the upper raw bytes",
+      vec![reallocated(0xffff_0000_0000)],
+      Good,
+      100,
+    ),
+    (
+      "reallocation events",
+      vec![attribute(196, "reallocated-event-count", 3)],
+      Caution,
+      100,
+    ),
+    (
+      "under threshold",
+      vec![normalized(1, "raw-read-error-rate", 6, 6, 6)],
+      Bad,
+      100,
+    ),
+    (
+      "was under threshold",
+      vec![normalized(1, "raw-read-error-rate", 7, 6, 6)],
+      Caution,
+      100,
+    ),
+    (
+      "just over threshold",
+      vec![normalized(1, "raw-read-error-rate", 7, 7, 6)],
+      Good,
+      100,
+    ),
+    (
+      "no threshold",
+      vec![normalized(190, "airflow-temperature-celsius", 0, 0, 0)],
+      Good,
+      100,
+    ),
+    (
+      "plenty of life",
+      vec![normalized(231, "ssd-life-left", 80, 80, 0)],
+      Good,
+      80,
+    ),
+    (
+      "life just over caution",
+      vec![normalized(231, "ssd-life-left", 11, 11, 0)],
+      Good,
+      11,
+    ),
+    (
+      "life at caution",
+      vec![normalized(231, "ssd-life-left", 10, 10, 0)],
+      Caution,
+      10,
+    ),
+    (
+      "worn out",
+      vec![normalized(233, "media-wearout-indicator", 0, 0, 0)],
+      Bad,
+      0,
+    ),
+    (
+      "life over 100",
+      vec![normalized(177, "wear-leveling-count", 200, 200, 0)],
+      Good,
+      100,
+    ),
+    (
+      "preferred life attribute",
+      vec![
+        normalized(177, "wear-leveling-count", 5, 5, 0),
+        normalized(231, "ssd-life-left", 90, 90, 0),
+      ],
+      Good,
+      90,
+    ),
+    (
+      "lowest score wins",
+      vec![reallocated(30), normalized(231, "ssd-life-left", 90, 90, 0)],
+      Caution,
+      70,
+    ),
+  ];
+
+  for (name, attributes, status, score) in cases {
+    let report = HealthReport::from_attributes(&attributes, &HealthThresholds::default());
+    assert_eq!((report.status, report.score), (status, score), "{name}");
+  }
+}
+
+#[test]
+fn ata_custom_thresholds() {
+  let thresholds = HealthThresholds {
+    sectors_caution: 5,
+    sectors_bad: 10,
+    life_caution: 50,
+    life_bad: 20,
+  };
+  let cases = [
+    (vec![reallocated(4)], Good, 60),
+    (vec![reallocated(5)], Caution, 50),
+    (vec![reallocated(10)], Bad, 0),
+    (vec![normalized(231, "ssd-life-left", 51, 51, 0)], Good, 51),
+    (
+      vec![normalized(231, "ssd-life-left", 50, 50, 0)],
+      Caution,
+      50,
+    ),
+    (vec![normalized(231, "ssd-life-left", 20, 20, 0)], Bad, 20),
+  ];
+
+  for (attributes, status, score) in cases {
+    let report = HealthReport::from_attributes(&attributes, &thresholds);
+    assert_eq!((report.status, report.score), (status, score));
+  }
+
+  // A 0 for bad sectors doesn't divide by zero
+  let thresholds = HealthThresholds {
+    sectors_caution: 0,
+    sectors_bad: 0,
+    ..Default::default()
+  };
+  let report = HealthReport::from_attributes(&[reallocated(1)], &thresholds);
+  assert_eq!((report.status, report.score), (Bad, 0));
+}
+
+#[test]
+fn ata_reasons() {
+  let report = HealthReport::from_attributes(
+    &[
+      reallocated(2),
+      normalized(1, "raw-read-error-rate", 1, 1, 6),
+      normalized(231, "ssd-life-left", 90, 90, 0),
+    ],
+    &HealthThresholds::default(),
+  );
+
+  // Worst first, with the informational life entry last
+  let reasons = report
+    .reasons
+    .iter()
+    .map(|reason| (reason.id, reason.status))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    reasons,
+    vec![(Some(1), Bad), (Some(5), Caution), (Some(231), Good)]
+  );
+  assert_eq!(report.reasons[1].message, "2 reallocated sectors");
+  assert_eq!(report.problems().count(), 2);
+}
+
+#[test]
+fn nvme() {
+  let log = |critical_warning: u8, percentage_used: u8, media_errors: u128| NvmeSmartLog {
+    critical_warning,
+    percentage_used,
+    media_errors,
+    ..Default::default()
+  };
+  let cases = [
+    ("new", log(0, 0, 0), Good, 100),
+    ("some wear", log(0, 40, 0), Good, 60),
+    ("life at caution", log(0, 90, 0), Caution, 10),
+    ("life just over caution", log(0, 89, 0), Good, 11),
+    ("worn out", log(0, 100, 0), Bad, 0),
+    ("past rated life", log(0, 255, 0), Bad, 0),
+    ("a media error", log(0, 0, 1), Caution, 99),
+    ("media errors at bad", log(0, 0, 100), Bad, 0),
+    (
+      "too many media errors for a u64",
+      log(0, 0, u128::MAX),
+      Bad,
+      0,
+    ),
+    ("spare", log(WARNING_SPARE, 0, 0), Bad, 100),
+    ("temperature", log(WARNING_TEMPERATURE, 0, 0), Caution, 100),
+    ("reliability", log(WARNING_RELIABILITY, 0, 0), Bad, 100),
+    ("read only", log(WARNING_READ_ONLY, 0, 0), Bad, 100),
+    (
+      "volatile backup",
+      log(WARNING_VOLATILE_BACKUP, 0, 0),
+      Caution,
+      100,
+    ),
+    ("pmr", log(WARNING_PMR, 0, 0), Caution, 100),
+    ("reserved warning bits", log(0xc0, 0, 0), Good, 100),
+  ];
+
+  for (name, log, status, score) in cases {
+    let report = HealthReport::from_nvme(&log, &HealthThresholds::default());
+    assert_eq!((report.status, report.score), (status, score), "{name}");
+  }
+}
+
+#[test]
+fn nvme_reasons() {
+  let log = NvmeSmartLog {
+    critical_warning: WARNING_TEMPERATURE | WARNING_SPARE,
+    percentage_used: 3,
+    media_errors: 2,
+    ..Default::default()
+  };
+  let report = HealthReport::from_nvme(&log, &HealthThresholds::default());
+
+  let reasons = report
+    .reasons
+    .iter()
+    .map(|reason| (reason.attribute.as_str(), reason.status))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    reasons,
+    vec![
+      ("critical-warning", Bad),
+      ("critical-warning", Caution),
+      ("media-errors", Caution),
+      ("percentage-used", Good),
+    ]
+  );
+  assert_eq!(report.reasons[3].message, "97% of rated life remaining");
+}
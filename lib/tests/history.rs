@@ -0,0 +1,103 @@
+use std::{
+  env, fs,
+  path::PathBuf,
+  process, thread,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use libglacierdisk::{
+  history::{HistoryStore, RetentionPolicy},
+  snapshot::DiskSnapshot,
+};
+
+fn store(name: &str) -> (PathBuf, HistoryStore) {
+  let dir = env::temp_dir().join(format!("glacierdisk-history-{name}-{}", process::id()));
+  fs::remove_dir_all(&dir).unwrap_or_default();
+
+  // Keep everything, so compacting only rewrites the files
+  let retention = RetentionPolicy {
+    max_age: None,
+    downsample_after: None,
+    ..Default::default()
+  };
+  let store = HistoryStore::with_retention(&dir, retention).unwrap();
+
+  (dir, store)
+}
+
+fn snapshot(id: &str, timestamp: u64) -> DiskSnapshot {
+  DiskSnapshot {
+    id: id.to_string(),
+    path: PathBuf::from("/dev/sda"),
+    timestamp,
+    ..Default::default()
+  }
+}
+
+#[test]
+fn ids_get_their_own_files() {
+  let (dir, store) = store("ids");
+  let ids = [
+    "a/b",
+    "a_b",
+    "a%2Fb",
+    "/dev/sda",
+    "_dev_sda",
+    "Samsung SSD_S6",
+  ];
+
+  for (i, id) in ids.iter().enumerate() {
+    store.record(&snapshot(id, i as u64)).unwrap();
+  }
+
+  for (i, id) in ids.iter().enumerate() {
+    let history = store.query(id, ..).unwrap();
+    assert_eq!(history.len(), 1, "{id}");
+    assert_eq!(history[0].timestamp, i as u64);
+  }
+
+  let mut disks = store.disks().unwrap();
+  disks.sort();
+  let mut expected = ids.map(String::from).to_vec();
+  expected.sort();
+  assert_eq!(disks, expected);
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn compact_while_recording() {
+  let (dir, store) = store("compact");
+  let base = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_secs();
+  store.record(&snapshot("disk", base - 1)).unwrap();
+
+  // Compaction renames a new file over the old one, which mustn't lose anything appended to the old one meanwhile
+  thread::scope(|scope| {
+    let writers = (0..4)
+      .map(|writer| {
+        let store = &store;
+        scope.spawn(move || {
+          for i in 0..1000 {
+            store
+              .record(&snapshot("disk", base + writer * 1000 + i))
+              .unwrap();
+          }
+        })
+      })
+      .collect::<Vec<_>>();
+
+    while !writers.iter().all(|writer| writer.is_finished()) {
+      store.compact_disk("disk").unwrap();
+    }
+  });
+
+  store.compact().unwrap();
+  let history = store.query("disk", ..).unwrap();
+  assert_eq!(history.len(), 4001);
+  assert!(history.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+
+  fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,173 @@
+use std::{path::PathBuf, time::Duration};
+
+use libglacierdisk::md::{parse_mdstat, MdArray, MdRole, MdSync};
+
+fn member(array: &MdArray, name: &str) -> (MdRole, Vec<String>) {
+  let member = array
+    .members
+    .iter()
+    .find(|member| member.name == name)
+    .unwrap_or_else(|| panic!("{name} isn't in {}", array.name));
+
+  (member.role, member.state.clone())
+}
+
+fn flags(flags: &[&str]) -> Vec<String> {
+  flags.iter().map(|flag| flag.to_string()).collect()
+}
+
+#[test]
+fn degraded() {
+  let arrays = parse_mdstat(
+    "Personalities : [raid1] [raid6] [raid5] [raid4]
+md0 : active raid1 sdb1[1](F) sda1[0]
+      1953382464 blocks super 1.2 [2/1] [U_]
+      bitmap: 3/15 pages [12KB], 65536KB chunk
+
+unused devices: <none>
+",
+  );
+
+  assert_eq!(arrays.len(), 1);
+  let array = &arrays[0];
+  assert_eq!(array.name, "md0");
+  assert_eq!(array.path, PathBuf::from("/dev/md0"));
+  assert_eq!(array.level.as_deref(), Some("raid1"));
+  assert_eq!(array.state, "active");
+  assert_eq!((array.raid_disks, array.degraded), (2, 1));
+  assert_eq!(array.sync, None);
+  assert!(array.is_degraded());
+
+  // The working member first
+  let names = array
+    .members
+    .iter()
+    .map(|m| m.name.as_str())
+    .collect::<Vec<&str>>();
+  assert_eq!(names, vec!["sda1", "sdb1"]);
+  assert_eq!(
+    member(array, "sda1"),
+    (MdRole::Active(0), flags(&["in_sync"]))
+  );
+  assert_eq!(member(array, "sdb1"), (MdRole::None, flags(&["faulty"])));
+  assert!(array.members[1].is_faulty());
+  assert_eq!(array.members[1].path, PathBuf::from("/dev/sdb1"));
+
+  assert!(array.contains("/dev/sdb1"));
+  assert!(!array.contains("/dev/sdc1"));
+}
+
+#[test]
+fn recovery() {
+  let arrays = parse_mdstat(
+    "Personalities : [raid6] [raid5] [raid4]
+md1 : active raid5 sdd1[3] sdc1[1] sdb1[0]
+      1953260544 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/2] [UU_]
+      [=>...................]  recovery =  8.1% (79178624/976630272) finish=87.4min speed=171093K/sec
+      bitmap: 0/8 pages [0KB], 65536KB chunk
+
+md2 : active raid1 sdf1[1] sde1[0]
+      976630464 blocks super 1.2 [2/2] [UU]
+      [===>.................]  check = 15.6% (152369152/976630464) finish=71.2min speed=192851K/sec
+
+unused devices: <none>
+",
+  );
+
+  assert_eq!(arrays.len(), 2);
+
+  let recovering = &arrays[0];
+  assert_eq!(recovering.level.as_deref(), Some("raid5"));
+  assert_eq!((recovering.raid_disks, recovering.degraded), (3, 1));
+  assert_eq!(
+    recovering.sync,
+    Some(MdSync {
+      // Named as in sysfs, and in 512 byte sectors
+      action: "recover".into(),
+      done: 79178624 * 2,
+      total: 976630272 * 2,
+      speed: Some(171093 * 1024),
+    })
+  );
+  let sync = recovering.sync.as_ref().unwrap();
+  assert!((sync.progress() - 8.1).abs() < 0.05);
+  // 87.4 minutes, give or take
+  let remaining = sync.remaining().unwrap();
+  assert!(remaining > Duration::from_secs(87 * 60) && remaining < Duration::from_secs(88 * 60));
+
+  let checking = &arrays[1];
+  assert_eq!(checking.name, "md2");
+  assert_eq!(checking.degraded, 0);
+  assert!(!checking.is_degraded());
+  assert_eq!(
+    checking.sync.as_ref().map(|s| s.action.as_str()),
+    Some("check")
+  );
+}
+
+#[test]
+fn inactive() {
+  let arrays = parse_mdstat(
+    "Personalities :
+md127 : inactive sdc[1](S) sdb[0](S)
+      3906764976 blocks super 1.2
+
+unused devices: <none>
+",
+  );
+
+  assert_eq!(arrays.len(), 1);
+  let array = &arrays[0];
+  assert_eq!(array.name, "md127");
+  assert_eq!(array.state, "inactive");
+  assert_eq!(array.level, None);
+  assert_eq!((array.raid_disks, array.degraded), (0, 0));
+  assert_eq!(member(array, "sdb"), (MdRole::Spare, flags(&["spare"])));
+  assert_eq!(member(array, "sdc"), (MdRole::Spare, flags(&["spare"])));
+}
+
+#[test]
+fn read_only() {
+  let arrays = parse_mdstat(
+    "Personalities : [raid1]
+md126 : active (auto-read-only) raid1 sdh1[2](S) sdg1[1](W) sdf1[0]
+      976630464 blocks super 1.2 [2/2] [UU]
+        resync=PENDING
+
+md125 : active (read-only) raid1 sdj1[1] sdi1[0]
+      976630464 blocks super 1.2 [2/2] [UU]
+
+unused devices: <none>
+",
+  );
+
+  assert_eq!(arrays.len(), 2);
+
+  let auto = &arrays[0];
+  assert_eq!(auto.state, "active");
+  assert_eq!(auto.level.as_deref(), Some("raid1"));
+  assert_eq!((auto.raid_disks, auto.degraded), (2, 0));
+  // A pending resync isn't running yet
+  assert_eq!(auto.sync, None);
+  assert_eq!(
+    member(auto, "sdf1"),
+    (MdRole::Active(0), flags(&["in_sync"]))
+  );
+  assert_eq!(
+    member(auto, "sdg1"),
+    (MdRole::Active(1), flags(&["in_sync", "write_mostly"]))
+  );
+  assert_eq!(member(auto, "sdh1"), (MdRole::Spare, flags(&["spare"])));
+  assert_eq!(auto.members.last().unwrap().name, "sdh1");
+
+  let read_only = &arrays[1];
+  assert_eq!(read_only.name, "md125");
+  assert_eq!(read_only.level.as_deref(), Some("raid1"));
+  assert_eq!(read_only.members.len(), 2);
+}
+
+#[test]
+fn nothing() {
+  assert!(parse_mdstat("").is_empty());
+  assert!(parse_mdstat("Personalities :\nunused devices: <none>\n").is_empty());
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use libglacierdisk::{metrics::render, snapshot::DiskSnapshot};
+
+fn temperature(value: f64) -> Option<String> {
+  render(&[DiskSnapshot {
+    path: PathBuf::from("/dev/sda"),
+    temperature: Some(value),
+    ..Default::default()
+  }])
+  .lines()
+  .find(|line| line.starts_with("glacierdisk_temperature_celsius{"))
+  .and_then(|line| line.rsplit_once(' '))
+  .map(|(_, value)| value.to_string())
+}
+
+#[test]
+fn values() {
+  assert_eq!(temperature(41.), Some("41".into()));
+  assert_eq!(temperature(41.5), Some("41.5".into()));
+  assert_eq!(temperature(-3.25), Some("-3.25".into()));
+}
+
+#[test]
+fn special_values() {
+  assert_eq!(temperature(f64::INFINITY), Some("+Inf".into()));
+  assert_eq!(temperature(f64::NEG_INFINITY), Some("-Inf".into()));
+  assert_eq!(temperature(f64::NAN), Some("NaN".into()));
+}
+
+#[test]
+fn labels() {
+  let out = render(&[DiskSnapshot {
+    id: "wwn-0x5000c500a1b2c3d4".into(),
+    path: PathBuf::from("/dev/sda"),
+    model: Some("Disk \"Pro\"\\2".into()),
+    ..Default::default()
+  }]);
+
+  assert!(out.contains("# TYPE glacierdisk_info gauge\n"));
+  assert!(out.contains(
+    r#"glacierdisk_info{device="/dev/sda",model="Disk \"Pro\"\\2",serial="",kind="",id="wwn-0x5000c500a1b2c3d4"} 1"#
+  ));
+}
@@ -0,0 +1,110 @@
+use std::path::{Path, PathBuf};
+
+use libglacierdisk::mount::{parse_mountinfo, Mount};
+
+#[test]
+fn plain() {
+  let mounts = parse_mountinfo(
+    "22 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw,errors=remount-ro
+23 22 0:21 / /proc rw,nosuid,nodev,noexec,relatime shared:12 - proc proc rw
+",
+  );
+
+  assert_eq!(
+    mounts,
+    vec![
+      Mount {
+        mount_point: PathBuf::from("/"),
+        fs_type: "ext4".into(),
+        options: vec!["rw".into(), "relatime".into()],
+        super_options: vec!["rw".into(), "errors=remount-ro".into()],
+        root: PathBuf::from("/"),
+        source: "/dev/sda2".into(),
+        device: Some(PathBuf::from("/dev/sda2")),
+        device_number: (8, 2),
+      },
+      Mount {
+        mount_point: PathBuf::from("/proc"),
+        fs_type: "proc".into(),
+        options: ["rw", "nosuid", "nodev", "noexec", "relatime"]
+          .map(String::from)
+          .into(),
+        super_options: vec!["rw".into()],
+        root: PathBuf::from("/"),
+        source: "proc".into(),
+        device: None,
+        device_number: (0, 21),
+      },
+    ]
+  );
+  assert!(!mounts[0].is_read_only());
+  assert_eq!(mounts[0].subvolume(), None);
+}
+
+#[test]
+fn escaped() {
+  let mounts = parse_mountinfo(
+    r"40 22 8:17 / /media/My\040Disk rw,relatime - vfat /dev/sdb1 rw,fmask=0022
+41 22 8:33 /back\134slash /mnt/tab\011and\012newline rw - ext4 /dev/disk/by-label/a\040b rw
+42 22 8:49 / /mnt/not\04escaped rw - ext4 /dev/sdd1 rw
+",
+  );
+
+  assert_eq!(mounts.len(), 3);
+  assert_eq!(mounts[0].mount_point, Path::new("/media/My Disk"));
+  assert_eq!(mounts[1].root, Path::new(r"/back\slash"));
+  assert_eq!(mounts[1].mount_point, Path::new("/mnt/tab\tand\nnewline"));
+  assert_eq!(mounts[1].source, "/dev/disk/by-label/a b");
+  assert_eq!(
+    mounts[1].device,
+    Some(PathBuf::from("/dev/disk/by-label/a b"))
+  );
+  // Too short to be an escape, so left alone
+  assert_eq!(mounts[2].mount_point, Path::new(r"/mnt/not\04escaped"));
+}
+
+#[test]
+fn bind_mount() {
+  let mounts = parse_mountinfo(
+    "22 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw
+50 22 8:2 /srv/data /var/lib/data ro,relatime shared:1 master:3 - ext4 /dev/sda2 rw
+",
+  );
+
+  // Same filesystem, different directory of it, and read-only where it's bound to
+  let bind = &mounts[1];
+  assert_eq!(bind.device_number, mounts[0].device_number);
+  assert_eq!(bind.root, Path::new("/srv/data"));
+  assert_eq!(bind.mount_point, Path::new("/var/lib/data"));
+  assert!(bind.is_read_only());
+  assert!(!mounts[0].is_read_only());
+}
+
+#[test]
+fn btrfs_subvolumes() {
+  let mounts = parse_mountinfo(
+    "30 1 0:27 /@ / rw,relatime shared:1 - btrfs /dev/nvme0n1p2 rw,ssd,space_cache=v2,subvolid=256,subvol=/@
+31 30 0:27 /@home /home rw,relatime shared:2 - btrfs /dev/nvme0n1p2 rw,ssd,space_cache=v2,subvolid=257,subvol=/@home
+",
+  );
+
+  assert_eq!(mounts.len(), 2);
+  // An anonymous device number, so the source is the only way back to the disk
+  assert_eq!(mounts[0].device_number, (0, 27));
+  assert_eq!(mounts[0].device, Some(PathBuf::from("/dev/nvme0n1p2")));
+  assert_eq!(mounts[0].subvolume(), Some("/@"));
+  assert_eq!(mounts[1].root, Path::new("/@home"));
+  assert_eq!(mounts[1].subvolume(), Some("/@home"));
+}
+
+#[test]
+fn malformed() {
+  assert!(parse_mountinfo("").is_empty());
+  // No separator, and a bad device number
+  assert!(parse_mountinfo(
+    "22 1 8:2 / / rw,relatime ext4 /dev/sda2 rw
+22 1 x:2 / / rw,relatime - ext4 /dev/sda2 rw
+"
+  )
+  .is_empty());
+}
@@ -0,0 +1,250 @@
+use std::io::Cursor;
+
+use libglacierdisk::{
+  filesystem::FilesystemKind,
+  partition::{PartitionTable, PartitionTableKind},
+};
+
+const LINUX_FILESYSTEM: [u8; 16] = [
+  0xaf, 0x3d, 0xc6, 0x0f, 0x83, 0x84, 0x72, 0x47, 0x8e, 0x79, 0x3d, 0x69, 0xd8, 0x47, 0x7d, 0xe4,
+];
+const EFI_SYSTEM: [u8; 16] = [
+  0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b,
+];
+
+fn put(image: &mut [u8], at: usize, bytes: &[u8]) {
+  image[at..at + bytes.len()].copy_from_slice(bytes);
+}
+
+/// Fill in an MBR or EBR with up to four (type, first sector, sector count) entries
+fn put_mbr(image: &mut [u8], at: usize, entries: &[(u8, u32, u32)]) {
+  for (i, (kind, start, size)) in entries.iter().enumerate() {
+    let entry = at + 446 + i * 16;
+    image[entry + 4] = *kind;
+    put(image, entry + 8, &start.to_le_bytes());
+    put(image, entry + 12, &size.to_le_bytes());
+  }
+
+  put(image, at + 510, &[0x55, 0xaa]);
+}
+
+/// A protective MBR and a GPT header at LBA 1, with the entries at LBA 2
+fn gpt_image(sector_size: usize, count: u32, entry_size: u32) -> Vec<u8> {
+  let mut image = vec![0; sector_size * 64];
+  put_mbr(&mut image, 0, &[(0xee, 1, u32::MAX)]);
+
+  let header = sector_size;
+  put(&mut image, header, b"EFI PART");
+  put(&mut image, header + 56, &[0x11; 16]);
+  put(&mut image, header + 72, &2u64.to_le_bytes());
+  put(&mut image, header + 80, &count.to_le_bytes());
+  put(&mut image, header + 84, &entry_size.to_le_bytes());
+  image
+}
+
+fn put_gpt_entry(
+  image: &mut [u8],
+  at: usize,
+  type_guid: [u8; 16],
+  first: u64,
+  last: u64,
+  name: &str,
+) {
+  put(image, at, &type_guid);
+  put(image, at + 16, &[0x22; 16]);
+  put(image, at + 32, &first.to_le_bytes());
+  put(image, at + 40, &last.to_le_bytes());
+
+  let name = name
+    .encode_utf16()
+    .flat_map(|unit| unit.to_le_bytes())
+    .collect::<Vec<u8>>();
+  put(image, at + 56, &name);
+}
+
+fn read(image: Vec<u8>, sector_size: u64) -> Option<PartitionTable> {
+  PartitionTable::read(&mut Cursor::new(image), sector_size).unwrap()
+}
+
+#[test]
+fn gpt() {
+  let mut image = gpt_image(512, 128, 128);
+  put_gpt_entry(&mut image, 1024, EFI_SYSTEM, 34, 40, "EFI");
+  put_gpt_entry(&mut image, 1024 + 128, LINUX_FILESYSTEM, 48, 63, "root");
+
+  // An ext4 superblock in the second partition
+  let ext = 48 * 512 + 1024;
+  put(&mut image, ext + 56, &0xef53u16.to_le_bytes());
+  put(&mut image, ext + 96, &0x40u32.to_le_bytes());
+
+  let table = read(image, 512).unwrap();
+  assert_eq!(table.kind, PartitionTableKind::Gpt);
+  assert_eq!(
+    table.id.as_deref(),
+    Some("11111111-1111-1111-1111-111111111111")
+  );
+  assert_eq!(table.partitions.len(), 2);
+
+  let efi = &table.partitions[0];
+  assert_eq!((efi.number, efi.start, efi.size), (1, 34, 7));
+  assert_eq!(
+    efi.type_guid.as_deref(),
+    Some("c12a7328-f81f-11d2-ba4b-00a0c93ec93b")
+  );
+  assert_eq!(efi.type_name(), Some("EFI System"));
+  assert_eq!(efi.name.as_deref(), Some("EFI"));
+  assert_eq!(efi.filesystem, None);
+
+  let root = &table.partitions[1];
+  assert_eq!((root.number, root.start, root.size), (2, 48, 16));
+  assert_eq!(root.type_name(), Some("Linux filesystem"));
+  assert_eq!(root.name.as_deref(), Some("root"));
+  assert_eq!(
+    root.filesystem.as_ref().map(|fs| fs.kind),
+    Some(FilesystemKind::Ext4)
+  );
+}
+
+#[test]
+fn gpt_4k_sectors() {
+  let mut image = gpt_image(4096, 4, 128);
+  put_gpt_entry(&mut image, 2 * 4096, LINUX_FILESYSTEM, 6, 9, "data");
+
+  // Reported in 512 byte sectors, whatever the disk uses
+  let table = read(image, 4096).unwrap();
+  let data = &table.partitions[0];
+  assert_eq!((data.start, data.size), (48, 32));
+  assert_eq!(data.size_bytes(), 4 * 4096);
+}
+
+#[test]
+fn mbr_with_logical_partitions() {
+  let mut image = vec![0; 512 * 64];
+  put_mbr(&mut image, 0, &[(0x83, 2, 8), (0x05, 16, 40)]);
+  image[440..444].copy_from_slice(&0xdeadbeefu32.to_le_bytes());
+
+  // Logical partitions are relative to their EBR, links to the next EBR to the extended partition
+  put_mbr(&mut image, 16 * 512, &[(0x83, 1, 7), (0x05, 16, 16)]);
+  put_mbr(&mut image, 32 * 512, &[(0x82, 2, 6)]);
+
+  let table = read(image, 512).unwrap();
+  assert_eq!(table.kind, PartitionTableKind::Mbr);
+  assert_eq!(table.id.as_deref(), Some("deadbeef"));
+
+  let partitions = table
+    .partitions
+    .iter()
+    .map(|p| (p.number, p.start, p.size, p.mbr_type.unwrap()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    partitions,
+    vec![
+      (1, 2, 8, 0x83),
+      (2, 16, 40, 0x05),
+      (5, 17, 7, 0x83),
+      (6, 34, 6, 0x82),
+    ]
+  );
+  assert_eq!(table.partitions[3].type_name(), Some("Linux swap"));
+}
+
+#[test]
+fn looping_ebr_chain() {
+  let mut image = vec![0; 512 * 64];
+  put_mbr(&mut image, 0, &[(0x05, 16, 40)]);
+  // Links back to the first EBR, which links back to this one
+  put_mbr(&mut image, 16 * 512, &[(0x83, 1, 7), (0x05, 16, 16)]);
+  put_mbr(
+    &mut image,
+    32 * 512,
+    &[(0x83, 1, 7), (0x05, 0xffff_fff0, 16)],
+  );
+
+  let table = read(image, 512).unwrap();
+  assert!(table.partitions.len() <= 130);
+}
+
+#[test]
+fn no_table() {
+  assert_eq!(read(vec![], 512), None);
+  assert_eq!(read(vec![0; 4096], 512), None);
+
+  // An unpartitioned FAT32 stick also ends its first sector in 0x55aa
+  let mut fat = vec![0; 4096];
+  put(&mut fat, 82, b"FAT32   ");
+  put(&mut fat, 510, &[0x55, 0xaa]);
+  assert_eq!(read(fat, 512), None);
+}
+
+#[test]
+fn truncated() {
+  // A protective MBR, but the GPT header is missing, so only the MBR is left
+  let mut image = vec![0; 512];
+  put_mbr(&mut image, 0, &[(0xee, 1, u32::MAX)]);
+  let table = read(image, 512).unwrap();
+  assert_eq!(table.kind, PartitionTableKind::Mbr);
+  assert_eq!(table.partitions[0].type_name(), Some("GPT protective"));
+
+  // The entries are cut off after the first
+  let mut image = gpt_image(512, 128, 128);
+  put_gpt_entry(&mut image, 1024, LINUX_FILESYSTEM, 34, 63, "root");
+  image.truncate(1024 + 200);
+  assert_eq!(read(image, 512).unwrap().partitions.len(), 1);
+}
+
+#[test]
+fn corrupt_gpt_header() {
+  let read_err = |image: Vec<u8>| PartitionTable::read(&mut Cursor::new(image), 512).is_err();
+
+  // Entry sizes must be 128 * 2^n, and no bigger than 4 KiB
+  assert!(read_err(gpt_image(512, 128, 0)));
+  assert!(read_err(gpt_image(512, 128, 129)));
+  assert!(read_err(gpt_image(512, 128, 8192)));
+  // Would be a 4 TiB allocation
+  assert!(read_err(gpt_image(512, 1024, u32::MAX)));
+  assert!(read_err(gpt_image(512, 1025, 128)));
+
+  // Entries at an LBA that overflows when converted to bytes
+  let mut image = gpt_image(512, 128, 128);
+  put(&mut image, 512 + 72, &u64::MAX.to_le_bytes());
+  assert!(read_err(image));
+}
+
+#[test]
+fn corrupt_gpt_entry() {
+  let mut image = gpt_image(512, 4, 128);
+  put_gpt_entry(&mut image, 1024, LINUX_FILESYSTEM, 34, u64::MAX, "end");
+  assert!(PartitionTable::read(&mut Cursor::new(image), 512).is_err());
+
+  // Fine in 512 byte sectors, but overflows once 4 KiB sectors are converted
+  let mut image = gpt_image(4096, 4, 128);
+  put_gpt_entry(
+    &mut image,
+    2 * 4096,
+    LINUX_FILESYSTEM,
+    u64::MAX / 4,
+    u64::MAX / 4,
+    "big",
+  );
+  assert!(PartitionTable::read(&mut Cursor::new(image), 4096).is_err());
+
+  // A partition past the end of any real disk still doesn't overflow when probing for a filesystem
+  let mut image = gpt_image(512, 4, 128);
+  put_gpt_entry(
+    &mut image,
+    1024,
+    LINUX_FILESYSTEM,
+    u64::MAX / 512 + 1,
+    u64::MAX / 512 + 1,
+    "far",
+  );
+  let table = read(image, 512).unwrap();
+  assert_eq!(table.partitions[0].filesystem, None);
+}
+
+#[test]
+fn invalid_sector_size() {
+  for sector_size in [0, 1, 513, 1 << 40] {
+    assert!(PartitionTable::read(&mut Cursor::new(vec![0; 1024]), sector_size).is_err());
+  }
+}
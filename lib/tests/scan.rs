@@ -0,0 +1,120 @@
+use std::{env, fs, io, process};
+
+use libglacierdisk::scan::{ScanConfig, ScanSource, SurfaceScan};
+
+/// An in-memory "disk" where some sectors always fail to read
+struct FaultyImage {
+  size: u64,
+  bad_lbas: Vec<u64>,
+}
+
+impl ScanSource for FaultyImage {
+  fn size(&mut self) -> io::Result<u64> {
+    Ok(self.size)
+  }
+
+  fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+    let first = offset / 512;
+    let last = (offset + buf.len() as u64).div_ceil(512);
+
+    if self.bad_lbas.iter().any(|lba| (first..last).contains(lba)) {
+      return Err(io::Error::other("Injected read error"));
+    }
+
+    buf.fill(0);
+    Ok(())
+  }
+}
+
+fn scan_faulty(bad_lbas: Vec<u64>, config: ScanConfig) -> Vec<u64> {
+  let image = FaultyImage {
+    size: 64 * 1024 * 1024,
+    bad_lbas,
+  };
+
+  SurfaceScan::with_source(image, config)
+    .run(|_| {})
+    .unwrap()
+    .bad_sectors
+    .iter()
+    .map(|b| b.lba)
+    .collect()
+}
+
+#[test]
+fn finds_injected_bad_sectors() {
+  let config = ScanConfig {
+    sector_size: Some(512),
+    ..Default::default()
+  };
+
+  // Including neighbours, and sectors at the start and end of a chunk
+  let bad = vec![0, 7, 2047, 2048, 2049, 100_000, 131_071];
+  assert_eq!(scan_faulty(bad.clone(), config), bad);
+}
+
+#[test]
+fn bad_sectors_are_in_sector_size_units() {
+  let config = ScanConfig {
+    sector_size: Some(4096),
+    ..Default::default()
+  };
+
+  // Both 512 byte sectors are in the same 4 KiB sector
+  assert_eq!(scan_faulty(vec![16, 17, 4000], config), vec![2, 500]);
+}
+
+#[test]
+fn scans_only_the_range() {
+  let config = ScanConfig {
+    sector_size: Some(512),
+    // Not sector aligned, so rounded down to LBA 1000
+    start: 1000 * 512 + 100,
+    end: Some(2000 * 512),
+    ..Default::default()
+  };
+
+  assert_eq!(
+    scan_faulty(vec![999, 1000, 1999, 2000], config),
+    vec![1000, 1999]
+  );
+}
+
+#[test]
+fn cancelled() {
+  let image = FaultyImage {
+    size: 64 * 1024 * 1024,
+    bad_lbas: vec![],
+  };
+  let mut scan = SurfaceScan::with_source(image, ScanConfig::default());
+  let control = scan.control();
+
+  let result = scan
+    .run(|progress| {
+      if progress.bytes_scanned >= 4 * 1024 * 1024 {
+        control.cancel();
+      }
+    })
+    .unwrap();
+
+  assert!(result.cancelled);
+  assert_eq!(result.bytes_scanned, 4 * 1024 * 1024);
+}
+
+#[test]
+fn image_file() {
+  let path = env::temp_dir().join(format!("glacierdisk-scan-{}.img", process::id()));
+  fs::write(&path, vec![0x5a; 3 * 1024 * 1024 + 4096]).unwrap();
+
+  let config = ScanConfig {
+    sector_size: Some(4096),
+    ..Default::default()
+  };
+  let result = SurfaceScan::new(&path, config).and_then(|mut scan| scan.run(|_| {}));
+  fs::remove_file(&path).unwrap();
+
+  let result = result.unwrap();
+  assert_eq!(result.bytes_scanned, 3 * 1024 * 1024 + 4096);
+  assert!(result.bad_sectors.is_empty());
+  assert!(!result.cancelled);
+}
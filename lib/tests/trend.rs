@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+use libglacierdisk::{
+  nvme::NvmeSmartLog,
+  snapshot::{DiskSnapshot, SnapshotAttribute},
+  trend::{forecast, forecast_all, Fit, TrendMetric, TrendModel},
+};
+
+const DAY: u64 = 60 * 60 * 24;
+
+/// One snapshot a day, with these reallocated sector counts
+fn reallocated(counts: &[u64]) -> Vec<DiskSnapshot> {
+  counts
+    .iter()
+    .enumerate()
+    .map(|(day, count)| DiskSnapshot {
+      timestamp: 1_700_000_000 + day as u64 * DAY,
+      attributes: vec![SnapshotAttribute {
+        id: 5,
+        raw: *count,
+        ..Default::default()
+      }],
+      ..Default::default()
+    })
+    .collect()
+}
+
+/// One snapshot every `days`, with these NVMe percentage used values
+fn wear(days: u64, used: &[u8]) -> Vec<DiskSnapshot> {
+  used
+    .iter()
+    .enumerate()
+    .map(|(i, used)| DiskSnapshot {
+      timestamp: i as u64 * days * DAY,
+      nvme: Some(NvmeSmartLog {
+        percentage_used: *used,
+        ..Default::default()
+      }),
+      ..Default::default()
+    })
+    .collect()
+}
+
+fn close(a: f64, b: f64) -> bool {
+  (a - b).abs() < 1e-6
+}
+
+#[test]
+fn fit() {
+  let line = Fit::linear(&[(0., 1.), (1., 3.), (2., 5.), (3., 7.)]).unwrap();
+  assert!(close(line.slope, 2.) && close(line.intercept, 1.) && close(line.r_squared, 1.));
+
+  let flat = Fit::linear(&[(0., 4.), (1., 4.), (2., 4.)]).unwrap();
+  assert!(close(flat.slope, 0.) && close(flat.intercept, 4.) && close(flat.r_squared, 1.));
+
+  let noisy = Fit::linear(&[(0., 0.), (1., 2.), (2., 1.), (3., 3.)]).unwrap();
+  assert!(close(noisy.slope, 0.8));
+  assert!(noisy.r_squared > 0. && noisy.r_squared < 1.);
+
+  // Not enough to fit a line to
+  assert_eq!(Fit::linear(&[]), None);
+  assert_eq!(Fit::linear(&[(1., 1.)]), None);
+  assert_eq!(Fit::linear(&[(1., 1.), (1., 2.), (1., 3.)]), None);
+}
+
+#[test]
+fn not_enough_history() {
+  assert_eq!(
+    forecast(&reallocated(&[1, 2]), TrendMetric::Reallocated, None),
+    None
+  );
+  // Other attributes don't count
+  assert_eq!(
+    forecast(&reallocated(&[1, 2, 3]), TrendMetric::Pending, None),
+    None
+  );
+}
+
+#[test]
+fn flat() {
+  let forecast = forecast(
+    &reallocated(&[8, 8, 8, 8, 8]),
+    TrendMetric::Reallocated,
+    Some(100.),
+  )
+  .unwrap();
+
+  assert_eq!(forecast.model, TrendModel::Linear);
+  assert_eq!(forecast.current, 8.);
+  assert!(close(forecast.rate_per_day, 0.));
+  assert!(!forecast.degrading());
+  assert!(!forecast.accelerating);
+  assert_eq!(forecast.eta, None);
+  assert_eq!(forecast.doubling_time, None);
+  assert_eq!(
+    forecast.to_string(),
+    "reallocated sectors stable at 8 (50% confidence)"
+  );
+}
+
+#[test]
+fn rising() {
+  let forecast = forecast(
+    &reallocated(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
+    TrendMetric::Reallocated,
+    Some(100.),
+  )
+  .unwrap();
+
+  assert_eq!(forecast.model, TrendModel::Linear);
+  assert!(close(forecast.rate_per_day, 1.));
+  assert!(forecast.degrading());
+  assert!(!forecast.accelerating);
+  assert!(close(forecast.confidence, 1.));
+  // 9 now, 100 in 91 days
+  assert_eq!(forecast.eta, Some(Duration::from_secs(91 * DAY)));
+  assert_eq!(
+    forecast.to_string(),
+    "reallocated sectors changing by +1.00/day, hits 100 in ~91 days (100% confidence)"
+  );
+}
+
+#[test]
+fn doubling() {
+  let forecast = forecast(
+    &reallocated(&[1, 2, 4, 8, 16, 32]),
+    TrendMetric::Reallocated,
+    Some(1024.),
+  )
+  .unwrap();
+
+  assert_eq!(forecast.model, TrendModel::Exponential);
+  assert!(forecast.accelerating);
+  let doubling = forecast.doubling_time.unwrap().as_secs_f64() / DAY as f64;
+  assert!(close(doubling, 1.));
+  // 32 now, 1024 after 5 more doublings
+  let eta = forecast.eta.unwrap().as_secs_f64() / DAY as f64;
+  assert!(close(eta, 5.));
+  assert!(forecast
+    .to_string()
+    .starts_with("reallocated sectors doubling daily"));
+}
+
+#[test]
+fn accelerating() {
+  // Steady, then much faster, but not a clean exponential
+  let forecast = forecast(
+    &reallocated(&[0, 1, 2, 3, 4, 10, 16, 22, 28]),
+    TrendMetric::Reallocated,
+    None,
+  )
+  .unwrap();
+
+  assert!(forecast.accelerating);
+  assert_eq!(forecast.eta, None);
+}
+
+#[test]
+fn wearing_out() {
+  // 1% every 10 days, so 10% left in 900 days from now
+  let forecast = forecast(
+    &wear(10, &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]),
+    TrendMetric::WearRemaining,
+    Some(10.),
+  )
+  .unwrap();
+
+  assert_eq!(forecast.model, TrendModel::Linear);
+  assert_eq!(forecast.current, 90.);
+  assert!(close(forecast.rate_per_day, -0.1));
+  assert!(forecast.degrading());
+  assert_eq!(forecast.doubling_time, None);
+  let eta = forecast.eta.unwrap().as_secs_f64() / DAY as f64;
+  assert!(close(eta, 800.));
+}
+
+#[test]
+fn threshold_behind() {
+  // Already past the threshold, so there's nothing to forecast
+  let forecast = forecast(
+    &reallocated(&[10, 11, 12]),
+    TrendMetric::Reallocated,
+    Some(5.),
+  )
+  .unwrap();
+  assert_eq!(forecast.eta, None);
+}
+
+#[test]
+fn too_far_out() {
+  // One sector over two years, against a threshold that takes far longer than a Duration can hold to reach
+  let mut history = reallocated(&[0, 0, 1]);
+  history[1].timestamp += 365 * DAY;
+  history[2].timestamp += 730 * DAY;
+
+  let far = forecast(&history, TrendMetric::Reallocated, Some(1e18)).unwrap();
+  assert!(far.degrading());
+  assert_eq!(far.eta, None);
+}
+
+#[test]
+fn unsorted() {
+  let mut history = reallocated(&[0, 1, 2, 3, 4]);
+  history.reverse();
+
+  let sorted = forecast(&history, TrendMetric::Reallocated, None).unwrap();
+  assert_eq!(sorted.current, 4.);
+  assert!(close(sorted.rate_per_day, 1.));
+}
+
+#[test]
+fn all() {
+  let mut history = reallocated(&[0, 1, 2, 3, 4]);
+  for (snapshot, pending) in history.iter_mut().zip([3, 3, 3, 3, 3]) {
+    snapshot.attributes.push(SnapshotAttribute {
+      id: 197,
+      raw: pending,
+      ..Default::default()
+    });
+  }
+
+  // Only what's getting worse
+  let forecasts = forecast_all(&history, 100., 10.);
+  assert_eq!(forecasts.len(), 1);
+  assert_eq!(forecasts[0].metric, TrendMetric::Reallocated);
+  assert_eq!(forecasts[0].threshold, Some(100.));
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
@@ -9,12 +9,33 @@ use super::{
   theme::{self, Theme},
 };
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
   #[serde(skip_serializing, skip_deserializing)]
   pub app: App,
   // Path to the theme
   pub theme: String,
+  // Rated endurance (in TBW) by drive model, since drives don't report it themselves
+  #[serde(default)]
+  pub tbw_ratings: HashMap<String, f64>,
+  // Desktop notifications when a drive's health verdict changes or it gets too hot
+  #[serde(default = "default_notifications")]
+  pub notifications: bool,
+  // Temperature (in Celsius) to notify at
+  #[serde(default = "default_temperature_alert")]
+  pub temperature_alert: f64,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      app: App::default(),
+      theme: String::new(),
+      tbw_ratings: HashMap::new(),
+      notifications: default_notifications(),
+      temperature_alert: default_temperature_alert(),
+    }
+  }
 }
 
 impl Config {
@@ -30,6 +51,14 @@ impl Config {
   }
 }
 
+fn default_notifications() -> bool {
+  true
+}
+
+fn default_temperature_alert() -> f64 {
+  55.
+}
+
 pub fn config_file(app: App) -> PathBuf {
   let cfg = dot_config();
   cfg.join(app.to_string()).join("config.toml")
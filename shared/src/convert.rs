@@ -2,11 +2,11 @@ pub fn bytes_to_readable(bytes: u64) -> String {
   if bytes < 1024 {
     format!("{} B", bytes)
   } else if bytes < 1024 * 1024 {
-    return format!("{:.2} KB", bytes as f64 / 1024.0);
+    format!("{:.2} KB", bytes as f64 / 1024.0)
   } else if bytes < 1024 * 1024 * 1024 {
-    return format!("{:.2} MB", bytes as f64 / 1024.0 / 1024.0);
+    format!("{:.2} MB", bytes as f64 / 1024.0 / 1024.0)
   } else if bytes < 1024 * 1024 * 1024 * 1024 {
-    return format!("{:.2} GB", bytes as f64 / 1024.0 / 1024.0 / 1024.0);
+    format!("{:.2} GB", bytes as f64 / 1024.0 / 1024.0 / 1024.0)
   } else {
     format!("{:.2} TB", bytes as f64 / 1024.0 / 1024.0 / 1024.0 / 1024.0)
   }
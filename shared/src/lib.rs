@@ -4,6 +4,10 @@ use uzers::User;
 
 pub mod config;
 pub mod convert;
+#[cfg(feature = "monitor")]
+pub mod monitor;
+#[cfg(feature = "monitor")]
+pub mod notify;
 pub mod root;
 pub mod theme;
 
@@ -29,11 +33,11 @@ pub fn dot_config() -> PathBuf {
 
   // If we aren't running as SUDO, it could be that we ran with pkexec
   if user.is_empty() {
-    // Get the PKEXEC_UID
+    // Get the PKEXEC_UID, or our own if we weren't escalated at all (eg. when using the monitor daemon)
     let uid = env::var("PKEXEC_UID")
-      .unwrap_or_default()
-      .parse::<u32>()
-      .unwrap_or_default();
+      .ok()
+      .and_then(|uid| uid.parse::<u32>().ok())
+      .unwrap_or(uzers::get_current_uid());
     // Then get the username
     user = uzers::get_user_by_uid(uid)
       .unwrap_or(User::new(0, "root", 0))
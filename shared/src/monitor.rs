@@ -0,0 +1,62 @@
+use std::{error::Error, io::Read, os::unix::net::UnixStream, path::Path, time::Duration};
+
+use libglacierdisk::{alert::Alert, snapshot::DiskSnapshot};
+use serde::{Deserialize, Serialize};
+
+/// Where glacierdisk-monitord listens by default
+pub const DEFAULT_SOCKET: &str = "/run/glacierdisk/monitord.sock";
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything the monitor daemon knows, as served over its socket
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct MonitorState {
+  pub version: String,
+  /// When the daemon started, in seconds since the Unix epoch
+  pub started: u64,
+  /// When disks were last polled, in seconds since the Unix epoch
+  pub updated: u64,
+  /// Seconds between polls
+  pub interval: u64,
+  /// The latest snapshot of each disk
+  pub disks: Vec<DiskSnapshot>,
+  /// Currently active alerts
+  pub alerts: Vec<Alert>,
+}
+
+impl MonitorState {
+  /// Find a disk by stable ID or path
+  pub fn disk(&self, disk: impl AsRef<str>) -> Option<&DiskSnapshot> {
+    let disk = disk.as_ref();
+    self
+      .disks
+      .iter()
+      .find(|snapshot| snapshot.id == disk || snapshot.path == Path::new(disk))
+  }
+
+  /// Active alerts for a disk, by stable ID
+  pub fn alerts_for(&self, disk_id: impl AsRef<str>) -> Vec<&Alert> {
+    self
+      .alerts
+      .iter()
+      .filter(|alert| alert.disk == disk_id.as_ref())
+      .collect()
+  }
+}
+
+/// Fetch the current state from a running monitor daemon. This doesn't need root, so the GUI can use it as a normal user.
+pub fn fetch_state(socket: impl AsRef<Path>) -> Result<MonitorState, Box<dyn Error>> {
+  let mut stream = UnixStream::connect(socket)?;
+  stream.set_read_timeout(Some(TIMEOUT))?;
+
+  // The daemon writes its state as JSON and hangs up
+  let mut contents = String::new();
+  stream.read_to_string(&mut contents)?;
+
+  Ok(serde_json::from_str(&contents)?)
+}
+
+/// Whether a monitor daemon is listening on the socket
+pub fn is_running(socket: impl AsRef<Path>) -> bool {
+  UnixStream::connect(socket).is_ok()
+}
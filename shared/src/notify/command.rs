@@ -0,0 +1,55 @@
+use std::{error::Error, process::Command};
+
+use libglacierdisk::{alert::AlertEvent, snapshot::DiskSnapshot};
+
+use super::Notifier;
+
+/// Runs a program for every event
+pub struct CommandNotifier {
+  pub command: String,
+  pub args: Vec<String>,
+}
+
+impl CommandNotifier {
+  pub fn new(command: impl AsRef<str>, args: Vec<String>) -> Self {
+    Self {
+      command: command.as_ref().to_string(),
+      args,
+    }
+  }
+}
+
+impl Notifier for CommandNotifier {
+  fn name(&self) -> &str {
+    &self.command
+  }
+
+  fn notify(&mut self, event: &AlertEvent, snapshot: &DiskSnapshot) -> Result<(), Box<dyn Error>> {
+    let alert = event.alert();
+    let kind = match event {
+      AlertEvent::Fired(_) => "fired",
+      AlertEvent::Cleared(_) => "cleared",
+    };
+
+    let status = Command::new(&self.command)
+      .args(&self.args)
+      .env("GLACIERDISK_EVENT", kind)
+      .env("GLACIERDISK_RULE", &alert.rule)
+      .env("GLACIERDISK_SEVERITY", alert.severity.to_string())
+      .env("GLACIERDISK_DISK", &alert.disk)
+      .env("GLACIERDISK_PATH", &alert.path)
+      .env("GLACIERDISK_VALUE", alert.value.to_string())
+      .env("GLACIERDISK_MESSAGE", &alert.message)
+      .env(
+        "GLACIERDISK_HEALTH",
+        snapshot.health.map(|h| h.to_string()).unwrap_or_default(),
+      )
+      .status()?;
+
+    if !status.success() {
+      return Err(format!("{} exited with {status}", self.command).into());
+    }
+
+    Ok(())
+  }
+}
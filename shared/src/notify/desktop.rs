@@ -0,0 +1,132 @@
+use std::{collections::HashMap, env, error::Error};
+
+use libglacierdisk::{
+  alert::{AlertEvent, Severity},
+  snapshot::DiskSnapshot,
+};
+use zbus::{blocking::Connection, zvariant::Value};
+
+use super::Notifier;
+
+const DESTINATION: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+const ICON: &str = "drive-harddisk";
+
+/// Shows freedesktop notifications over D-Bus (`org.freedesktop.Notifications`)
+pub struct DesktopNotifier {
+  connection: Connection,
+  pub app_name: String,
+  /// Also notify when alerts clear
+  pub show_cleared: bool,
+  /// Notification IDs by (disk, rule), so a cleared alert replaces its notification instead of adding another
+  shown: HashMap<(String, String), u32>,
+}
+
+impl DesktopNotifier {
+  pub fn new(connection: Connection) -> Self {
+    Self {
+      connection,
+      app_name: "GlacierDiskInfo".to_string(),
+      show_cleared: true,
+      shown: HashMap::new(),
+    }
+  }
+
+  /// Connect to the current session bus
+  pub fn session() -> Result<Self, Box<dyn Error>> {
+    Ok(Self::new(Connection::session()?))
+  }
+
+  /// Connect to a bus by address (e.g. `"unix:path=/run/user/1000/bus"`)
+  pub fn with_address(address: impl AsRef<str>) -> Result<Self, Box<dyn Error>> {
+    let connection = zbus::blocking::connection::Builder::address(address.as_ref())?.build()?;
+    Ok(Self::new(connection))
+  }
+
+  /// Connect to a user's session bus. This is how a service running as root reaches someone's desktop.
+  pub fn for_user(uid: u32) -> Result<Self, Box<dyn Error>> {
+    Self::with_address(format!("unix:path=/run/user/{uid}/bus"))
+  }
+
+  /// Connect to the session bus of whoever ran us, even if we were escalated with sudo or pkexec
+  pub fn for_invoking_user() -> Result<Self, Box<dyn Error>> {
+    let uid = ["SUDO_UID", "PKEXEC_UID"]
+      .iter()
+      .find_map(|var| env::var(var).ok()?.parse::<u32>().ok());
+
+    match uid {
+      Some(uid) => Self::for_user(uid),
+      None => Self::session(),
+    }
+  }
+}
+
+impl Notifier for DesktopNotifier {
+  fn name(&self) -> &str {
+    "desktop"
+  }
+
+  fn notify(&mut self, event: &AlertEvent, snapshot: &DiskSnapshot) -> Result<(), Box<dyn Error>> {
+    let alert = event.alert();
+    let key = (alert.disk.clone(), alert.rule.clone());
+    let drive = snapshot
+      .model
+      .clone()
+      .unwrap_or(snapshot.path.display().to_string());
+
+    let (summary, replaces, urgency) = match event {
+      AlertEvent::Fired(_) => (
+        format!("{}: {drive}", alert.severity),
+        0,
+        match alert.severity {
+          Severity::Info => 0u8,
+          Severity::Warning => 1,
+          Severity::Critical => 2,
+        },
+      ),
+      AlertEvent::Cleared(_) => {
+        let Some(id) = self.shown.remove(&key) else {
+          return Ok(());
+        };
+
+        if !self.show_cleared {
+          return Ok(());
+        }
+
+        (format!("Resolved: {drive}"), id, 0)
+      }
+    };
+
+    let body = format!(
+      "{} ({}): {}",
+      alert.rule,
+      alert.path.display(),
+      alert.message
+    );
+    let hints = HashMap::from([("urgency", Value::from(urgency))]);
+
+    let reply = self.connection.call_method(
+      Some(DESTINATION),
+      PATH,
+      Some(DESTINATION),
+      "Notify",
+      &(
+        self.app_name.as_str(),
+        replaces,
+        ICON,
+        summary.as_str(),
+        body.as_str(),
+        Vec::<&str>::new(),
+        hints,
+        // Default expiry
+        -1i32,
+      ),
+    )?;
+
+    if let AlertEvent::Fired(_) = event {
+      self.shown.insert(key, reply.body().deserialize::<u32>()?);
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,239 @@
+use std::{
+  collections::{BTreeMap, VecDeque},
+  error::Error,
+  fs,
+  path::PathBuf,
+  time::{Duration, Instant},
+};
+
+use lettre::{
+  message::header::ContentType, transport::smtp::authentication::Credentials, Message,
+  SmtpTransport, Transport,
+};
+use libglacierdisk::{
+  alert::{deserialize_duration, AlertEvent},
+  snapshot::DiskSnapshot,
+};
+use serde::Deserialize;
+
+use super::Notifier;
+
+const RATE_WINDOW: Duration = Duration::from_secs(60 * 60);
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How to secure the connection to the SMTP server
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Security {
+  /// Plain text. Only use this for a relay on localhost.
+  None,
+  /// Upgrade a plain connection with STARTTLS, usually on port 587
+  #[default]
+  StartTls,
+  /// Implicit TLS, usually on port 465
+  Tls,
+}
+
+/// Settings for [`EmailNotifier`], written as a `type = "email"` notifier
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct EmailConfig {
+  pub host: String,
+  /// Defaults to the usual port for `security`
+  #[serde(default)]
+  pub port: Option<u16>,
+  #[serde(default)]
+  pub security: Security,
+  #[serde(default)]
+  pub username: Option<String>,
+  #[serde(default)]
+  pub password: Option<String>,
+  /// Read the password from a file instead, so it can be kept out of the config
+  #[serde(default)]
+  pub password_file: Option<PathBuf>,
+  /// e.g. `"GlacierDisk <disks@example.com>"`
+  pub from: String,
+  pub to: Vec<String>,
+  /// Send at most this many emails an hour. Alerts over the limit are sent together once the limit allows.
+  #[serde(default)]
+  pub rate_limit: Option<usize>,
+  /// Instead of an email per alert, send everything in one email at this interval (e.g. `"1d"`)
+  #[serde(default, deserialize_with = "deserialize_duration")]
+  pub digest: Option<Duration>,
+}
+
+/// Emails alerts over SMTP
+pub struct EmailNotifier {
+  pub config: EmailConfig,
+  transport: SmtpTransport,
+  /// When recent emails were sent, for the rate limit
+  sent: VecDeque<Instant>,
+  /// Events held back for the digest or by the rate limit
+  queue: Vec<(AlertEvent, DiskSnapshot)>,
+  last_digest: Instant,
+}
+
+impl EmailNotifier {
+  pub fn new(config: EmailConfig) -> Result<Self, Box<dyn Error>> {
+    let mut builder = match config.security {
+      Security::None => SmtpTransport::builder_dangerous(&config.host),
+      Security::StartTls => SmtpTransport::starttls_relay(&config.host)?,
+      Security::Tls => SmtpTransport::relay(&config.host)?,
+    };
+
+    if let Some(port) = config.port {
+      builder = builder.port(port);
+    }
+
+    let password = match (&config.password, &config.password_file) {
+      (Some(password), _) => Some(password.clone()),
+      (None, Some(path)) => Some(fs::read_to_string(path)?.trim_end().to_string()),
+      (None, None) => None,
+    };
+
+    if let Some(username) = &config.username {
+      builder = builder.credentials(Credentials::new(
+        username.clone(),
+        password.ok_or("SMTP username is set without a password")?,
+      ));
+    }
+
+    if config.to.is_empty() {
+      return Err("Email notifier needs at least one recipient".into());
+    }
+
+    Ok(Self {
+      transport: builder.timeout(Some(TIMEOUT)).build(),
+      config,
+      sent: VecDeque::new(),
+      queue: vec![],
+      last_digest: Instant::now(),
+    })
+  }
+
+  fn can_send(&mut self) -> bool {
+    let Some(limit) = self.config.rate_limit else {
+      return true;
+    };
+
+    while self
+      .sent
+      .front()
+      .is_some_and(|sent| sent.elapsed() >= RATE_WINDOW)
+    {
+      self.sent.pop_front();
+    }
+
+    self.sent.len() < limit
+  }
+
+  fn send(&mut self, subject: impl Into<String>, body: String) -> Result<(), Box<dyn Error>> {
+    let mut message = Message::builder()
+      .from(self.config.from.parse()?)
+      .subject(subject)
+      .header(ContentType::TEXT_PLAIN);
+
+    for to in &self.config.to {
+      message = message.to(to.parse()?);
+    }
+
+    self.transport.send(&message.body(body)?)?;
+    self.sent.push_back(Instant::now());
+
+    Ok(())
+  }
+
+  /// Send everything in the queue as one email
+  fn send_queue(&mut self) -> Result<(), Box<dyn Error>> {
+    if self.queue.is_empty() {
+      return Ok(());
+    }
+
+    let mut body = String::new();
+    // Only the most recent reading of each disk is worth reporting
+    let mut disks = BTreeMap::new();
+
+    for (event, snapshot) in &self.queue {
+      body.push_str(&format!("{event}\n"));
+      disks.insert(&snapshot.id, snapshot);
+    }
+
+    for snapshot in disks.values() {
+      body.push_str(&format!("\n{}", snapshot.report()));
+    }
+
+    let subject = match self.queue.len() {
+      1 => subject(&self.queue[0].0, &self.queue[0].1),
+      n => format!("[GlacierDisk] {n} disk alerts"),
+    };
+
+    // Keep the queue until it's actually been sent, so a flaky server doesn't lose alerts
+    self.send(subject, body)?;
+    self.queue.clear();
+
+    Ok(())
+  }
+}
+
+impl Notifier for EmailNotifier {
+  fn name(&self) -> &str {
+    "email"
+  }
+
+  fn notify(&mut self, event: &AlertEvent, snapshot: &DiskSnapshot) -> Result<(), Box<dyn Error>> {
+    if self.config.digest.is_some() || !self.can_send() {
+      self.queue.push((event.clone(), snapshot.clone()));
+      return Ok(());
+    }
+
+    let sent = self.send(
+      subject(event, snapshot),
+      format!("{event}\n\n{}", snapshot.report()),
+    );
+
+    // Try again with the next flush
+    if sent.is_err() {
+      self.queue.push((event.clone(), snapshot.clone()));
+    }
+
+    sent
+  }
+
+  fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+    if self
+      .config
+      .digest
+      .is_some_and(|digest| self.last_digest.elapsed() < digest)
+    {
+      return Ok(());
+    }
+
+    if !self.queue.is_empty() {
+      if !self.can_send() {
+        return Ok(());
+      }
+
+      self.send_queue()?;
+    }
+
+    // Only once the digest is out, so a failed one is retried on the next flush rather than a whole interval later
+    self.last_digest = Instant::now();
+
+    Ok(())
+  }
+}
+
+fn subject(event: &AlertEvent, snapshot: &DiskSnapshot) -> String {
+  let alert = event.alert();
+  let drive = snapshot
+    .model
+    .clone()
+    .unwrap_or(snapshot.path.display().to_string());
+
+  match event {
+    AlertEvent::Fired(_) => format!(
+      "[GlacierDisk] {}: {} on {drive}",
+      alert.severity, alert.rule
+    ),
+    AlertEvent::Cleared(_) => format!("[GlacierDisk] Resolved: {} on {drive}", alert.rule),
+  }
+}
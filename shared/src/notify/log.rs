@@ -0,0 +1,19 @@
+use std::error::Error;
+
+use libglacierdisk::{alert::AlertEvent, snapshot::DiskSnapshot};
+
+use super::Notifier;
+
+/// Prints events to stdout
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+  fn name(&self) -> &str {
+    "log"
+  }
+
+  fn notify(&mut self, event: &AlertEvent, _snapshot: &DiskSnapshot) -> Result<(), Box<dyn Error>> {
+    println!("{event}");
+    Ok(())
+  }
+}
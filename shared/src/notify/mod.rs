@@ -0,0 +1,89 @@
+use std::error::Error;
+
+use libglacierdisk::{alert::AlertEvent, snapshot::DiskSnapshot};
+use serde::Deserialize;
+
+pub mod command;
+pub mod desktop;
+pub mod email;
+pub mod log;
+pub mod webhook;
+
+/// Somewhere to send alerts
+pub trait Notifier: Send {
+  /// Short name used in logs
+  fn name(&self) -> &str;
+
+  /// Send an alert event. `snapshot` is the reading that triggered it.
+  fn notify(&mut self, event: &AlertEvent, snapshot: &DiskSnapshot) -> Result<(), Box<dyn Error>>;
+
+  /// Send anything that has been held back, e.g. for a digest. Called regularly, after every poll.
+  fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+    Ok(())
+  }
+}
+
+/// A notifier as written in a config file, as a `[[notify]]` table with a `type` key
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NotifierConfig {
+  /// Print to stdout (or the journal, when run as a service)
+  Log,
+  /// Show desktop notifications. A service running as root needs to be told whose desktop to use, by `user` name or
+  /// bus `address`. Otherwise the current session bus is used.
+  Desktop {
+    #[serde(default)]
+    user: Option<String>,
+    #[serde(default)]
+    address: Option<String>,
+    /// Also notify when alerts clear
+    #[serde(default = "default_true")]
+    show_cleared: bool,
+  },
+  /// Send emails over SMTP
+  Email(email::EmailConfig),
+  /// POST to a URL
+  Webhook(webhook::WebhookConfig),
+  /// Run a program for every event, with the details in `GLACIERDISK_*` environment variables
+  Command {
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+  },
+}
+
+impl NotifierConfig {
+  pub fn build(&self) -> Result<Box<dyn Notifier>, Box<dyn Error>> {
+    Ok(match self {
+      NotifierConfig::Log => Box::new(log::LogNotifier),
+      NotifierConfig::Desktop {
+        user,
+        address,
+        show_cleared,
+      } => {
+        let mut notifier = match (user, address) {
+          (_, Some(address)) => desktop::DesktopNotifier::with_address(address)?,
+          (Some(user), None) => {
+            let uid = uzers::get_user_by_name(user)
+              .ok_or(format!("Unknown user: {user}"))?
+              .uid();
+            desktop::DesktopNotifier::for_user(uid)?
+          }
+          (None, None) => desktop::DesktopNotifier::session()?,
+        };
+
+        notifier.show_cleared = *show_cleared;
+        Box::new(notifier)
+      }
+      NotifierConfig::Email(config) => Box::new(email::EmailNotifier::new(config.clone())?),
+      NotifierConfig::Webhook(config) => Box::new(webhook::WebhookNotifier::new(config.clone())?),
+      NotifierConfig::Command { command, args } => {
+        Box::new(command::CommandNotifier::new(command, args.clone()))
+      }
+    })
+  }
+}
+
+fn default_true() -> bool {
+  true
+}
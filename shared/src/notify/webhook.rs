@@ -0,0 +1,343 @@
+use std::{collections::HashMap, error::Error, thread, time::Duration};
+
+use hmac::{Hmac, Mac};
+use libglacierdisk::{
+  alert::{deserialize_duration, Alert, AlertEvent, Severity},
+  snapshot::DiskSnapshot,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+
+use super::Notifier;
+
+/// Header carrying `sha256=<hex HMAC of the body>` when a secret is set
+pub const SIGNATURE_HEADER: &str = "X-GlacierDisk-Signature";
+
+/// What to send
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+  /// The full [`WebhookPayload`]
+  #[default]
+  Json,
+  /// `{"text": ...}`
+  Slack,
+  /// `{"content": ...}`
+  Discord,
+  /// `{"msgtype": "m.text", "body": ...}`
+  Matrix,
+  /// A plain text body with `Title`, `Priority` and `Tags` headers
+  Ntfy,
+  /// The `template` setting, with placeholders filled in
+  Template,
+}
+
+/// Settings for [`WebhookNotifier`], written as a `type = "webhook"` notifier
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct WebhookConfig {
+  pub url: String,
+  #[serde(default)]
+  pub format: WebhookFormat,
+  /// Body for the `template` format. `{{rule}}`, `{{severity}}`, `{{event}}`, `{{disk}}`, `{{path}}`, `{{model}}`,
+  /// `{{value}}`, `{{message}}` and `{{text}}` are replaced with JSON-escaped strings, and `{{snapshot}}` with the
+  /// snapshot as JSON.
+  #[serde(default)]
+  pub template: Option<String>,
+  /// Content type for the `template` format
+  #[serde(default = "default_content_type")]
+  pub content_type: String,
+  /// Extra headers, e.g. for authentication
+  #[serde(default)]
+  pub headers: HashMap<String, String>,
+  /// Sign the body with HMAC-SHA256 using this secret, in the [`SIGNATURE_HEADER`] header
+  #[serde(default)]
+  pub secret: Option<String>,
+  /// How many times to retry after a failure. Only server errors and connection failures are retried.
+  #[serde(default = "default_retries")]
+  pub retries: u32,
+  /// Delay before the first retry, doubling after each one
+  #[serde(default = "default_backoff", deserialize_with = "deserialize_duration")]
+  pub backoff: Option<Duration>,
+  #[serde(default = "default_timeout", deserialize_with = "deserialize_duration")]
+  pub timeout: Option<Duration>,
+}
+
+/// The body sent with the `json` format
+#[derive(Serialize, Clone, Debug)]
+pub struct WebhookPayload<'a> {
+  /// `"fired"` or `"cleared"`
+  pub event: &'static str,
+  #[serde(flatten)]
+  pub alert: &'a Alert,
+  pub snapshot: &'a DiskSnapshot,
+}
+
+/// A built request, before signing
+#[derive(Clone, PartialEq, Debug)]
+pub struct WebhookRequest {
+  pub body: String,
+  pub headers: Vec<(String, String)>,
+}
+
+/// POSTs alerts to a URL
+pub struct WebhookNotifier {
+  pub config: WebhookConfig,
+  agent: ureq::Agent,
+  /// Just the host, since Slack, Discord and Matrix URLs are credentials themselves
+  name: String,
+}
+
+impl WebhookNotifier {
+  pub fn new(config: WebhookConfig) -> Result<Self, Box<dyn Error>> {
+    if config.format == WebhookFormat::Template && config.template.is_none() {
+      return Err("Webhook format is template, but no template is set".into());
+    }
+
+    let mut agent = ureq::AgentBuilder::new();
+    if let Some(timeout) = config.timeout {
+      agent = agent.timeout(timeout);
+    }
+
+    Ok(Self {
+      agent: agent.build(),
+      name: format!("webhook to {}", host(&config.url)),
+      config,
+    })
+  }
+
+  /// Build the body and headers for an event
+  pub fn request(
+    &self,
+    event: &AlertEvent,
+    snapshot: &DiskSnapshot,
+  ) -> Result<WebhookRequest, Box<dyn Error>> {
+    let alert = event.alert();
+    let kind = match event {
+      AlertEvent::Fired(_) => "fired",
+      AlertEvent::Cleared(_) => "cleared",
+    };
+    let text = match &snapshot.model {
+      Some(model) => format!("{event} ({model})"),
+      None => event.to_string(),
+    };
+    let json_type = ("Content-Type".to_string(), "application/json".to_string());
+
+    let (body, headers) = match self.config.format {
+      WebhookFormat::Json => {
+        let payload = WebhookPayload {
+          event: kind,
+          alert,
+          snapshot,
+        };
+        (serde_json::to_string(&payload)?, vec![json_type])
+      }
+      WebhookFormat::Slack => (json!({ "text": text }).to_string(), vec![json_type]),
+      WebhookFormat::Discord => (json!({ "content": text }).to_string(), vec![json_type]),
+      WebhookFormat::Matrix => (
+        json!({ "msgtype": "m.text", "body": text }).to_string(),
+        vec![json_type],
+      ),
+      WebhookFormat::Ntfy => {
+        let (title, priority) = match event {
+          AlertEvent::Fired(_) => (
+            format!("{}: {}", alert.severity, alert.rule),
+            match alert.severity {
+              Severity::Info => "default",
+              Severity::Warning => "high",
+              Severity::Critical => "urgent",
+            },
+          ),
+          AlertEvent::Cleared(_) => (format!("Resolved: {}", alert.rule), "low"),
+        };
+
+        (
+          text,
+          vec![
+            ("Title".to_string(), title),
+            ("Priority".to_string(), priority.to_string()),
+            ("Tags".to_string(), "floppy_disk".to_string()),
+          ],
+        )
+      }
+      WebhookFormat::Template => {
+        let template = self.config.template.as_deref().unwrap_or_default();
+        let escape = |s: &str| {
+          let quoted = serde_json::to_string(s).unwrap_or_default();
+          quoted[1..quoted.len() - 1].to_string()
+        };
+
+        let values = [
+          ("rule", escape(&alert.rule)),
+          ("severity", escape(&alert.severity.to_string())),
+          ("event", kind.to_string()),
+          ("disk", escape(&alert.disk)),
+          ("path", escape(&alert.path.display().to_string())),
+          (
+            "model",
+            escape(snapshot.model.as_deref().unwrap_or_default()),
+          ),
+          ("value", alert.value.to_string()),
+          ("message", escape(&alert.message)),
+          ("text", escape(&text)),
+          ("snapshot", serde_json::to_string(snapshot)?),
+        ];
+
+        (
+          fill(template, &values),
+          vec![("Content-Type".to_string(), self.config.content_type.clone())],
+        )
+      }
+    };
+
+    Ok(WebhookRequest { body, headers })
+  }
+
+  /// Describe an error without the URL, which ureq includes in its messages
+  fn redact(&self, error: &ureq::Error) -> String {
+    let message = match error {
+      ureq::Error::Status(status, _) => format!("Server returned HTTP {status}"),
+      ureq::Error::Transport(transport) => {
+        let mut message = transport.kind().to_string();
+        if let Some(detail) = transport.message() {
+          message.push_str(&format!(": {detail}"));
+        }
+        if let Some(source) = std::error::Error::source(transport) {
+          message.push_str(&format!(" ({source})"));
+        }
+        message
+      }
+    };
+
+    message.replace(&self.config.url, &self.name)
+  }
+
+  fn post(&self, webhook: &WebhookRequest) -> Result<(), Box<ureq::Error>> {
+    let mut request = self.agent.post(&self.config.url);
+
+    for (name, value) in &webhook.headers {
+      request = request.set(name, value);
+    }
+
+    // Configured headers go last, so they can override the defaults
+    for (name, value) in &self.config.headers {
+      request = request.set(name, value);
+    }
+
+    if let Some(secret) = &self.config.secret {
+      request = request.set(
+        SIGNATURE_HEADER,
+        &format!("sha256={}", sign(secret, &webhook.body)),
+      );
+    }
+
+    request
+      .send_string(&webhook.body)
+      .map(|_| ())
+      .map_err(Box::new)
+  }
+}
+
+impl Notifier for WebhookNotifier {
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn notify(&mut self, event: &AlertEvent, snapshot: &DiskSnapshot) -> Result<(), Box<dyn Error>> {
+    let request = self.request(event, snapshot)?;
+    let mut delay = self.config.backoff.unwrap_or_default();
+    let mut attempt = 0;
+
+    loop {
+      let error = match self.post(&request) {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+      };
+
+      // Client errors won't go away by trying again
+      let retryable = match error.as_ref() {
+        ureq::Error::Status(status, _) => *status >= 500 || *status == 429,
+        ureq::Error::Transport(_) => true,
+      };
+
+      if !retryable || attempt >= self.config.retries {
+        return Err(self.redact(&error).into());
+      }
+
+      attempt += 1;
+      thread::sleep(delay);
+      delay *= 2;
+    }
+  }
+}
+
+/// The host (and port) of a URL, without any credentials in it
+fn host(url: &str) -> &str {
+  let authority = url.split_once("://").map_or(url, |(_, rest)| rest);
+  let authority = authority.split(['/', '?', '#']).next().unwrap_or_default();
+
+  authority.rsplit_once('@').map_or(authority, |(_, host)| host)
+}
+
+/// Replace each `{{key}}` in a template with its value. This is a single pass, so anything that looks like a placeholder
+/// inside a value (e.g. in an alert message) is left as it is.
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+  let mut body = String::new();
+  let mut rest = template;
+
+  while let Some(start) = rest.find("{{") {
+    body.push_str(&rest[..start]);
+    rest = &rest[start..];
+
+    let placeholder = rest[2..].find("}}").and_then(|end| {
+      let key = &rest[2..end + 2];
+      let (_, value) = values.iter().find(|(k, _)| *k == key)?;
+      Some((value, end + 4))
+    });
+
+    match placeholder {
+      Some((value, len)) => {
+        body.push_str(value);
+        rest = &rest[len..];
+      }
+      // Not a placeholder, but the next brace might start one, e.g. `{{{rule}}}`
+      None => {
+        body.push('{');
+        rest = &rest[1..];
+      }
+    }
+  }
+
+  body.push_str(rest);
+  body
+}
+
+/// Hex encoded HMAC-SHA256 of `body`
+pub fn sign(secret: impl AsRef<[u8]>, body: impl AsRef<[u8]>) -> String {
+  // HMAC accepts keys of any length
+  let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_ref()).expect("HMAC key");
+  mac.update(body.as_ref());
+
+  mac
+    .finalize()
+    .into_bytes()
+    .iter()
+    .map(|b| format!("{b:02x}"))
+    .collect()
+}
+
+fn default_content_type() -> String {
+  "application/json".to_string()
+}
+
+fn default_retries() -> u32 {
+  3
+}
+
+fn default_backoff() -> Option<Duration> {
+  Some(Duration::from_secs(1))
+}
+
+fn default_timeout() -> Option<Duration> {
+  Some(Duration::from_secs(10))
+}
@@ -0,0 +1,214 @@
+//! Sends alerts through [`DesktopNotifier`] to a stub notification server on a private bus, so nothing shows up on
+//! the real desktop. These need `dbus-daemon`, so run them with `cargo test --features monitor -- --ignored`.
+
+use std::{
+  collections::HashMap,
+  io::{BufRead, BufReader},
+  path::PathBuf,
+  process::{Child, Command, Stdio},
+  sync::{Arc, Mutex},
+};
+
+use libglacierdisk::{
+  alert::{Alert, AlertEvent, Severity},
+  snapshot::DiskSnapshot,
+};
+use shared::notify::{desktop::DesktopNotifier, Notifier};
+use zbus::{blocking::Connection, zvariant::OwnedValue};
+
+#[derive(Debug)]
+struct Notification {
+  replaces_id: u32,
+  summary: String,
+  body: String,
+  urgency: u8,
+}
+
+struct StubServer {
+  received: Arc<Mutex<Vec<Notification>>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl StubServer {
+  #[allow(clippy::too_many_arguments)]
+  fn notify(
+    &mut self,
+    _app_name: String,
+    replaces_id: u32,
+    _app_icon: String,
+    summary: String,
+    body: String,
+    _actions: Vec<String>,
+    hints: HashMap<String, OwnedValue>,
+    _expire_timeout: i32,
+  ) -> u32 {
+    let urgency = hints
+      .get("urgency")
+      .and_then(|value| u8::try_from(value).ok())
+      .unwrap_or_default();
+
+    let mut received = self.received.lock().unwrap();
+    received.push(Notification {
+      replaces_id,
+      summary,
+      body,
+      urgency,
+    });
+
+    received.len() as u32
+  }
+}
+
+/// A private session bus with a stub notification server on it. The bus is killed when dropped.
+struct Desktop {
+  bus: Child,
+  address: String,
+  received: Arc<Mutex<Vec<Notification>>>,
+  _server: Connection,
+}
+
+impl Desktop {
+  fn start() -> Self {
+    let mut bus = Command::new("dbus-daemon")
+      .args(["--session", "--nofork", "--print-address"])
+      .stdout(Stdio::piped())
+      .spawn()
+      .expect("Failed to start dbus-daemon");
+
+    let mut address = String::new();
+    BufReader::new(bus.stdout.take().unwrap())
+      .read_line(&mut address)
+      .expect("Failed to read bus address");
+    let address = address.trim().to_string();
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let server = zbus::blocking::connection::Builder::address(address.as_str())
+      .unwrap()
+      .name("org.freedesktop.Notifications")
+      .unwrap()
+      .serve_at(
+        "/org/freedesktop/Notifications",
+        StubServer {
+          received: received.clone(),
+        },
+      )
+      .unwrap()
+      .build()
+      .expect("Failed to start stub notification server");
+
+    Self {
+      bus,
+      address,
+      received,
+      _server: server,
+    }
+  }
+
+  fn notifier(&self) -> DesktopNotifier {
+    DesktopNotifier::with_address(&self.address).expect("Failed to connect")
+  }
+}
+
+impl Drop for Desktop {
+  fn drop(&mut self) {
+    self.bus.kill().ok();
+    self.bus.wait().ok();
+  }
+}
+
+fn snapshot() -> DiskSnapshot {
+  DiskSnapshot {
+    id: "wwn-0x5000c500a1b2c3d4".into(),
+    path: PathBuf::from("/dev/sda"),
+    model: Some("Samsung SSD 860 EVO 500GB".into()),
+    ..Default::default()
+  }
+}
+
+fn alert(rule: &str, severity: Severity) -> Alert {
+  Alert {
+    rule: rule.into(),
+    severity,
+    disk: "wwn-0x5000c500a1b2c3d4".into(),
+    path: PathBuf::from("/dev/sda"),
+    value: 1.,
+    message: "health_status changed from Good to Caution".into(),
+    timestamp: 0,
+  }
+}
+
+#[test]
+#[ignore = "needs dbus-daemon"]
+fn fired_and_cleared() {
+  let desktop = Desktop::start();
+  let mut notifier = desktop.notifier();
+  let alert = alert("health", Severity::Warning);
+
+  notifier
+    .notify(&AlertEvent::Fired(alert.clone()), &snapshot())
+    .unwrap();
+  notifier
+    .notify(&AlertEvent::Cleared(alert), &snapshot())
+    .unwrap();
+
+  let received = desktop.received.lock().unwrap();
+  assert_eq!(received.len(), 2);
+  assert_eq!(received[0].summary, "Warning: Samsung SSD 860 EVO 500GB");
+  assert!(received[0].body.contains("/dev/sda"));
+  assert!(received[0].body.contains("Good to Caution"));
+  assert_eq!(received[0].urgency, 1);
+  // The resolved notification replaces the original
+  assert_eq!(received[1].summary, "Resolved: Samsung SSD 860 EVO 500GB");
+  assert_eq!(received[1].replaces_id, 1);
+  assert_eq!(received[1].urgency, 0);
+}
+
+#[test]
+#[ignore = "needs dbus-daemon"]
+fn urgency() {
+  let desktop = Desktop::start();
+  let mut notifier = desktop.notifier();
+
+  for (rule, severity) in [
+    ("info", Severity::Info),
+    ("warning", Severity::Warning),
+    ("critical", Severity::Critical),
+  ] {
+    notifier
+      .notify(&AlertEvent::Fired(alert(rule, severity)), &snapshot())
+      .unwrap();
+  }
+
+  let received = desktop.received.lock().unwrap();
+  let urgencies = received.iter().map(|n| n.urgency).collect::<Vec<u8>>();
+  assert_eq!(urgencies, vec![0, 1, 2]);
+  assert!(received.iter().all(|n| n.replaces_id == 0));
+}
+
+#[test]
+#[ignore = "needs dbus-daemon"]
+fn cleared_quietly() {
+  let desktop = Desktop::start();
+  let mut notifier = desktop.notifier();
+  notifier.show_cleared = false;
+
+  // Never shown, so there's nothing to resolve
+  notifier
+    .notify(
+      &AlertEvent::Cleared(alert("temperature", Severity::Warning)),
+      &snapshot(),
+    )
+    .unwrap();
+
+  let alert = alert("health", Severity::Critical);
+  notifier
+    .notify(&AlertEvent::Fired(alert.clone()), &snapshot())
+    .unwrap();
+  notifier
+    .notify(&AlertEvent::Cleared(alert), &snapshot())
+    .unwrap();
+
+  let received = desktop.received.lock().unwrap();
+  assert_eq!(received.len(), 1);
+  assert_eq!(received[0].summary, "Critical: Samsung SSD 860 EVO 500GB");
+}
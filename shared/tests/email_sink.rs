@@ -0,0 +1,244 @@
+//! Sends alerts through [`EmailNotifier`] to a minimal SMTP sink on localhost
+
+use std::{
+  io::{BufRead, BufReader, Write},
+  net::TcpListener,
+  path::PathBuf,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+  thread,
+  time::Duration,
+};
+
+use libglacierdisk::{
+  alert::{Alert, AlertEvent, Severity},
+  snapshot::{DiskSnapshot, SnapshotAttribute},
+};
+use shared::notify::{
+  email::{EmailConfig, EmailNotifier, Security},
+  Notifier,
+};
+
+/// Accepts mail forever, storing each message's data. While `failing` is set, every message is rejected.
+struct Sink {
+  port: u16,
+  received: Arc<Mutex<Vec<String>>>,
+  failing: Arc<AtomicBool>,
+}
+
+impl Sink {
+  fn start() -> Self {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+    let port = listener.local_addr().unwrap().port();
+    let received = Arc::new(Mutex::new(vec![]));
+    let failing = Arc::new(AtomicBool::new(false));
+
+    let (messages, fail) = (received.clone(), failing.clone());
+    thread::spawn(move || {
+      for stream in listener.incoming() {
+        let mut stream = stream.unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut data: Option<String> = None;
+
+        stream.write_all(b"220 localhost\r\n").unwrap();
+
+        loop {
+          let mut line = String::new();
+          if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+          }
+
+          if let Some(message) = data.as_mut() {
+            if line == ".\r\n" {
+              messages.lock().unwrap().push(data.take().unwrap());
+              stream.write_all(b"250 OK\r\n").unwrap();
+            } else {
+              message.push_str(&line);
+            }
+            continue;
+          }
+
+          let command = line.get(..4).unwrap_or_default().to_ascii_uppercase();
+          let reply: &[u8] = match command.as_str() {
+            "DATA" if fail.load(Ordering::SeqCst) => b"554 Rejected\r\n",
+            "DATA" => {
+              data = Some(String::new());
+              b"354 Go ahead\r\n"
+            }
+            "QUIT" => {
+              stream.write_all(b"221 Bye\r\n").unwrap();
+              break;
+            }
+            _ => b"250 OK\r\n",
+          };
+          stream.write_all(reply).unwrap();
+        }
+      }
+    });
+
+    Self {
+      port,
+      received,
+      failing,
+    }
+  }
+
+  fn config(&self) -> EmailConfig {
+    EmailConfig {
+      host: "127.0.0.1".into(),
+      port: Some(self.port),
+      security: Security::None,
+      username: None,
+      password: None,
+      password_file: None,
+      from: "GlacierDisk <disks@example.com>".into(),
+      to: vec!["ops@example.com".into()],
+      rate_limit: None,
+      digest: None,
+    }
+  }
+
+  fn received(&self) -> Vec<String> {
+    self.received.lock().unwrap().clone()
+  }
+
+  fn fail(&self, failing: bool) {
+    self.failing.store(failing, Ordering::SeqCst);
+  }
+}
+
+fn snapshot() -> DiskSnapshot {
+  DiskSnapshot {
+    id: "wwn-0x5000c500a1b2c3d4".into(),
+    path: PathBuf::from("/dev/sda"),
+    model: Some("ST4000DM004".into()),
+    attributes: vec![SnapshotAttribute {
+      id: 197,
+      name: "current-pending-sector".into(),
+      current: 100,
+      worst: 100,
+      threshold: 0,
+      raw: 8,
+      ..Default::default()
+    }],
+    ..Default::default()
+  }
+}
+
+fn event(rule: &str) -> AlertEvent {
+  AlertEvent::Fired(Alert {
+    rule: rule.into(),
+    severity: Severity::Critical,
+    disk: "wwn-0x5000c500a1b2c3d4".into(),
+    path: PathBuf::from("/dev/sda"),
+    value: 8.,
+    message: "attribute.197.raw is 8 (increased by more than 0)".into(),
+    timestamp: 0,
+  })
+}
+
+#[test]
+fn rate_limit() {
+  let sink = Sink::start();
+  let mut notifier = EmailNotifier::new(EmailConfig {
+    rate_limit: Some(2),
+    ..sink.config()
+  })
+  .unwrap();
+
+  // Only two emails an hour, so the third alert is held back
+  for rule in ["first", "second", "third"] {
+    notifier.notify(&event(rule), &snapshot()).unwrap();
+  }
+  notifier.flush().unwrap();
+
+  let received = sink.received();
+  assert_eq!(received.len(), 2);
+  assert!(received[0].contains("Subject: [GlacierDisk] Critical: first on ST4000DM004"));
+  assert!(received[0].contains("Health: Caution"));
+  assert!(received[0].contains("current-pending-sector"));
+  assert!(received[1].contains("second"));
+}
+
+#[test]
+fn digest() {
+  let sink = Sink::start();
+  let mut notifier = EmailNotifier::new(EmailConfig {
+    digest: Some(Duration::ZERO),
+    ..sink.config()
+  })
+  .unwrap();
+
+  // Nothing goes out until the digest does, and then all at once
+  for rule in ["first", "second"] {
+    notifier.notify(&event(rule), &snapshot()).unwrap();
+  }
+  assert!(sink.received().is_empty());
+  notifier.flush().unwrap();
+
+  let received = sink.received();
+  assert_eq!(received.len(), 1);
+  assert!(received[0].contains("Subject: [GlacierDisk] 2 disk alerts"));
+  assert!(received[0].contains("first") && received[0].contains("second"));
+
+  // And the queue is empty afterwards
+  notifier.flush().unwrap();
+  assert_eq!(sink.received().len(), 1);
+}
+
+#[test]
+fn digest_not_due() {
+  let sink = Sink::start();
+  let mut notifier = EmailNotifier::new(EmailConfig {
+    digest: Some(Duration::from_secs(60 * 60)),
+    ..sink.config()
+  })
+  .unwrap();
+
+  notifier.notify(&event("first"), &snapshot()).unwrap();
+  notifier.flush().unwrap();
+  assert!(sink.received().is_empty());
+}
+
+#[test]
+fn digest_retried_after_failure() {
+  let sink = Sink::start();
+  let mut notifier = EmailNotifier::new(EmailConfig {
+    digest: Some(Duration::from_millis(200)),
+    ..sink.config()
+  })
+  .unwrap();
+
+  notifier.notify(&event("first"), &snapshot()).unwrap();
+  thread::sleep(Duration::from_millis(250));
+
+  sink.fail(true);
+  assert!(notifier.flush().is_err());
+  assert!(sink.received().is_empty());
+
+  // Straight away, not after another interval
+  sink.fail(false);
+  notifier.flush().unwrap();
+
+  let received = sink.received();
+  assert_eq!(received.len(), 1);
+  assert!(received[0].contains("Critical: first on ST4000DM004"));
+}
+
+#[test]
+fn failed_alert_is_retried() {
+  let sink = Sink::start();
+  let mut notifier = EmailNotifier::new(sink.config()).unwrap();
+
+  sink.fail(true);
+  assert!(notifier.notify(&event("first"), &snapshot()).is_err());
+
+  sink.fail(false);
+  notifier.flush().unwrap();
+
+  let received = sink.received();
+  assert_eq!(received.len(), 1);
+  assert!(received[0].contains("Critical: first on ST4000DM004"));
+}
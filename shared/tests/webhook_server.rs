@@ -0,0 +1,258 @@
+//! Sends alerts through [`WebhookNotifier`] to a minimal HTTP server on localhost
+
+use std::{
+  io::{BufRead, BufReader, Read, Write},
+  net::TcpListener,
+  path::PathBuf,
+  sync::{Arc, Mutex},
+  thread,
+  time::Duration,
+};
+
+use libglacierdisk::{
+  alert::{Alert, AlertEvent, Severity},
+  snapshot::DiskSnapshot,
+};
+use shared::notify::{
+  webhook::{self, WebhookConfig, WebhookFormat, WebhookNotifier, SIGNATURE_HEADER},
+  Notifier,
+};
+
+struct Request {
+  headers: Vec<(String, String)>,
+  body: String,
+}
+
+impl Request {
+  fn header(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(n, _)| n.eq_ignore_ascii_case(name))
+      .map(|(_, v)| v.as_str())
+  }
+}
+
+/// Serve forever, failing the first `failures` requests with a 503
+fn server(received: Arc<Mutex<Vec<Request>>>, failures: usize) -> u16 {
+  let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+  let port = listener.local_addr().unwrap().port();
+
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let mut stream = stream.unwrap();
+      let mut reader = BufReader::new(stream.try_clone().unwrap());
+      let mut headers = vec![];
+
+      loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+
+        if line.is_empty() {
+          break;
+        }
+
+        if let Some((name, value)) = line.split_once(": ") {
+          headers.push((name.to_string(), value.to_string()));
+        }
+      }
+
+      let mut request = Request {
+        headers,
+        body: String::new(),
+      };
+      let length = request
+        .header("Content-Length")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or_default();
+      let mut body = vec![0; length];
+      reader.read_exact(&mut body).unwrap();
+      request.body = String::from_utf8(body).unwrap();
+
+      let mut received = received.lock().unwrap();
+      received.push(request);
+
+      let status = if received.len() <= failures {
+        "503 Service Unavailable"
+      } else {
+        "200 OK"
+      };
+      write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+      )
+      .unwrap();
+    }
+  });
+
+  port
+}
+
+fn config(port: u16) -> WebhookConfig {
+  WebhookConfig {
+    url: format!("http://127.0.0.1:{port}/hook"),
+    format: WebhookFormat::Json,
+    template: None,
+    content_type: "application/json".into(),
+    headers: [("Authorization".to_string(), "Bearer token".to_string())].into(),
+    secret: Some("hunter2".into()),
+    retries: 3,
+    backoff: Some(Duration::from_millis(10)),
+    timeout: Some(Duration::from_secs(5)),
+  }
+}
+
+fn snapshot() -> DiskSnapshot {
+  DiskSnapshot {
+    id: "wwn-0x5000c500a1b2c3d4".into(),
+    path: PathBuf::from("/dev/sda"),
+    model: Some("ST4000DM004".into()),
+    temperature: Some(58.),
+    ..Default::default()
+  }
+}
+
+fn event(message: &str) -> AlertEvent {
+  AlertEvent::Fired(Alert {
+    rule: "hot".into(),
+    severity: Severity::Warning,
+    disk: "wwn-0x5000c500a1b2c3d4".into(),
+    path: PathBuf::from("/dev/sda"),
+    value: 58.,
+    message: message.into(),
+    timestamp: 0,
+  })
+}
+
+/// A notifier that only builds requests, never sending them
+fn formatted(format: WebhookFormat, template: Option<&str>) -> WebhookNotifier {
+  WebhookNotifier::new(WebhookConfig {
+    format,
+    template: template.map(String::from),
+    ..config(0)
+  })
+  .unwrap()
+}
+
+#[test]
+fn retries_and_signs() {
+  let received = Arc::new(Mutex::new(vec![]));
+  let port = server(received.clone(), 2);
+
+  // Fails twice, then succeeds on the second retry
+  let mut notifier = WebhookNotifier::new(config(port)).unwrap();
+  notifier
+    .notify(&event("temperature is 58 (> 55)"), &snapshot())
+    .unwrap();
+
+  let received = received.lock().unwrap();
+  assert_eq!(received.len(), 3);
+
+  let request = &received[2];
+  let payload: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+  assert_eq!(payload["event"], "fired");
+  assert_eq!(payload["rule"], "hot");
+  assert_eq!(payload["disk"], "wwn-0x5000c500a1b2c3d4");
+  assert_eq!(payload["value"], 58.);
+  assert_eq!(payload["snapshot"]["temperature"], 58.);
+  assert_eq!(request.header("Authorization"), Some("Bearer token"));
+  assert_eq!(
+    request.header(SIGNATURE_HEADER),
+    Some(format!("sha256={}", webhook::sign("hunter2", &request.body)).as_str())
+  );
+}
+
+#[test]
+fn gives_up() {
+  let received = Arc::new(Mutex::new(vec![]));
+  let port = server(received.clone(), usize::MAX);
+
+  let mut notifier = WebhookNotifier::new(WebhookConfig {
+    retries: 1,
+    ..config(port)
+  })
+  .unwrap();
+  let error = notifier
+    .notify(&event("temperature is 58 (> 55)"), &snapshot())
+    .unwrap_err();
+
+  assert_eq!(error.to_string(), "Server returned HTTP 503");
+  assert_eq!(received.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn chat_formats() {
+  let event = event("temperature is 58 (> 55)");
+  let text = "[Warning] hot on /dev/sda: temperature is 58 (> 55) (ST4000DM004)";
+
+  let body = |format| {
+    formatted(format, None)
+      .request(&event, &snapshot())
+      .unwrap()
+      .body
+  };
+  assert_eq!(
+    body(WebhookFormat::Slack),
+    format!(r#"{{"text":"{text}"}}"#)
+  );
+  assert_eq!(
+    body(WebhookFormat::Discord),
+    format!(r#"{{"content":"{text}"}}"#)
+  );
+  assert_eq!(
+    body(WebhookFormat::Matrix),
+    format!(r#"{{"body":"{text}","msgtype":"m.text"}}"#)
+  );
+
+  let ntfy = formatted(WebhookFormat::Ntfy, None)
+    .request(&event, &snapshot())
+    .unwrap();
+  assert_eq!(ntfy.body, text);
+  assert!(ntfy
+    .headers
+    .contains(&("Title".to_string(), "Warning: hot".to_string())));
+  assert!(ntfy
+    .headers
+    .contains(&("Priority".to_string(), "high".to_string())));
+}
+
+#[test]
+fn template() {
+  let notifier = formatted(
+    WebhookFormat::Template,
+    Some(
+      r#"{"title": "{{rule}} on {{model}}", "temp": {{value}}, "disk": {{snapshot}}, "x": "{{unknown}}"}"#,
+    ),
+  );
+  let body = notifier
+    .request(&event("temperature is 58 (> 55)"), &snapshot())
+    .unwrap()
+    .body;
+
+  let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+  assert_eq!(payload["title"], "hot on ST4000DM004");
+  assert_eq!(payload["temp"], 58.);
+  assert_eq!(payload["disk"]["path"], "/dev/sda");
+  assert_eq!(payload["x"], "{{unknown}}");
+}
+
+#[test]
+fn template_values_are_not_expanded() {
+  let notifier = formatted(
+    WebhookFormat::Template,
+    Some(r#"{"message": "{{message}}", "text": "{{text}}", "rule": "{{{rule}}}"}"#),
+  );
+  let body = notifier
+    .request(&event("{{snapshot}} and {{rule}}"), &snapshot())
+    .unwrap()
+    .body;
+
+  let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+  assert_eq!(payload["message"], "{{snapshot}} and {{rule}}");
+  assert_eq!(
+    payload["text"],
+    "[Warning] hot on /dev/sda: {{snapshot}} and {{rule}} (ST4000DM004)"
+  );
+  assert_eq!(payload["rule"], "{hot}");
+}